@@ -0,0 +1,112 @@
+//! Theme lookup cost for the styles of a large file.
+//!
+//! Run with `cargo bench -p tuan --bench theme`.
+
+use std::{collections::HashSet, hint::black_box};
+
+use criterion::{BatchSize, Criterion, criterion_group, criterion_main};
+
+// The editor is a binary crate, so the theme module is compiled in directly.
+#[allow(dead_code)]
+#[path = "../src/theme/mod.rs"]
+mod theme;
+
+use theme::{theme::Theme as _, vscode_theme::VscodeTheme};
+
+const THEME: &str = include_str!("../assets/rose-pine-moon-color-theme.json");
+
+/// Scopes as produced by the tree-sitter highlighter.
+const SCOPES: &[&str] = &[
+    "attribute",
+    "boolean",
+    "comment",
+    "constant",
+    "constant.builtin",
+    "constructor",
+    "embedded",
+    "enum",
+    "enumMember",
+    "escape",
+    "field",
+    "function",
+    "function.builtin",
+    "function.macro",
+    "function.method",
+    "keyword",
+    "label",
+    "macro",
+    "markup.bold",
+    "markup.heading",
+    "markup.italic",
+    "markup.link.url",
+    "number",
+    "operator",
+    "property",
+    "punctuation",
+    "punctuation.bracket",
+    "punctuation.delimiter",
+    "string",
+    "string.escape",
+    "string.regex",
+    "string.special",
+    "tag",
+    "type",
+    "type.builtin",
+    "variable",
+    "variable.builtin",
+    "variable.other.member",
+    "variable.parameter",
+];
+
+/// About as many highlighted ranges as a 20k lines source file.
+const SPANS: usize = 200_000;
+
+fn spans() -> Vec<&'static str> {
+    (0..SPANS).map(|i| SCOPES[(i * 7) % SCOPES.len()]).collect()
+}
+
+fn load_theme() -> VscodeTheme {
+    VscodeTheme::from_json(THEME).unwrap()
+}
+
+fn theme_lookup(c: &mut Criterion) {
+    let spans = spans();
+
+    c.bench_function("load theme", |b| b.iter(|| black_box(load_theme())));
+
+    c.bench_function("get_style, cold cache", |b| {
+        b.iter_batched(
+            load_theme,
+            |theme| {
+                for scope in SCOPES {
+                    black_box(theme.get_style(vec![scope]));
+                }
+            },
+            BatchSize::SmallInput,
+        )
+    });
+
+    let theme = load_theme();
+    c.bench_function("get_style per span, warm cache", |b| {
+        b.iter(|| {
+            for scope in &spans {
+                black_box(theme.get_style(vec![scope]));
+            }
+        })
+    });
+
+    c.bench_function("get_styles per distinct scope, warm cache", |b| {
+        b.iter(|| {
+            let scopes = spans
+                .iter()
+                .copied()
+                .collect::<HashSet<_>>()
+                .into_iter()
+                .collect::<Vec<_>>();
+            black_box(theme.get_styles(&scopes))
+        })
+    });
+}
+
+criterion_group!(benches, theme_lookup);
+criterion_main!(benches);
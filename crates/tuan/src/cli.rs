@@ -0,0 +1,171 @@
+use std::path::{Path, PathBuf};
+
+use clap::Parser;
+
+/// Command-line arguments of the `tuan` binary.
+///
+/// ```text
+/// tuan .
+/// tuan ~/code/project src/main.rs:12:5 Cargo.toml
+/// ```
+#[derive(Debug, Parser)]
+#[command(name = "tuan", version, about = "The Tuan IDE")]
+pub struct Cli {
+    /// Workspace directory to open. Defaults to the current directory.
+    #[arg(value_name = "WORKSPACE")]
+    workspace: Option<PathBuf>,
+
+    /// Files to open, optionally suffixed with `:line` or `:line:column`.
+    #[arg(value_name = "FILE", value_parser = FileArg::parse)]
+    files: Vec<FileArg>,
+
+    /// Directory holding the user configuration (keybindings, settings, ...).
+    #[arg(long, value_name = "DIR")]
+    pub config_dir: Option<PathBuf>,
+
+    /// Log filter, e.g. `info` or `warn,tuan=debug`. Overrides `RUST_LOG`.
+    #[arg(long, value_name = "FILTER")]
+    pub log_level: Option<String>,
+}
+
+/// A file given on the command line, with an optional 1-based position.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileArg {
+    pub path: PathBuf,
+    pub line: Option<usize>,
+    pub column: Option<usize>,
+}
+
+impl FileArg {
+    fn parse(s: &str) -> Result<Self, String> {
+        if s.is_empty() {
+            return Err("empty file path".to_string());
+        }
+
+        let (path, line, column) = split_position(s);
+        Ok(Self {
+            path: absolute_path(Path::new(path)),
+            line,
+            column,
+        })
+    }
+
+    /// The 0-based `(line, column)` where the cursor should be placed.
+    pub fn cursor_position(&self) -> (usize, usize) {
+        (
+            self.line.unwrap_or(1).saturating_sub(1),
+            self.column.unwrap_or(1).saturating_sub(1),
+        )
+    }
+}
+
+impl Cli {
+    /// The workspace root to open.
+    ///
+    /// When the first positional argument points to a file (`tuan src/main.rs:3`),
+    /// the workspace is the directory containing it.
+    pub fn workspace_path(&self) -> PathBuf {
+        match self.workspace_as_file() {
+            Some(file) => file
+                .path
+                .parent()
+                .map(Path::to_path_buf)
+                .unwrap_or_else(|| absolute_path(Path::new("."))),
+            None => {
+                let path = self.workspace.as_deref().unwrap_or(Path::new("."));
+                absolute_path(path)
+            }
+        }
+    }
+
+    /// Every file to open, in the order they were given.
+    pub fn files_to_open(&self) -> Vec<FileArg> {
+        self.workspace_as_file()
+            .into_iter()
+            .chain(self.files.iter().cloned())
+            .collect()
+    }
+
+    fn workspace_as_file(&self) -> Option<FileArg> {
+        let workspace = self.workspace.as_ref()?;
+        if workspace.is_dir() {
+            return None;
+        }
+        let file = FileArg::parse(&workspace.to_string_lossy()).ok()?;
+        (file.path.is_file() || file.line.is_some()).then_some(file)
+    }
+}
+
+/// Splits an optional `:line` or `:line:column` suffix off `s`, e.g.
+/// `src/main.rs:12:5` into `("src/main.rs", Some(12), Some(5))`.
+///
+/// Only numeric suffixes are stripped, so `C:\foo` or `a:b.txt` are kept intact.
+pub fn split_position(s: &str) -> (&str, Option<usize>, Option<usize>) {
+    let mut numbers = Vec::new();
+    let mut rest = s;
+    while numbers.len() < 2 {
+        match rest.rsplit_once(':') {
+            Some((head, tail)) if !head.is_empty() => match tail.parse::<usize>() {
+                Ok(n) => {
+                    numbers.push(n);
+                    rest = head;
+                }
+                Err(_) => break,
+            },
+            _ => break,
+        }
+    }
+    numbers.reverse();
+    (rest, numbers.first().copied(), numbers.get(1).copied())
+}
+
+fn absolute_path(path: &Path) -> PathBuf {
+    path.canonicalize()
+        .or_else(|_| std::path::absolute(path))
+        .unwrap_or_else(|_| path.to_path_buf())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn split_position_strips_numeric_suffixes() {
+        assert_eq!(split_position("src/main.rs"), ("src/main.rs", None, None));
+        assert_eq!(
+            split_position("src/main.rs:12"),
+            ("src/main.rs", Some(12), None)
+        );
+        assert_eq!(
+            split_position("src/main.rs:12:5"),
+            ("src/main.rs", Some(12), Some(5))
+        );
+        // At most a line and a column.
+        assert_eq!(split_position("a.rs:1:2:3"), ("a.rs:1", Some(2), Some(3)));
+    }
+
+    #[test]
+    fn split_position_keeps_other_colons() {
+        assert_eq!(split_position("C:\\foo"), ("C:\\foo", None, None));
+        assert_eq!(split_position("a:b.txt"), ("a:b.txt", None, None));
+        assert_eq!(split_position("a.rs:x:3"), ("a.rs:x", Some(3), None));
+        assert_eq!(split_position(":3"), (":3", None, None));
+        assert_eq!(split_position("a.rs:"), ("a.rs:", None, None));
+    }
+
+    #[test]
+    fn file_arg_parses_an_absolute_path_and_a_position() {
+        let file = FileArg::parse("src/main.rs:12:5").unwrap();
+        assert!(file.path.is_absolute());
+        assert!(file.path.ends_with("src/main.rs"));
+        assert_eq!((file.line, file.column), (Some(12), Some(5)));
+        assert_eq!(file.cursor_position(), (11, 4));
+
+        let file = FileArg::parse("Cargo.toml").unwrap();
+        assert_eq!((file.line, file.column), (None, None));
+        assert_eq!(file.cursor_position(), (0, 0));
+
+        assert_eq!(FileArg::parse("a.rs:0").unwrap().cursor_position(), (0, 0));
+        assert!(FileArg::parse("").is_err());
+    }
+}
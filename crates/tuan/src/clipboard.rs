@@ -0,0 +1,55 @@
+/// A text clipboard the editor copies to and pastes from.
+pub trait Clipboard: Send {
+    fn get_text(&mut self) -> Option<String>;
+    fn set_text(&mut self, text: String);
+}
+
+/// The clipboard of the operating system.
+pub struct SystemClipboard {
+    clipboard: Option<arboard::Clipboard>,
+}
+
+impl SystemClipboard {
+    pub fn new() -> Self {
+        let clipboard = arboard::Clipboard::new()
+            .inspect_err(|err| tracing::error!("Failed to access the system clipboard: {err}"))
+            .ok();
+        Self { clipboard }
+    }
+}
+
+impl Clipboard for SystemClipboard {
+    fn get_text(&mut self) -> Option<String> {
+        self.clipboard
+            .as_mut()?
+            .get_text()
+            .inspect_err(|err| tracing::debug!("Failed to read the clipboard: {err}"))
+            .ok()
+    }
+
+    fn set_text(&mut self, text: String) {
+        if let Some(clipboard) = self.clipboard.as_mut()
+            && let Err(err) = clipboard.set_text(text)
+        {
+            tracing::error!("Failed to write the clipboard: {err}");
+        }
+    }
+}
+
+/// A clipboard living in memory, so clipboard actions can run in tests.
+#[cfg(test)]
+#[derive(Default)]
+pub struct InMemoryClipboard {
+    text: Option<String>,
+}
+
+#[cfg(test)]
+impl Clipboard for InMemoryClipboard {
+    fn get_text(&mut self) -> Option<String> {
+        self.text.clone()
+    }
+
+    fn set_text(&mut self, text: String) {
+        self.text = Some(text);
+    }
+}
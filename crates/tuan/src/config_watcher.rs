@@ -0,0 +1,200 @@
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, Weak},
+};
+
+use crossbeam_channel::{Receiver, Sender};
+use notify::{RecommendedWatcher, RecursiveMode, Watcher as _};
+
+/// Watches configuration files (settings, keybindings, theme) for changes.
+///
+/// The nearest existing directory above each file is watched rather than the
+/// file itself, non-recursively, so that editors saving through a rename, and
+/// files created later, are seen. Until the `.tuan` directory of a workspace
+/// is created, that is the workspace root, whose other events are filtered out.
+#[derive(Clone)]
+pub struct ConfigWatcher {
+    inner: Arc<Mutex<Inner>>,
+    rx: Receiver<HashSet<PathBuf>>,
+}
+
+struct Inner {
+    watcher: Option<RecommendedWatcher>,
+    watched_dirs: HashSet<PathBuf>,
+    watched_files: HashSet<PathBuf>,
+}
+
+impl ConfigWatcher {
+    pub fn new() -> Self {
+        let (events_tx, events_rx) = crossbeam_channel::unbounded();
+        let watcher = notify::recommended_watcher(move |result| {
+            let _ = events_tx.send(result);
+        })
+        .inspect_err(|err| tracing::error!("Failed to start the config watcher: {err}"))
+        .ok();
+
+        let inner = Arc::new(Mutex::new(Inner {
+            watcher,
+            watched_dirs: HashSet::new(),
+            watched_files: HashSet::new(),
+        }));
+
+        // The directories are rewatched from this thread: the watcher cannot
+        // add watches from its own thread, which sends the events.
+        let (tx, rx) = crossbeam_channel::unbounded();
+        std::thread::Builder::new()
+            .name("config-watcher".to_string())
+            .spawn({
+                let inner = Arc::downgrade(&inner);
+                move || Self::forward_changes(&inner, &events_rx, &tx)
+            })
+            .expect("Failed to start the config watcher thread");
+
+        Self { inner, rx }
+    }
+
+    /// Sends the watched files changed by the events, until the watcher is dropped.
+    ///
+    /// The events that come in a burst, as when a file is saved, are sent at once.
+    fn forward_changes(
+        inner: &Weak<Mutex<Inner>>,
+        events_rx: &Receiver<notify::Result<notify::Event>>,
+        tx: &Sender<HashSet<PathBuf>>,
+    ) {
+        while let Ok(result) = events_rx.recv() {
+            let Some(inner) = inner.upgrade() else {
+                break;
+            };
+            let mut inner = inner.lock().unwrap();
+            let mut changed_files = HashSet::new();
+            for result in std::iter::once(result).chain(events_rx.try_iter()) {
+                let event = match result {
+                    Ok(event) if !event.kind.is_access() => event,
+                    Ok(_) => continue,
+                    Err(err) => {
+                        tracing::error!("Config watcher error: {err}");
+                        continue;
+                    }
+                };
+                for path in event.paths.iter().filter_map(|path| Self::normalize(path)) {
+                    if inner.watched_files.contains(&path) {
+                        changed_files.insert(path);
+                    } else if inner
+                        .watched_files
+                        .iter()
+                        .any(|file| file.starts_with(&path))
+                    {
+                        // A directory on the way to a file came or went: the
+                        // file may have been created along with it.
+                        let dirs = inner.rewatch();
+                        changed_files.extend(
+                            inner
+                                .watched_files
+                                .iter()
+                                .filter(|file| file.parent().is_some_and(|dir| dirs.contains(dir)))
+                                .filter(|file| file.exists())
+                                .cloned(),
+                        );
+                    }
+                }
+            }
+            if !changed_files.is_empty() && tx.send(changed_files).is_err() {
+                break;
+            }
+        }
+    }
+
+    /// Watches exactly `files`, replacing the previously watched set.
+    pub fn watch(&self, files: impl IntoIterator<Item = PathBuf>) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.watched_files = files
+            .into_iter()
+            .filter_map(|file| Self::normalize(&file))
+            .collect();
+        inner.rewatch();
+    }
+
+    /// The changed watched files, as the events about them come in.
+    pub fn changed_files(&self) -> Receiver<HashSet<PathBuf>> {
+        self.rx.clone()
+    }
+
+    /// Canonicalizes the nearest existing directory above `path`, since the
+    /// file itself, or the directories up to it, may not exist (yet).
+    ///
+    /// Paths sent by [`Self::changed_files`] are normalized this way.
+    pub fn normalize(path: &Path) -> Option<PathBuf> {
+        path.file_name()?;
+        let (existing, rest) = path.ancestors().skip(1).find_map(|dir| {
+            let existing = dir.canonicalize().ok()?;
+            Some((existing, path.strip_prefix(dir).ok()?))
+        })?;
+        Some(existing.join(rest))
+    }
+}
+
+impl Inner {
+    /// Watches the nearest existing directory above each watched file, and
+    /// only those. Returns the directories newly watched.
+    fn rewatch(&mut self) -> HashSet<PathBuf> {
+        let dirs = self
+            .watched_files
+            .iter()
+            .filter_map(|file| file.ancestors().skip(1).find(|dir| dir.is_dir()))
+            .map(Path::to_path_buf)
+            .collect::<HashSet<_>>();
+
+        let Some(watcher) = &mut self.watcher else {
+            return HashSet::new();
+        };
+        for dir in self.watched_dirs.difference(&dirs) {
+            if let Err(err) = watcher.unwatch(dir) {
+                tracing::debug!("Failed to unwatch {:?}: {err}", dir);
+            }
+        }
+        let added = dirs
+            .difference(&self.watched_dirs)
+            .cloned()
+            .collect::<HashSet<_>>();
+        for dir in &added {
+            if let Err(err) = watcher.watch(dir, RecursiveMode::NonRecursive) {
+                tracing::debug!("Failed to watch {:?}: {err}", dir);
+            }
+        }
+
+        self.watched_dirs = dirs;
+        added
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    #[test]
+    fn sees_a_file_created_in_a_missing_directory() {
+        let root = std::env::temp_dir().join(format!("tuan-config-watcher-{}", std::process::id()));
+        std::fs::create_dir_all(&root).unwrap();
+        let settings = root.join(".tuan/settings.json");
+
+        let watcher = ConfigWatcher::new();
+        watcher.watch([settings.clone()]);
+        let settings = ConfigWatcher::normalize(&settings).unwrap();
+        let changed_files = watcher.changed_files();
+
+        // Other files of the workspace root are left out.
+        std::fs::write(root.join("main.rs"), "").unwrap();
+        std::fs::create_dir(root.join(".tuan")).unwrap();
+        std::fs::write(root.join(".tuan/settings.json"), "{}").unwrap();
+
+        let files = changed_files
+            .recv_timeout(Duration::from_secs(5))
+            .expect("the settings file should be seen");
+        assert_eq!(files, HashSet::from([settings]));
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+}
@@ -1,14 +1,29 @@
-use std::{fmt::Debug, path::PathBuf, sync::Arc};
+use std::{
+    collections::VecDeque,
+    fmt::Debug,
+    ops::Range,
+    path::PathBuf,
+    sync::{Arc, Mutex},
+};
 
+use lapce_xi_rope::Rope;
 use masonry::kurbo::Rect;
-use tuan_core::{buffer::rope_text::RopeText, selection::Selection, syntax::Syntax};
+use tuan_core::{buffer::rope_text::RopeText, editor::EditType, selection::Selection};
+use tuan_rpc::{buffer::BufferId, core::FileChanged, proxy::ProxyRpcHandler};
 
-use super::line;
+use super::{
+    highlight::{self, Highlighter, SyntaxEdits},
+    history::{Change, EditKind, History},
+    line,
+};
 use crate::{
     editor_view::{self, paint::cursor::Cursor},
-    theme::{self, theme::Theme as _},
+    theme,
 };
 
+/// How many changes a document remembers for [`Document::changes_since`].
+const RECENT_CHANGES_LIMIT: usize = 64;
+
 #[derive(Debug, Clone)]
 pub struct RangeStyle {
     pub start: usize,
@@ -16,12 +31,32 @@ pub struct RangeStyle {
     pub style: theme::Style,
 }
 
+/// A change made to the file on disk since it was opened or last saved.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DiskChange {
+    Modified(String),
+    Deleted,
+}
+
 #[derive(Clone)]
 pub struct Document {
     pub(crate) path: PathBuf,
+    pub(crate) buffer_id: BufferId,
     pub(crate) buffer: tuan_core::buffer::Buffer,
+    proxy_rpc: ProxyRpcHandler,
     config: Arc<editor_view::EditorConfig>,
-    styles: Vec<RangeStyle>,
+    /// The styles of the last parse, shifted along the edits made since.
+    styles: Arc<Vec<RangeStyle>>,
+    highlighter: Arc<Mutex<Highlighter>>,
+    /// The edits not yet seen by the highlighter.
+    syntax_edits: SyntaxEdits,
+    /// The last changes applied to the buffer, with the revisions before and after them.
+    recent_changes: Arc<VecDeque<(u64, u64, Arc<Vec<Change>>)>>,
+    history: History,
+    /// The buffer revision last written to (or read from) the disk.
+    saved_rev: u64,
+    /// Set when the file changed on disk, until the user reloads or overwrites it.
+    pub disk_change: Option<DiskChange>,
 }
 
 impl Debug for Document {
@@ -31,6 +66,8 @@ impl Debug for Document {
             .field("buffer", &self.buffer.text().len())
             .field("config", &"&self.config")
             .field("styles", &self.styles.len())
+            .field("saved_rev", &self.saved_rev)
+            .field("disk_change", &self.disk_change.is_some())
             .finish()
     }
 }
@@ -38,18 +75,86 @@ impl Debug for Document {
 impl Document {
     pub fn new(
         path: PathBuf,
+        buffer_id: BufferId,
         content: String,
         read_only: bool,
+        proxy_rpc: ProxyRpcHandler,
         config: Arc<editor_view::EditorConfig>,
     ) -> Self {
+        let buffer = tuan_core::buffer::Buffer::new(content);
         Self {
+            highlighter: Arc::new(Mutex::new(Highlighter::new(&path))),
             path,
-            buffer: tuan_core::buffer::Buffer::new(content),
+            buffer_id,
+            saved_rev: buffer.rev(),
+            buffer,
+            proxy_rpc,
             config,
-            styles: Vec::new(),
+            styles: Arc::new(Vec::new()),
+            syntax_edits: SyntaxEdits::default(),
+            recent_changes: Arc::default(),
+            history: History::default(),
+            disk_change: None,
         }
     }
 
+    /// Whether the buffer has changes not written to the disk.
+    pub fn is_dirty(&self) -> bool {
+        self.buffer.rev() != self.saved_rev
+    }
+
+    /// Marks revision `rev` as written to the disk, at `path` in case of a save-as.
+    pub(crate) fn mark_saved(&mut self, path: PathBuf, rev: u64) {
+        self.path = path;
+        self.saved_rev = rev;
+        self.disk_change = None;
+    }
+
+    pub(crate) fn set_disk_change(&mut self, change: FileChanged) {
+        self.disk_change = Some(match change {
+            FileChanged::Change(content) => DiskChange::Modified(content),
+            FileChanged::Delete => DiskChange::Deleted,
+        });
+    }
+
+    /// Replaces the buffer by the content found on disk, as an undoable edit.
+    ///
+    /// Returns the cursors clamped to the new content, or `None` when there is
+    /// nothing to reload, e.g. the file was deleted.
+    pub(crate) fn reload_from_disk(
+        &mut self,
+        cursors: Vec<(usize, usize)>,
+    ) -> Option<Vec<(usize, usize)>> {
+        let Some(DiskChange::Modified(content)) = self.disk_change.take() else {
+            return None;
+        };
+        self.edit(
+            vec![(0..self.buffer.len(), content)],
+            EditKind::Other,
+            cursors.clone(),
+        );
+        self.history.break_group();
+        self.saved_rev = self.buffer.rev();
+
+        Some(
+            cursors
+                .into_iter()
+                .map(|position| self.clamp_position(position))
+                .collect(),
+        )
+    }
+
+    /// The nearest position of the text to `(line, column)`, e.g. when it is
+    /// past the end of its line or of the text.
+    pub fn clamp_position(&self, (line, column): (usize, usize)) -> (usize, usize) {
+        let line = line.min(self.buffer.last_line());
+        (line, column.min(self.buffer.line_end_col(line, true)))
+    }
+
+    pub(crate) fn set_config(&mut self, config: Arc<editor_view::EditorConfig>) {
+        self.config = config;
+    }
+
     pub fn get_visible_lines(&self, viewport: Rect) -> impl Iterator<Item = line::Line> {
         let line_height = self.config.real_line_height();
 
@@ -82,45 +187,176 @@ impl Document {
         self.buffer.num_lines()
     }
 
-    pub fn insert_character_at(&mut self, position: usize, c: &str) {
-        self.buffer.edit(
-            [(Selection::caret(position), c)],
-            tuan_core::editor::EditType::InsertChars,
-        );
+    /// Converts a buffer offset to a `(line, column)` position.
+    pub fn offset_to_position(&self, offset: usize) -> (usize, usize) {
+        self.buffer.offset_to_line_col(offset)
+    }
+
+    /// Converts an LSP position, counted in UTF-16 code units, to a buffer offset.
+    pub fn offset_of_position(&self, position: &lsp_types::Position) -> usize {
+        self.buffer.offset_of_position(position)
+    }
+
+    /// Converts a buffer offset to an LSP position, counted in UTF-16 code units.
+    pub fn lsp_position(&self, offset: usize) -> lsp_types::Position {
+        self.buffer.offset_to_position(offset)
+    }
+
+    pub fn text_in(&self, range: Range<usize>) -> String {
+        self.buffer.slice_to_cow(range).into_owned()
+    }
+
+    /// The offsets of `line`, including its line ending.
+    pub fn line_range(&self, line: usize) -> Range<usize> {
+        self.buffer.offset_of_line(line)..self.buffer.offset_of_line(line + 1)
+    }
+
+    /// Replaces each range (in offsets of the current text) by its text, as a
+    /// single undoable step, typically one edit per cursor.
+    ///
+    /// Returns the position right after each inserted text, in the order of `edits`.
+    /// An empty edit changes nothing but still gets its position, e.g. for a
+    /// cursor with nothing to edit.
+    pub fn edit(
+        &mut self,
+        edits: Vec<(Range<usize>, String)>,
+        kind: EditKind,
+        cursors_before: Vec<(usize, usize)>,
+    ) -> Vec<(usize, usize)> {
+        let mut order = (0..edits.len()).collect::<Vec<_>>();
+        order.sort_by_key(|&i| edits[i].0.start);
+
+        let mut changes = Vec::with_capacity(edits.len());
+        let mut carets = vec![0; edits.len()];
+        let mut shift = 0isize;
+        let mut last_end = 0;
+        for i in order {
+            let (range, text) = &edits[i];
+            if range.is_empty() && text.is_empty() {
+                let offset = range.start.max(last_end).min(self.buffer.len());
+                carets[i] = offset.saturating_add_signed(shift);
+                continue;
+            }
+            // Overlapping ranges (e.g. two cursors deleting the same character) are applied once.
+            let start = range.start.max(last_end).min(self.buffer.len());
+            let end = range.end.clamp(start, self.buffer.len());
+
+            carets[i] = start.saturating_add_signed(shift) + text.len();
+            shift += text.len() as isize - (end - start) as isize;
+            last_end = end;
+
+            changes.push(Change {
+                start,
+                deleted: self.buffer.slice_to_cow(start..end).to_string(),
+                inserted: text.clone(),
+            });
+        }
+
+        let edit_type = match kind {
+            EditKind::InsertChars => EditType::InsertChars,
+            EditKind::Delete => EditType::DeleteSelection,
+            EditKind::Other => EditType::Other,
+        };
+        self.apply_changes(&changes, edit_type);
+
+        let cursors_after = carets
+            .into_iter()
+            .map(|offset| self.offset_to_position(offset))
+            .collect::<Vec<_>>();
+        self.history
+            .record(kind, changes, cursors_before, cursors_after.clone());
+        cursors_after
+    }
+
+    /// Reverts the last undo step, returning the cursor positions to restore.
+    pub fn undo(&mut self) -> Option<Vec<(usize, usize)>> {
+        let revert = self.history.undo()?;
+        for step in &revert.steps {
+            self.apply_changes(step, EditType::Undo);
+        }
+        Some(revert.cursors)
+    }
+
+    /// Re-applies the last undone step, returning the cursor positions to restore.
+    pub fn redo(&mut self) -> Option<Vec<(usize, usize)>> {
+        let revert = self.history.redo()?;
+        for step in &revert.steps {
+            self.apply_changes(step, EditType::Redo);
+        }
+        Some(revert.cursors)
+    }
+
+    /// Ends the current undo group, so the next edit starts a new undo step.
+    pub fn break_undo_group(&mut self) {
+        self.history.break_group();
     }
 
-    pub fn delete_character_at(&mut self, position: usize) {
-        self.buffer.edit(
-            [(Selection::region(position - 1, position), "")],
-            tuan_core::editor::EditType::DeleteSelection,
+    /// Applies `changes` to the buffer and forwards the resulting delta to the proxy.
+    fn apply_changes(&mut self, changes: &[Change], edit_type: EditType) {
+        let rev_before = self.buffer.rev();
+        let (old_text, delta, _) = self.buffer.edit(
+            changes.iter().map(|change| {
+                (
+                    Selection::region(change.start, change.start + change.deleted.len()),
+                    change.inserted.as_str(),
+                )
+            }),
+            edit_type,
         );
+        highlight::shift_styles(Arc::make_mut(&mut self.styles), changes);
+        self.syntax_edits.push(&old_text, delta.clone(), changes);
+        self.proxy_rpc
+            .update(self.path.clone(), delta, self.buffer.rev());
+
+        let recent_changes = Arc::make_mut(&mut self.recent_changes);
+        if recent_changes.len() == RECENT_CHANGES_LIMIT {
+            recent_changes.pop_front();
+        }
+        recent_changes.push_back((rev_before, self.buffer.rev(), Arc::new(changes.to_vec())));
     }
 
-    pub fn update_styles_with_syntax(&mut self) {
-        let mut syntax = Syntax::init(&self.path);
-        syntax.parse(1, self.buffer.text().clone(), None);
-        self.styles = if let Some(syntax_styles) = syntax.styles {
-            syntax_styles
-                .iter()
-                .map(|(interval, style)| {
-                    style
-                        .fg_color
-                        .as_ref()
-                        .and_then(|fg_color| match &self.config.theme {
-                            theme::Theme::Vscode(vscode_theme) => {
-                                vscode_theme.get_style(vec![fg_color])
-                            }
-                        })
-                        .map(|style| RangeStyle {
-                            start: interval.start,
-                            end: interval.end,
-                            style,
-                        })
-                })
-                .flatten()
-                .collect()
-        } else {
-            Vec::new()
+    /// The changes applied since revision `rev`, oldest first, each sorted by
+    /// offset; `None` when they are not all remembered anymore.
+    pub(crate) fn changes_since(&self, rev: u64) -> Option<Vec<Arc<Vec<Change>>>> {
+        let start = match self
+            .recent_changes
+            .iter()
+            .position(|(before, ..)| *before == rev)
+        {
+            Some(start) => start,
+            None if rev == self.buffer.rev() => return Some(Vec::new()),
+            None => return None,
         };
+        let mut expected = rev;
+        let mut changes = Vec::new();
+        for (before, after, step) in self.recent_changes.iter().skip(start) {
+            if *before != expected {
+                return None;
+            }
+            expected = *after;
+            changes.push(step.clone());
+        }
+        (expected == self.buffer.rev()).then_some(changes)
+    }
+
+    pub(crate) fn highlighter(&self) -> Arc<Mutex<Highlighter>> {
+        self.highlighter.clone()
+    }
+
+    /// Takes what the highlighter needs to catch up with the buffer: its
+    /// revision and text, the edits made since the last parse, and the config.
+    pub(crate) fn take_syntax_edits(
+        &mut self,
+    ) -> (u64, Rope, SyntaxEdits, Arc<editor_view::EditorConfig>) {
+        (
+            self.buffer.rev(),
+            self.buffer.text().clone(),
+            std::mem::take(&mut self.syntax_edits),
+            self.config.clone(),
+        )
+    }
+
+    pub(crate) fn set_styles(&mut self, styles: Vec<RangeStyle>) {
+        self.styles = Arc::new(styles);
     }
 }
@@ -0,0 +1,358 @@
+use std::{
+    collections::{HashMap, HashSet},
+    ops::Range,
+    path::Path,
+    sync::Arc,
+};
+
+use lapce_xi_rope::{Interval, Rope, RopeDelta};
+use tree_sitter::{InputEdit, Point, Tree};
+use tuan_core::{
+    language::LapceLanguage,
+    style::Style,
+    syntax::{Syntax, edit::SyntaxEdit},
+};
+
+use super::{RangeStyle, history::Change};
+use crate::{
+    editor_view::EditorConfig,
+    theme::{self, theme::Theme as _},
+};
+
+/// The syntax tree of a document, reparsed incrementally from its edits.
+///
+/// It is shared by all the clones of a document, and only used off the UI thread.
+pub struct Highlighter {
+    syntax: Syntax,
+    /// The buffer revision of the last parse, `None` before the first one.
+    parsed_rev: Option<u64>,
+    /// The config the last styles were resolved with.
+    config: Option<Arc<EditorConfig>>,
+    /// The styles of the last parse, only resolved again where the next one changes.
+    styles: Vec<RangeStyle>,
+}
+
+/// The edits made to a document since its last parse.
+#[derive(Clone, Default)]
+pub struct SyntaxEdits {
+    syntax: Vec<SyntaxEdit>,
+    /// The same edits, one after the other, to compare the previous syntax
+    /// tree with the new one.
+    tree: Vec<InputEdit>,
+}
+
+impl SyntaxEdits {
+    /// Records `changes` (sorted by offset), applied to `old_text` as `delta`.
+    pub(super) fn push(&mut self, old_text: &Rope, delta: RopeDelta, changes: &[Change]) {
+        self.syntax.push(SyntaxEdit::from_delta(old_text, delta));
+        self.tree.extend(tree_edits(old_text, changes));
+    }
+}
+
+/// `changes` (sorted by offset) of `old_text` as tree-sitter edits, applied
+/// one after the other.
+fn tree_edits(old_text: &Rope, changes: &[Change]) -> Vec<InputEdit> {
+    // From the last change, the offsets of the previous ones stay valid.
+    changes
+        .iter()
+        .rev()
+        .map(|change| {
+            let old_end = change.start + change.deleted.len();
+            let start_position = point_at(old_text, change.start);
+            InputEdit {
+                start_byte: change.start,
+                old_end_byte: old_end,
+                new_end_byte: change.start + change.inserted.len(),
+                start_position,
+                old_end_position: point_at(old_text, old_end),
+                new_end_position: point_after(start_position, &change.inserted),
+            }
+        })
+        .collect()
+}
+
+impl Highlighter {
+    pub fn new(path: &Path) -> Self {
+        Self {
+            syntax: Syntax::init(path),
+            parsed_rev: None,
+            config: None,
+            styles: Vec::new(),
+        }
+    }
+
+    /// Reparses `text` at revision `rev`, given the edits made since the last
+    /// parse, and returns the styles of the whole text.
+    ///
+    /// Only the ranges where the syntax tree changed are styled again, unless
+    /// the config changed. Returns `None` when `rev` was already parsed with
+    /// the same config.
+    pub fn highlight(
+        &mut self,
+        rev: u64,
+        text: Rope,
+        edits: SyntaxEdits,
+        config: &Arc<EditorConfig>,
+    ) -> Option<Vec<RangeStyle>> {
+        let config_changed = !self
+            .config
+            .as_ref()
+            .is_some_and(|current| Arc::ptr_eq(current, config));
+        if config_changed {
+            self.config = Some(config.clone());
+        }
+
+        let mut changed = None;
+        if self.parsed_rev != Some(rev) {
+            let previous_tree = self.parsed_rev.and(self.tree());
+            // Without a previous tree, the edits are meaningless: parse everything.
+            let syntax_edits = self.parsed_rev.is_some().then_some(edits.syntax.as_slice());
+            self.syntax.parse(rev, text, syntax_edits);
+            self.parsed_rev = Some(rev);
+
+            if let (Some(mut previous_tree), Some(tree)) = (previous_tree, self.tree()) {
+                for edit in &edits.tree {
+                    previous_tree.edit(edit);
+                }
+                changed = Some(changed_ranges(&previous_tree, &tree, &edits.tree));
+            }
+        } else if !config_changed {
+            return None;
+        }
+
+        self.styles = match changed {
+            Some(changed) if !config_changed => {
+                let mut styles = std::mem::take(&mut self.styles);
+                for style in styles.iter_mut() {
+                    style.start = shift_tree_offset(style.start, &edits.tree);
+                    style.end = shift_tree_offset(style.end, &edits.tree);
+                }
+                restyle(styles, &changed, &self.syntax, config)
+            }
+            _ => resolve_styles(&self.syntax, config),
+        };
+        Some(self.styles.clone())
+    }
+
+    /// The syntax tree of the document, without the injected languages.
+    fn tree(&self) -> Option<Tree> {
+        self.syntax.layers.as_ref()?.try_tree().cloned()
+    }
+}
+
+/// The `(row, column)` of `offset` in `text`, in bytes as tree-sitter counts.
+fn point_at(text: &Rope, offset: usize) -> Point {
+    let row = text.line_of_offset(offset);
+    Point::new(row, offset - text.offset_of_line(row))
+}
+
+/// The position after inserting `text` at `start`.
+fn point_after(start: Point, text: &str) -> Point {
+    match text.rfind('\n') {
+        Some(last_newline) => Point::new(
+            start.row + text.matches('\n').count(),
+            text.len() - last_newline - 1,
+        ),
+        None => Point::new(start.row, start.column + text.len()),
+    }
+}
+
+/// Maps an offset of the text before `edits` (applied one after the other)
+/// to the text after them.
+fn shift_tree_offset(offset: usize, edits: &[InputEdit]) -> usize {
+    edits.iter().fold(offset, |offset, edit| {
+        if offset < edit.start_byte {
+            offset
+        } else if offset < edit.old_end_byte {
+            edit.start_byte
+        } else {
+            offset - edit.old_end_byte + edit.new_end_byte
+        }
+    })
+}
+
+/// The ranges of the new text whose styles may have changed, sorted and
+/// merged: where the syntax tree changed, and the inserted text itself.
+fn changed_ranges(previous_tree: &Tree, tree: &Tree, edits: &[InputEdit]) -> Vec<Range<usize>> {
+    let mut ranges = previous_tree
+        .changed_ranges(tree)
+        .map(|range| range.start_byte..range.end_byte)
+        .collect::<Vec<_>>();
+    for (index, edit) in edits.iter().enumerate() {
+        let later_edits = &edits[index + 1..];
+        ranges.push(
+            shift_tree_offset(edit.start_byte, later_edits)
+                ..shift_tree_offset(edit.new_end_byte, later_edits),
+        );
+    }
+    ranges.sort_by_key(|range| range.start);
+
+    let mut merged: Vec<Range<usize>> = Vec::new();
+    for range in ranges {
+        match merged.last_mut() {
+            Some(last) if range.start <= last.end => last.end = last.end.max(range.end),
+            _ => merged.push(range),
+        }
+    }
+    merged
+}
+
+/// Replaces the `styles` touching the `changed` ranges by the ones of the
+/// last parse of `syntax`.
+fn restyle(
+    mut styles: Vec<RangeStyle>,
+    changed: &[Range<usize>],
+    syntax: &Syntax,
+    config: &EditorConfig,
+) -> Vec<RangeStyle> {
+    let touches_changed = |start: usize, end: usize| {
+        changed
+            .iter()
+            .any(|range| start < range.end.max(range.start + 1) && range.start <= end)
+    };
+    styles.retain(|style| !touches_changed(style.start, style.end));
+
+    if let Some(spans) = syntax.styles.as_ref() {
+        let spans = spans
+            .iter()
+            .filter(|(interval, _)| touches_changed(interval.start, interval.end))
+            .collect::<Vec<_>>();
+        styles.extend(resolve_spans(&spans, config));
+    }
+    styles.sort_by_key(|style| style.start);
+    styles
+}
+
+/// Highlights `code` in `language`, e.g. a code block of a hover popup, or
+/// in the language of `path` when it is unknown.
+pub fn highlight_code(
+    code: &str,
+    language: Option<&str>,
+    path: &Path,
+    config: &EditorConfig,
+) -> Vec<RangeStyle> {
+    let mut syntax = language
+        .and_then(LapceLanguage::from_name)
+        .map(Syntax::from_language)
+        .unwrap_or_else(|| Syntax::init(path));
+    syntax.parse(0, Rope::from(code), None);
+    resolve_styles(&syntax, config)
+}
+
+/// The styles of the last parse of `syntax`, with the colors of the theme.
+fn resolve_styles(syntax: &Syntax, config: &EditorConfig) -> Vec<RangeStyle> {
+    match syntax.styles.as_ref() {
+        Some(spans) => resolve_spans(&spans.iter().collect::<Vec<_>>(), config),
+        None => Vec::new(),
+    }
+}
+
+/// `spans` of the syntax, with the colors of the theme.
+fn resolve_spans(spans: &[(Interval, &Style)], config: &EditorConfig) -> Vec<RangeStyle> {
+    let scopes = spans
+        .iter()
+        .filter_map(|(_, style)| style.fg_color.as_deref())
+        .collect::<HashSet<_>>()
+        .into_iter()
+        .collect::<Vec<_>>();
+    let scope_styles = match &config.theme {
+        theme::Theme::Vscode(vscode_theme) => vscode_theme.get_styles(&scopes),
+    };
+    let scope_styles = scopes
+        .into_iter()
+        .zip(scope_styles)
+        .collect::<HashMap<_, _>>();
+
+    spans
+        .iter()
+        .filter_map(|(interval, style)| {
+            let style = scope_styles.get(style.fg_color.as_deref()?)?.clone()?;
+            Some(RangeStyle {
+                start: interval.start,
+                end: interval.end,
+                style,
+            })
+        })
+        .collect()
+}
+
+/// Moves `styles` along an edit, so they stay aligned with the text until the
+/// next parse replaces them.
+pub(super) fn shift_styles(styles: &mut Vec<RangeStyle>, changes: &[Change]) {
+    for style in styles.iter_mut() {
+        style.start = shift_offset(style.start, changes);
+        style.end = shift_offset(style.end, changes);
+    }
+    styles.retain(|style| style.start < style.end);
+}
+
+/// Maps an offset of the text before `changes` (sorted by offset) to the text after them.
+pub(crate) fn shift_offset(offset: usize, changes: &[Change]) -> usize {
+    let mut shift = 0isize;
+    for change in changes {
+        if offset < change.start {
+            break;
+        }
+        if offset < change.start + change.deleted.len() {
+            // Inside the deleted text.
+            return change.start.saturating_add_signed(shift);
+        }
+        shift += change.inserted.len() as isize - change.deleted.len() as isize;
+    }
+    offset.saturating_add_signed(shift)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn change(start: usize, deleted: &str, inserted: &str) -> Change {
+        Change {
+            start,
+            deleted: deleted.to_string(),
+            inserted: inserted.to_string(),
+        }
+    }
+
+    #[test]
+    fn tree_edits_start_from_the_last_change() {
+        let text = Rope::from("ab\ncd\n");
+        let edits = tree_edits(&text, &[change(1, "", "x\ny"), change(3, "cd", "")]);
+
+        assert_eq!(edits.len(), 2);
+        assert_eq!(
+            (
+                edits[0].start_byte,
+                edits[0].old_end_byte,
+                edits[0].new_end_byte
+            ),
+            (3, 5, 3)
+        );
+        assert_eq!(edits[0].start_position, Point::new(1, 0));
+        assert_eq!(edits[0].old_end_position, Point::new(1, 2));
+        assert_eq!(edits[0].new_end_position, Point::new(1, 0));
+        assert_eq!(
+            (
+                edits[1].start_byte,
+                edits[1].old_end_byte,
+                edits[1].new_end_byte
+            ),
+            (1, 1, 4)
+        );
+        assert_eq!(edits[1].start_position, Point::new(0, 1));
+        assert_eq!(edits[1].new_end_position, Point::new(1, 1));
+    }
+
+    #[test]
+    fn offsets_follow_tree_edits() {
+        let text = Rope::from("ab\ncd\n");
+        // "ab\ncd\n" becomes "ax\nyb\n\n".
+        let edits = tree_edits(&text, &[change(1, "", "x\ny"), change(3, "cd", "")]);
+
+        assert_eq!(shift_tree_offset(0, &edits), 0);
+        assert_eq!(shift_tree_offset(2, &edits), 5);
+        assert_eq!(shift_tree_offset(4, &edits), 6);
+        assert_eq!(shift_tree_offset(5, &edits), 6);
+        assert_eq!(shift_tree_offset(6, &edits), 7);
+    }
+}
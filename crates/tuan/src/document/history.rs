@@ -0,0 +1,287 @@
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+/// Typing pauses longer than this start a new undo step.
+const GROUP_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// A replacement of `deleted` by `inserted` at `start`, an offset in the text before the edit.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Change {
+    pub start: usize,
+    pub deleted: String,
+    pub inserted: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EditKind {
+    /// Typed characters, grouped together in a single undo step.
+    InsertChars,
+    Delete,
+    Other,
+}
+
+/// An undo step: one or more atomic edits, with the cursors around them.
+#[derive(Debug, Clone)]
+struct Transaction {
+    kind: EditKind,
+    /// Each step is a multi-cursor edit applied at once, its changes sorted by offset.
+    steps: Vec<Vec<Change>>,
+    cursors_before: Vec<(usize, usize)>,
+    cursors_after: Vec<(usize, usize)>,
+}
+
+/// What to apply to the buffer to undo or redo a transaction.
+pub struct Revert {
+    pub steps: Vec<Vec<Change>>,
+    pub cursors: Vec<(usize, usize)>,
+}
+
+/// The undo/redo stacks of a document.
+///
+/// Transactions are shared so that cloning a `Document` stays cheap.
+#[derive(Debug, Clone, Default)]
+pub struct History {
+    undo_stack: Vec<Arc<Transaction>>,
+    redo_stack: Vec<Arc<Transaction>>,
+    /// When the top of the undo stack was last extended, if it can still be.
+    last_edit_at: Option<Instant>,
+}
+
+impl History {
+    /// Records an atomic edit, merging it into the previous undo step when
+    /// the user is still typing at the same place.
+    pub fn record(
+        &mut self,
+        kind: EditKind,
+        changes: Vec<Change>,
+        cursors_before: Vec<(usize, usize)>,
+        cursors_after: Vec<(usize, usize)>,
+    ) {
+        self.redo_stack.clear();
+
+        let now = Instant::now();
+        let can_merge = kind == EditKind::InsertChars
+            && self
+                .last_edit_at
+                .is_some_and(|at| now.duration_since(at) < GROUP_TIMEOUT);
+        self.last_edit_at = Some(now);
+
+        if can_merge
+            && let Some(last) = self.undo_stack.last_mut()
+            && last.kind == kind
+            && last.cursors_after == cursors_before
+        {
+            let last = Arc::make_mut(last);
+            last.steps.push(changes);
+            last.cursors_after = cursors_after;
+            return;
+        }
+
+        self.undo_stack.push(Arc::new(Transaction {
+            kind,
+            steps: vec![changes],
+            cursors_before,
+            cursors_after,
+        }));
+    }
+
+    /// Stops the current undo step from growing, e.g. after the cursors moved.
+    pub fn break_group(&mut self) {
+        self.last_edit_at = None;
+    }
+
+    pub fn undo(&mut self) -> Option<Revert> {
+        let transaction = self.undo_stack.pop()?;
+        self.last_edit_at = None;
+
+        let revert = Revert {
+            steps: transaction
+                .steps
+                .iter()
+                .rev()
+                .map(|step| invert(step))
+                .collect(),
+            cursors: transaction.cursors_before.clone(),
+        };
+        self.redo_stack.push(transaction);
+        Some(revert)
+    }
+
+    pub fn redo(&mut self) -> Option<Revert> {
+        let transaction = self.redo_stack.pop()?;
+        self.last_edit_at = None;
+
+        let revert = Revert {
+            steps: transaction.steps.clone(),
+            cursors: transaction.cursors_after.clone(),
+        };
+        self.undo_stack.push(transaction);
+        Some(revert)
+    }
+}
+
+/// Returns the changes undoing `step`, in offsets of the text after `step`.
+fn invert(step: &[Change]) -> Vec<Change> {
+    let mut shift = 0isize;
+    step.iter()
+        .map(|change| {
+            let start = change.start.saturating_add_signed(shift);
+            shift += change.inserted.len() as isize - change.deleted.len() as isize;
+            Change {
+                start,
+                deleted: change.inserted.clone(),
+                inserted: change.deleted.clone(),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn insert(start: usize, inserted: &str) -> Vec<Change> {
+        vec![Change {
+            start,
+            deleted: String::new(),
+            inserted: inserted.to_string(),
+        }]
+    }
+
+    #[test]
+    fn typed_characters_are_grouped_in_one_undo_step() {
+        let mut history = History::default();
+        history.record(
+            EditKind::InsertChars,
+            insert(0, "a"),
+            vec![(0, 0)],
+            vec![(0, 1)],
+        );
+        history.record(
+            EditKind::InsertChars,
+            insert(1, "b"),
+            vec![(0, 1)],
+            vec![(0, 2)],
+        );
+        history.record(
+            EditKind::InsertChars,
+            insert(2, "c"),
+            vec![(0, 2)],
+            vec![(0, 3)],
+        );
+
+        let revert = history.undo().unwrap();
+        assert_eq!(revert.steps.len(), 3);
+        assert_eq!(revert.cursors, vec![(0, 0)]);
+        assert!(history.undo().is_none());
+    }
+
+    #[test]
+    fn typing_elsewhere_starts_a_new_undo_step() {
+        let mut history = History::default();
+        history.record(
+            EditKind::InsertChars,
+            insert(0, "a"),
+            vec![(0, 0)],
+            vec![(0, 1)],
+        );
+        // The cursor moved before typing again.
+        history.record(
+            EditKind::InsertChars,
+            insert(5, "b"),
+            vec![(0, 5)],
+            vec![(0, 6)],
+        );
+
+        assert_eq!(history.undo().unwrap().cursors, vec![(0, 5)]);
+        assert_eq!(history.undo().unwrap().cursors, vec![(0, 0)]);
+    }
+
+    #[test]
+    fn other_edits_and_breaks_are_not_grouped() {
+        let mut history = History::default();
+        history.record(
+            EditKind::InsertChars,
+            insert(0, "a"),
+            vec![(0, 0)],
+            vec![(0, 1)],
+        );
+        history.record(EditKind::Other, insert(1, "b"), vec![(0, 1)], vec![(0, 2)]);
+        history.record(EditKind::Other, insert(2, "c"), vec![(0, 2)], vec![(0, 3)]);
+        history.record(
+            EditKind::InsertChars,
+            insert(3, "d"),
+            vec![(0, 3)],
+            vec![(0, 4)],
+        );
+        history.break_group();
+        history.record(
+            EditKind::InsertChars,
+            insert(4, "e"),
+            vec![(0, 4)],
+            vec![(0, 5)],
+        );
+
+        let mut undone = 0;
+        while history.undo().is_some() {
+            undone += 1;
+        }
+        assert_eq!(undone, 5);
+    }
+
+    #[test]
+    fn undo_and_redo_restore_the_cursors() {
+        let mut history = History::default();
+        history.record(
+            EditKind::Other,
+            vec![
+                Change {
+                    start: 0,
+                    deleted: String::new(),
+                    inserted: "x".to_string(),
+                },
+                Change {
+                    start: 4,
+                    deleted: String::new(),
+                    inserted: "x".to_string(),
+                },
+            ],
+            vec![(0, 0), (1, 0)],
+            vec![(0, 1), (1, 1)],
+        );
+
+        let undo = history.undo().unwrap();
+        assert_eq!(undo.cursors, vec![(0, 0), (1, 0)]);
+        // The inverse changes are in offsets of the text after the edit.
+        assert_eq!(
+            undo.steps,
+            vec![vec![
+                Change {
+                    start: 0,
+                    deleted: "x".to_string(),
+                    inserted: String::new(),
+                },
+                Change {
+                    start: 5,
+                    deleted: "x".to_string(),
+                    inserted: String::new(),
+                },
+            ]]
+        );
+
+        let redo = history.redo().unwrap();
+        assert_eq!(redo.cursors, vec![(0, 1), (1, 1)]);
+        assert!(history.redo().is_none());
+    }
+
+    #[test]
+    fn a_new_edit_clears_the_redo_stack() {
+        let mut history = History::default();
+        history.record(EditKind::Other, insert(0, "a"), vec![(0, 0)], vec![(0, 1)]);
+        history.undo();
+        history.record(EditKind::Other, insert(0, "b"), vec![(0, 0)], vec![(0, 1)]);
+        assert!(history.redo().is_none());
+    }
+}
@@ -1,4 +1,7 @@
 mod document;
+pub mod highlight;
+pub mod history;
 pub mod line;
+pub mod snippet;
 
 pub use document::*;
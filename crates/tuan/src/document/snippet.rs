@@ -0,0 +1,207 @@
+use std::{collections::BTreeMap, iter::Peekable, ops::Range, str::Chars};
+
+/// An LSP snippet, e.g. `println!("${1:message}")$0`, expanded to its text
+/// and the byte ranges of its tabstops within that text.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Snippet {
+    pub text: String,
+    /// The ranges of each tabstop, in the order they are visited: by number,
+    /// `$0` (the final cursor position) last. A tabstop used several times
+    /// has several ranges, to be edited together.
+    pub tabstops: Vec<Vec<Range<usize>>>,
+}
+
+impl Snippet {
+    /// Parses the LSP snippet syntax: `$1`, `${1}`, `${1:placeholder}` (which
+    /// can nest), `${1|one,two|}` (keeping the first choice), and `$VAR` or
+    /// `${VAR:default}` (replaced by the default). `\` escapes `$`, `}` and `\`.
+    pub fn parse(snippet: &str) -> Self {
+        let mut parser = Parser {
+            chars: snippet.chars().peekable(),
+            text: String::new(),
+            tabstops: BTreeMap::new(),
+        };
+        parser.parse_until_brace(false);
+
+        // Without `$0`, the snippet ends after its text.
+        let mut tabstops = parser.tabstops;
+        let end = parser.text.len();
+        let last = tabstops
+            .remove(&0)
+            .or_else(|| (!tabstops.is_empty()).then(|| vec![end..end]));
+        Self {
+            text: parser.text,
+            tabstops: tabstops.into_values().chain(last).collect(),
+        }
+    }
+}
+
+struct Parser<'a> {
+    chars: Peekable<Chars<'a>>,
+    text: String,
+    tabstops: BTreeMap<u32, Vec<Range<usize>>>,
+}
+
+impl Parser<'_> {
+    /// Parses text and placeholders until the end, or a `}` closing the
+    /// current placeholder when `nested`, which is consumed.
+    fn parse_until_brace(&mut self, nested: bool) {
+        while let Some(c) = self.chars.next() {
+            match c {
+                '\\' => match self.chars.peek() {
+                    Some(&escaped @ ('$' | '}' | '\\')) => {
+                        self.chars.next();
+                        self.text.push(escaped);
+                    }
+                    _ => self.text.push('\\'),
+                },
+                '}' if nested => return,
+                '$' => self.parse_dollar(),
+                c => self.text.push(c),
+            }
+        }
+    }
+
+    fn parse_dollar(&mut self) {
+        match self.chars.peek() {
+            Some(c) if c.is_ascii_digit() => {
+                let number = self.parse_number();
+                let start = self.text.len();
+                self.add_tabstop(number, start..start);
+            }
+            Some(c) if c.is_alphabetic() || *c == '_' => {
+                // A variable without a default, e.g. `$TM_FILENAME`: left empty.
+                self.parse_name();
+            }
+            Some('{') => {
+                self.chars.next();
+                self.parse_braced();
+            }
+            _ => self.text.push('$'),
+        }
+    }
+
+    /// Parses what follows `${`, up to and including the closing `}`.
+    fn parse_braced(&mut self) {
+        let number = match self.chars.peek() {
+            Some(c) if c.is_ascii_digit() => Some(self.parse_number()),
+            _ => {
+                self.parse_name();
+                None
+            }
+        };
+
+        let start = self.text.len();
+        match self.chars.next() {
+            Some(':') => self.parse_until_brace(true),
+            Some('|') => self.parse_choice(),
+            // `}` or the end of a malformed snippet.
+            _ => {}
+        }
+        if let Some(number) = number {
+            self.add_tabstop(number, start..self.text.len());
+        }
+    }
+
+    /// Keeps the first choice of `${1|one,two|}`, after the `|`.
+    fn parse_choice(&mut self) {
+        let mut first = true;
+        while let Some(c) = self.chars.next() {
+            match c {
+                '\\' => {
+                    if let Some(escaped) = self.chars.next()
+                        && first
+                    {
+                        self.text.push(escaped);
+                    }
+                }
+                ',' => first = false,
+                '|' => {
+                    if self.chars.peek() == Some(&'}') {
+                        self.chars.next();
+                    }
+                    return;
+                }
+                c if first => self.text.push(c),
+                _ => {}
+            }
+        }
+    }
+
+    fn parse_number(&mut self) -> u32 {
+        let mut number = 0u32;
+        while let Some(digit) = self.chars.peek().and_then(|c| c.to_digit(10)) {
+            self.chars.next();
+            number = number.saturating_mul(10).saturating_add(digit);
+        }
+        number
+    }
+
+    fn parse_name(&mut self) {
+        while self
+            .chars
+            .peek()
+            .is_some_and(|c| c.is_alphanumeric() || *c == '_')
+        {
+            self.chars.next();
+        }
+    }
+
+    fn add_tabstop(&mut self, number: u32, range: Range<usize>) {
+        self.tabstops.entry(number).or_default().push(range);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn placeholders_become_tabstops_with_the_final_one_last() {
+        let snippet = Snippet::parse("println!(\"${1:message}\")$0");
+        assert_eq!(snippet.text, "println!(\"message\")");
+        assert_eq!(snippet.tabstops, vec![vec![10..17], vec![19..19]]);
+    }
+
+    #[test]
+    fn nested_placeholders_are_inside_their_parent() {
+        let snippet = Snippet::parse("${1:a${2:b}c}");
+        assert_eq!(snippet.text, "abc");
+        assert_eq!(snippet.tabstops, vec![vec![0..3], vec![1..2], vec![3..3]]);
+    }
+
+    #[test]
+    fn repeated_tabstops_are_edited_together() {
+        let snippet = Snippet::parse("$1 = $1;$0");
+        assert_eq!(snippet.text, " = ;");
+        assert_eq!(snippet.tabstops, vec![vec![0..0, 3..3], vec![4..4]]);
+    }
+
+    #[test]
+    fn choices_keep_the_first_one() {
+        let snippet = Snippet::parse("${1|one,two|} x");
+        assert_eq!(snippet.text, "one x");
+        assert_eq!(snippet.tabstops, vec![vec![0..3], vec![5..5]]);
+    }
+
+    #[test]
+    fn escapes_are_kept_as_text() {
+        let snippet = Snippet::parse("\\$1 \\} \\\\ ${1:a\\}b}");
+        assert_eq!(snippet.text, "$1 } \\ a}b");
+        assert_eq!(snippet.tabstops, vec![vec![7..10], vec![10..10]]);
+    }
+
+    #[test]
+    fn variables_are_replaced_by_their_default() {
+        let snippet = Snippet::parse("${TM_FILENAME:main.rs} $VAR");
+        assert_eq!(snippet.text, "main.rs ");
+        assert!(snippet.tabstops.is_empty());
+    }
+
+    #[test]
+    fn without_tabstops_there_is_no_final_one() {
+        let snippet = Snippet::parse("plain text");
+        assert_eq!(snippet.text, "plain text");
+        assert!(snippet.tabstops.is_empty());
+    }
+}
@@ -1,26 +1,63 @@
-use crate::theme;
+use xilem::Color;
+
+use crate::{
+    settings,
+    theme::{self, theme::Theme as _},
+};
+
+const DEFAULT_FONT_SIZE: f32 = 14.0;
+const DEFAULT_LINE_HEIGHT: f32 = 1.5;
 
 #[derive(Clone, Debug)]
 pub struct EditorConfig {
     pub font_size: f32,
     pub line_height: f32,
     pub theme: theme::Theme,
+    pub line_numbers: settings::LineNumbers,
 }
 
 impl EditorConfig {
     pub fn real_line_height(&self) -> f32 {
         self.font_size * self.line_height
     }
+
+    /// The color the theme gives to the first of `scopes` it sets, e.g.
+    /// `["editorGutter.background", "editor.background"]`.
+    pub fn theme_color(&self, scopes: &[&str]) -> Option<Color> {
+        match &self.theme {
+            theme::Theme::Vscode(vscode_theme) => scopes
+                .iter()
+                .find_map(|scope| vscode_theme.get_style(vec![*scope]).and_then(|s| s.color)),
+        }
+    }
+
+    /// Builds the config from merged settings, using the defaults for unset values.
+    pub fn from_settings(settings: &settings::Settings) -> Result<Self, settings::SettingsError> {
+        let theme = match &settings.theme {
+            Some(path) => theme::vscode_theme::VscodeTheme::from_path(path.clone())
+                .map(theme::Theme::from_vscode_theme)
+                .map_err(|err| {
+                    settings::SettingsError::invalid_value(path, "editor.theme", err.to_string())
+                })?,
+            None => theme::Theme::default(),
+        };
+
+        Ok(Self {
+            font_size: settings.font_size.unwrap_or(DEFAULT_FONT_SIZE),
+            line_height: settings.line_height.unwrap_or(DEFAULT_LINE_HEIGHT),
+            theme,
+            line_numbers: settings.line_numbers.unwrap_or_default(),
+        })
+    }
 }
 
 impl Default for EditorConfig {
     fn default() -> Self {
         Self {
-            font_size: 14.0,
-            line_height: 1.5,
-            theme: theme::Theme::from_vscode_theme(
-                theme::vscode_theme::VscodeTheme::from_path("/Users/arthurfontaine/Developer/code/github.com/arthur-fontaine/tuan/crates/tuan/assets/rose-pine-moon-color-theme.json".into()).unwrap()
-            ),
+            font_size: DEFAULT_FONT_SIZE,
+            line_height: DEFAULT_LINE_HEIGHT,
+            theme: theme::Theme::default(),
+            line_numbers: settings::LineNumbers::default(),
         }
     }
 }
@@ -1,5 +1,6 @@
 use crate::editor_view::paint::cursor::Cursor;
 use serde::Deserialize;
+use xilem::view::Axis;
 
 #[derive(PartialEq, Eq, Debug, Deserialize, Clone)]
 pub enum EditorAction {
@@ -11,15 +12,350 @@ pub enum EditorAction {
     CursorEnd,
     CursorTop,
     CursorBottom,
-    DeleteChar
+    SelectLeft,
+    SelectRight,
+    SelectUp,
+    SelectDown,
+    SelectWord,
+    SelectLine,
+    SelectAll,
+    DeleteChar,
+    Undo,
+    Redo,
+    Copy,
+    Cut,
+    Paste,
+    Save,
+    SaveAs,
+    SaveAll,
+    NextTab,
+    PreviousTab,
+    MoveTabLeft,
+    MoveTabRight,
+    CloseTab,
+    SplitRight,
+    SplitDown,
+    FocusNextPane,
+    FocusPreviousPane,
+    ClosePane,
+    ToggleExplorer,
+    RevealInExplorer,
+    ToggleProblems,
+    TriggerCompletion,
+    AcceptCompletion,
+    CancelCompletion,
+    SelectNextCompletion,
+    SelectPreviousCompletion,
+    NextTabstop,
+    PreviousTabstop,
+    GoToDefinition,
+    GoToTypeDefinition,
+    FindReferences,
+    GoToImplementation,
+    NavigateBack,
+    NavigateForward,
+    ShowHover,
+    RenameSymbol,
+    ShowCodeActions,
+    Find,
+    Replace,
+    FindNext,
+    FindPrevious,
+    CloseFind,
+    ToggleSearch,
+    ShowCommandPalette,
+    QuickOpen,
+}
+
+/// The group of an action, shown before its title in the command palette.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ActionCategory {
+    Cursor,
+    Selection,
+    Edit,
+    File,
+    Tabs,
+    Panes,
+    View,
+    Completion,
+    Navigation,
+    Language,
+    Search,
+}
+
+impl ActionCategory {
+    pub fn title(&self) -> &'static str {
+        match self {
+            Self::Cursor => "Cursor",
+            Self::Selection => "Selection",
+            Self::Edit => "Edit",
+            Self::File => "File",
+            Self::Tabs => "Tabs",
+            Self::Panes => "Panes",
+            Self::View => "View",
+            Self::Completion => "Completion",
+            Self::Navigation => "Navigation",
+            Self::Language => "Language",
+            Self::Search => "Search",
+        }
+    }
+}
+
+impl EditorAction {
+    /// Every action, in the order the command palette lists them.
+    pub const ALL: &[EditorAction] = &[
+        Self::CursorLeft,
+        Self::CursorRight,
+        Self::CursorUp,
+        Self::CursorDown,
+        Self::CursorStart,
+        Self::CursorEnd,
+        Self::CursorTop,
+        Self::CursorBottom,
+        Self::SelectLeft,
+        Self::SelectRight,
+        Self::SelectUp,
+        Self::SelectDown,
+        Self::SelectWord,
+        Self::SelectLine,
+        Self::SelectAll,
+        Self::DeleteChar,
+        Self::Undo,
+        Self::Redo,
+        Self::Copy,
+        Self::Cut,
+        Self::Paste,
+        Self::Save,
+        Self::SaveAs,
+        Self::SaveAll,
+        Self::NextTab,
+        Self::PreviousTab,
+        Self::MoveTabLeft,
+        Self::MoveTabRight,
+        Self::CloseTab,
+        Self::SplitRight,
+        Self::SplitDown,
+        Self::FocusNextPane,
+        Self::FocusPreviousPane,
+        Self::ClosePane,
+        Self::ToggleExplorer,
+        Self::RevealInExplorer,
+        Self::ToggleProblems,
+        Self::TriggerCompletion,
+        Self::AcceptCompletion,
+        Self::CancelCompletion,
+        Self::SelectNextCompletion,
+        Self::SelectPreviousCompletion,
+        Self::NextTabstop,
+        Self::PreviousTabstop,
+        Self::GoToDefinition,
+        Self::GoToTypeDefinition,
+        Self::FindReferences,
+        Self::GoToImplementation,
+        Self::NavigateBack,
+        Self::NavigateForward,
+        Self::ShowHover,
+        Self::RenameSymbol,
+        Self::ShowCodeActions,
+        Self::Find,
+        Self::Replace,
+        Self::FindNext,
+        Self::FindPrevious,
+        Self::CloseFind,
+        Self::ToggleSearch,
+        Self::ShowCommandPalette,
+        Self::QuickOpen,
+    ];
+
+    pub fn title(&self) -> &'static str {
+        match self {
+            Self::CursorLeft => "Move Cursor Left",
+            Self::CursorRight => "Move Cursor Right",
+            Self::CursorUp => "Move Cursor Up",
+            Self::CursorDown => "Move Cursor Down",
+            Self::CursorStart => "Move Cursor to Line Start",
+            Self::CursorEnd => "Move Cursor to Line End",
+            Self::CursorTop => "Move Cursor to Document Start",
+            Self::CursorBottom => "Move Cursor to Document End",
+            Self::SelectLeft => "Extend Selection Left",
+            Self::SelectRight => "Extend Selection Right",
+            Self::SelectUp => "Extend Selection Up",
+            Self::SelectDown => "Extend Selection Down",
+            Self::SelectWord => "Select Word",
+            Self::SelectLine => "Select Line",
+            Self::SelectAll => "Select All",
+            Self::DeleteChar => "Delete Character",
+            Self::Undo => "Undo",
+            Self::Redo => "Redo",
+            Self::Copy => "Copy",
+            Self::Cut => "Cut",
+            Self::Paste => "Paste",
+            Self::Save => "Save",
+            Self::SaveAs => "Save As…",
+            Self::SaveAll => "Save All",
+            Self::NextTab => "Next Tab",
+            Self::PreviousTab => "Previous Tab",
+            Self::MoveTabLeft => "Move Tab Left",
+            Self::MoveTabRight => "Move Tab Right",
+            Self::CloseTab => "Close Tab",
+            Self::SplitRight => "Split Pane Right",
+            Self::SplitDown => "Split Pane Down",
+            Self::FocusNextPane => "Focus Next Pane",
+            Self::FocusPreviousPane => "Focus Previous Pane",
+            Self::ClosePane => "Close Pane",
+            Self::ToggleExplorer => "Toggle Explorer",
+            Self::RevealInExplorer => "Reveal Active File in Explorer",
+            Self::ToggleProblems => "Toggle Problems",
+            Self::TriggerCompletion => "Trigger Completion",
+            Self::AcceptCompletion => "Accept Completion",
+            Self::CancelCompletion => "Cancel Completion",
+            Self::SelectNextCompletion => "Select Next Completion",
+            Self::SelectPreviousCompletion => "Select Previous Completion",
+            Self::NextTabstop => "Next Snippet Tabstop",
+            Self::PreviousTabstop => "Previous Snippet Tabstop",
+            Self::GoToDefinition => "Go to Definition",
+            Self::GoToTypeDefinition => "Go to Type Definition",
+            Self::FindReferences => "Find References",
+            Self::GoToImplementation => "Go to Implementation",
+            Self::NavigateBack => "Go Back",
+            Self::NavigateForward => "Go Forward",
+            Self::ShowHover => "Show Hover",
+            Self::RenameSymbol => "Rename Symbol",
+            Self::ShowCodeActions => "Show Code Actions",
+            Self::Find => "Find",
+            Self::Replace => "Replace",
+            Self::FindNext => "Find Next",
+            Self::FindPrevious => "Find Previous",
+            Self::CloseFind => "Close Find",
+            Self::ToggleSearch => "Search in Files",
+            Self::ShowCommandPalette => "Show Command Palette",
+            Self::QuickOpen => "Go to File",
+        }
+    }
+
+    pub fn category(&self) -> ActionCategory {
+        match self {
+            Self::CursorLeft
+            | Self::CursorRight
+            | Self::CursorUp
+            | Self::CursorDown
+            | Self::CursorStart
+            | Self::CursorEnd
+            | Self::CursorTop
+            | Self::CursorBottom => ActionCategory::Cursor,
+            Self::SelectLeft
+            | Self::SelectRight
+            | Self::SelectUp
+            | Self::SelectDown
+            | Self::SelectWord
+            | Self::SelectLine
+            | Self::SelectAll => ActionCategory::Selection,
+            Self::DeleteChar | Self::Undo | Self::Redo | Self::Copy | Self::Cut | Self::Paste => {
+                ActionCategory::Edit
+            }
+            Self::Save | Self::SaveAs | Self::SaveAll | Self::QuickOpen => ActionCategory::File,
+            Self::NextTab
+            | Self::PreviousTab
+            | Self::MoveTabLeft
+            | Self::MoveTabRight
+            | Self::CloseTab => ActionCategory::Tabs,
+            Self::SplitRight
+            | Self::SplitDown
+            | Self::FocusNextPane
+            | Self::FocusPreviousPane
+            | Self::ClosePane => ActionCategory::Panes,
+            Self::ToggleExplorer
+            | Self::RevealInExplorer
+            | Self::ToggleProblems
+            | Self::ShowCommandPalette => ActionCategory::View,
+            Self::TriggerCompletion
+            | Self::AcceptCompletion
+            | Self::CancelCompletion
+            | Self::SelectNextCompletion
+            | Self::SelectPreviousCompletion
+            | Self::NextTabstop
+            | Self::PreviousTabstop => ActionCategory::Completion,
+            Self::GoToDefinition
+            | Self::GoToTypeDefinition
+            | Self::FindReferences
+            | Self::GoToImplementation
+            | Self::NavigateBack
+            | Self::NavigateForward => ActionCategory::Navigation,
+            Self::ShowHover | Self::RenameSymbol | Self::ShowCodeActions => {
+                ActionCategory::Language
+            }
+            Self::Find
+            | Self::Replace
+            | Self::FindNext
+            | Self::FindPrevious
+            | Self::CloseFind
+            | Self::ToggleSearch => ActionCategory::Search,
+        }
+    }
+
+    /// Whether the action does something in the current state of the editor,
+    /// e.g. the completion actions only while the completion popup is open.
+    pub fn is_available(&self, state: &super::EditorState) -> bool {
+        let has_document = state.focused_document_path.is_some();
+        match self {
+            Self::SaveAll | Self::SplitRight | Self::SplitDown => !state.open_documents.is_empty(),
+            Self::NextTab | Self::PreviousTab | Self::MoveTabLeft | Self::MoveTabRight => {
+                state.open_documents.len() > 1
+            }
+            Self::FocusNextPane | Self::FocusPreviousPane | Self::ClosePane => {
+                state.layout.panes().len() > 1
+            }
+            Self::ToggleExplorer
+            | Self::ToggleProblems
+            | Self::ToggleSearch
+            | Self::ShowCommandPalette
+            | Self::QuickOpen => true,
+            Self::AcceptCompletion
+            | Self::CancelCompletion
+            | Self::SelectNextCompletion
+            | Self::SelectPreviousCompletion => state.completion.is_some(),
+            Self::NextTabstop | Self::PreviousTabstop => state.snippet.is_some(),
+            Self::NavigateBack => state.navigation_history.can_go_back(),
+            Self::NavigateForward => state.navigation_history.can_go_forward(),
+            Self::FindNext | Self::FindPrevious | Self::CloseFind => state.find.is_some(),
+            _ => has_document,
+        }
+    }
 }
 
 impl super::EditorState {
     pub fn handle_action(&mut self, action: &EditorAction) {
+        if *action != EditorAction::ShowHover {
+            self.hover = None;
+        }
+        // Only deleting and the completion actions keep the completion and signature popups open.
+        if !matches!(
+            action,
+            EditorAction::DeleteChar
+                | EditorAction::CursorUp
+                | EditorAction::CursorDown
+                | EditorAction::TriggerCompletion
+                | EditorAction::AcceptCompletion
+                | EditorAction::SelectNextCompletion
+                | EditorAction::SelectPreviousCompletion
+                | EditorAction::NextTabstop
+        ) {
+            self.completion = None;
+            self.signature_help = None;
+        }
+
         match action {
+            // While the completion popup is open, Up and Down move its selection.
+            EditorAction::CursorUp if self.completion.is_some() => {
+                self.select_previous_completion();
+            }
+            EditorAction::CursorDown if self.completion.is_some() => {
+                self.select_next_completion();
+            }
             EditorAction::CursorLeft => {
                 self.with_cursors_mut(|cursors| {
                     for cursor in cursors {
+                        cursor.clear_selection();
                         cursor.move_x_at(cursor.column.saturating_sub(1));
                     }
                 });
@@ -27,6 +363,7 @@ impl super::EditorState {
             EditorAction::CursorRight => {
                 self.with_cursors_mut(|cursors| {
                     for cursor in cursors {
+                        cursor.clear_selection();
                         cursor.move_x_at(cursor.column.saturating_add(1));
                     }
                 });
@@ -34,6 +371,7 @@ impl super::EditorState {
             EditorAction::CursorUp => {
                 self.with_cursors_mut(|cursors| {
                     for cursor in cursors {
+                        cursor.clear_selection();
                         cursor.move_y_at(cursor.line.saturating_sub(1));
                     }
                 });
@@ -41,6 +379,7 @@ impl super::EditorState {
             EditorAction::CursorDown => {
                 self.with_cursors_mut(|cursors| {
                     for cursor in cursors {
+                        cursor.clear_selection();
                         cursor.move_y_at(cursor.line.saturating_add(1));
                     }
                 });
@@ -48,6 +387,7 @@ impl super::EditorState {
             EditorAction::CursorStart => {
                 self.with_cursors_mut(|cursors| {
                     for cursor in cursors {
+                        cursor.clear_selection();
                         cursor.move_x_at(0);
                     }
                 });
@@ -55,6 +395,7 @@ impl super::EditorState {
             EditorAction::CursorEnd => {
                 self.with_cursors_mut(|cursors| {
                     for cursor in cursors {
+                        cursor.clear_selection();
                         cursor.move_x_at(usize::MAX);
                     }
                 });
@@ -62,6 +403,7 @@ impl super::EditorState {
             EditorAction::CursorTop => {
                 self.with_cursors_mut(|cursors| {
                     for cursor in cursors {
+                        cursor.clear_selection();
                         cursor.move_y_at(0);
                     }
                 });
@@ -69,13 +411,198 @@ impl super::EditorState {
             EditorAction::CursorBottom => {
                 self.with_cursors_mut(|cursors| {
                     for cursor in cursors {
+                        cursor.clear_selection();
                         cursor.move_y_at(usize::MAX);
                     }
                 });
             }
+            EditorAction::SelectLeft => {
+                self.with_cursors_mut(|cursors| {
+                    for cursor in cursors {
+                        cursor.extend_selection(|c| c.move_x_at(c.column.saturating_sub(1)));
+                    }
+                });
+            }
+            EditorAction::SelectRight => {
+                self.with_cursors_mut(|cursors| {
+                    for cursor in cursors {
+                        cursor.extend_selection(|c| c.move_x_at(c.column.saturating_add(1)));
+                    }
+                });
+            }
+            EditorAction::SelectUp => {
+                self.with_cursors_mut(|cursors| {
+                    for cursor in cursors {
+                        cursor.extend_selection(|c| c.move_y_at(c.line.saturating_sub(1)));
+                    }
+                });
+            }
+            EditorAction::SelectDown => {
+                self.with_cursors_mut(|cursors| {
+                    for cursor in cursors {
+                        cursor.extend_selection(|c| c.move_y_at(c.line.saturating_add(1)));
+                    }
+                });
+            }
+            EditorAction::SelectWord => {
+                self.with_cursors_mut(|cursors| {
+                    for cursor in cursors {
+                        cursor.select_word();
+                    }
+                });
+            }
+            EditorAction::SelectLine => {
+                self.with_cursors_mut(|cursors| {
+                    for cursor in cursors {
+                        cursor.select_line();
+                    }
+                });
+            }
+            EditorAction::SelectAll => {
+                self.select_all();
+            }
             EditorAction::DeleteChar => {
                 self.delete_character();
             }
+            EditorAction::Undo => {
+                self.undo();
+            }
+            EditorAction::Redo => {
+                self.redo();
+            }
+            EditorAction::Copy => {
+                self.copy();
+            }
+            EditorAction::Cut => {
+                self.cut();
+            }
+            EditorAction::Paste => {
+                self.paste();
+            }
+            EditorAction::Save => {
+                self.save();
+            }
+            EditorAction::SaveAs => {
+                self.start_save_as();
+            }
+            EditorAction::SaveAll => {
+                self.save_all();
+            }
+            EditorAction::NextTab => {
+                self.next_tab();
+            }
+            EditorAction::PreviousTab => {
+                self.previous_tab();
+            }
+            EditorAction::MoveTabLeft => {
+                self.move_tab(-1);
+            }
+            EditorAction::MoveTabRight => {
+                self.move_tab(1);
+            }
+            EditorAction::CloseTab => {
+                self.close_focused_tab();
+            }
+            EditorAction::SplitRight => {
+                self.split_pane(Axis::Horizontal);
+            }
+            EditorAction::SplitDown => {
+                self.split_pane(Axis::Vertical);
+            }
+            EditorAction::FocusNextPane => {
+                self.focus_next_pane();
+            }
+            EditorAction::FocusPreviousPane => {
+                self.focus_previous_pane();
+            }
+            EditorAction::ClosePane => {
+                self.close_pane();
+            }
+            EditorAction::ToggleExplorer => {
+                self.toggle_explorer();
+            }
+            EditorAction::RevealInExplorer => {
+                self.reveal_active_file();
+            }
+            EditorAction::ToggleProblems => {
+                self.toggle_problems();
+            }
+            EditorAction::TriggerCompletion => {
+                self.trigger_completion();
+            }
+            EditorAction::AcceptCompletion => {
+                self.accept_completion();
+            }
+            EditorAction::CancelCompletion => {
+                self.cancel_completion();
+            }
+            EditorAction::SelectNextCompletion => {
+                self.select_next_completion();
+            }
+            EditorAction::SelectPreviousCompletion => {
+                self.select_previous_completion();
+            }
+            EditorAction::NextTabstop => {
+                self.next_tabstop();
+            }
+            EditorAction::PreviousTabstop => {
+                self.previous_tabstop();
+            }
+            EditorAction::GoToDefinition => {
+                self.go_to_definition();
+            }
+            EditorAction::GoToTypeDefinition => {
+                self.go_to_type_definition();
+            }
+            EditorAction::FindReferences => {
+                self.find_references();
+            }
+            EditorAction::GoToImplementation => {
+                self.go_to_implementation();
+            }
+            EditorAction::NavigateBack => {
+                self.navigate_back();
+            }
+            EditorAction::NavigateForward => {
+                self.navigate_forward();
+            }
+            EditorAction::ShowHover => {
+                self.show_hover();
+            }
+            EditorAction::RenameSymbol => {
+                self.start_symbol_rename();
+            }
+            EditorAction::ShowCodeActions => {
+                self.show_code_actions();
+            }
+            EditorAction::Find => {
+                self.open_find(false);
+            }
+            EditorAction::Replace => {
+                self.open_find(true);
+            }
+            EditorAction::FindNext => {
+                self.find_next();
+            }
+            EditorAction::FindPrevious => {
+                self.find_previous();
+            }
+            EditorAction::CloseFind if self.completion.is_some() || self.snippet.is_some() => {
+                // Bound to Escape, which closes the completion popup first.
+                self.cancel_completion();
+            }
+            EditorAction::CloseFind => {
+                self.close_find();
+            }
+            EditorAction::ToggleSearch => {
+                self.toggle_search();
+            }
+            EditorAction::ShowCommandPalette => {
+                self.show_command_palette();
+            }
+            EditorAction::QuickOpen => {
+                self.toggle_quick_open();
+            }
         }
     }
 
@@ -87,6 +614,101 @@ impl super::EditorState {
             if let Some(cursors) = self.document_cursors.get_mut(focused_path) {
                 f(cursors)
             }
+            // Typing after moving the cursors starts a new undo step.
+            if let Some(document) = self.documents.lock().unwrap().get_mut(focused_path) {
+                document.break_undo_group();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::{
+        Deserializer,
+        de::{self, Visitor},
+    };
+
+    use super::*;
+    use crate::keybindings::Keybindings;
+
+    /// Fails with the variant names a derived `Deserialize` of an enum expects.
+    struct VariantNames;
+
+    #[derive(Debug)]
+    struct Names(&'static [&'static str]);
+
+    impl std::fmt::Display for Names {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "{:?}", self.0)
+        }
+    }
+
+    impl std::error::Error for Names {}
+
+    impl de::Error for Names {
+        fn custom<T: std::fmt::Display>(_msg: T) -> Self {
+            Self(&[])
+        }
+    }
+
+    impl<'de> Deserializer<'de> for VariantNames {
+        type Error = Names;
+
+        fn deserialize_any<V: Visitor<'de>>(self, _visitor: V) -> Result<V::Value, Names> {
+            Err(Names(&[]))
+        }
+
+        fn deserialize_enum<V: Visitor<'de>>(
+            self,
+            _name: &'static str,
+            variants: &'static [&'static str],
+            _visitor: V,
+        ) -> Result<V::Value, Names> {
+            Err(Names(variants))
+        }
+
+        serde::forward_to_deserialize_any! {
+            bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string bytes
+            byte_buf option unit unit_struct newtype_struct seq tuple tuple_struct map
+            struct identifier ignored_any
+        }
+    }
+
+    #[test]
+    fn all_lists_every_action_once() {
+        let Err(Names(names)) = EditorAction::deserialize(VariantNames) else {
+            panic!("EditorAction should be an enum");
+        };
+        assert!(!names.is_empty());
+        for name in names {
+            let action = serde_json::from_value::<EditorAction>(serde_json::json!(name)).unwrap();
+            assert!(
+                EditorAction::ALL.contains(&action),
+                "{name} is missing from ALL"
+            );
+        }
+        assert_eq!(EditorAction::ALL.len(), names.len(), "ALL has duplicates");
+    }
+
+    #[test]
+    fn actions_have_a_default_key_unless_meant_for_the_palette() {
+        // Like in most editors, Go to Type Definition is only in the command
+        // palette. The completion popup is driven by the keys of the cursor
+        // moves and Esc, while it is open.
+        let without_key = [
+            EditorAction::GoToTypeDefinition,
+            EditorAction::CancelCompletion,
+            EditorAction::SelectNextCompletion,
+            EditorAction::SelectPreviousCompletion,
+        ];
+        let keybindings = Keybindings::default();
+        for action in EditorAction::ALL {
+            assert_eq!(
+                keybindings.key_for(action).is_none(),
+                without_key.contains(action),
+                "{action:?}"
+            );
         }
     }
 }
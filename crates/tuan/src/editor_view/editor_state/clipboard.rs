@@ -0,0 +1,292 @@
+use std::{collections::HashMap, ops::Range};
+
+use crate::document::history::EditKind;
+
+/// What the cursors of a document copy, in document order.
+struct Clips {
+    /// Whole lines are copied when no cursor has a selection.
+    whole_lines: bool,
+    text: String,
+}
+
+impl super::EditorState {
+    /// Copies the selections of all cursors, one clipboard line per cursor.
+    ///
+    /// Without any selection, the lines of the cursors are copied instead.
+    pub fn copy(&mut self) {
+        if let Some(clips) = self.clips() {
+            self.set_clipboard_text(clips);
+        }
+    }
+
+    /// Copies like [`Self::copy`], then deletes what was copied.
+    pub fn cut(&mut self) {
+        let Some(clips) = self.clips() else {
+            return;
+        };
+        let whole_lines = clips.whole_lines;
+        self.set_clipboard_text(clips);
+
+        if whole_lines {
+            let lines = self.line_ranges();
+            self.edit_at_cursors(EditKind::Other, |_, cursor| {
+                Some((lines.get(&cursor.line)?.clone(), String::new()))
+            });
+        } else {
+            self.edit_at_cursors(EditKind::Other, |_, cursor| {
+                Some((cursor.get_selection_range()?, String::new()))
+            });
+        }
+    }
+
+    /// Pastes the clipboard at every cursor, replacing the selections.
+    ///
+    /// When the clipboard has exactly one line per cursor, each cursor gets its
+    /// own line, in document order.
+    ///
+    /// Whole lines copied without a selection are pasted above the line of each
+    /// cursor without a selection, which stays where it was in its line.
+    pub fn paste(&mut self) {
+        let Some(text) = self.clipboard.lock().unwrap().get_text() else {
+            return;
+        };
+        let whole_lines = self.whole_line_clip.as_ref() == Some(&text);
+        let text = text.replace("\r\n", "\n");
+        let line_starts = if whole_lines {
+            self.line_ranges()
+                .into_iter()
+                .map(|(line, range)| (line, range.start))
+                .collect()
+        } else {
+            HashMap::new()
+        };
+        let columns = self
+            .focused_cursors()
+            .iter()
+            .map(|c| c.get_selection_range().is_none().then_some(c.column))
+            .collect::<Vec<_>>();
+
+        let mut offsets = self
+            .focused_cursors()
+            .iter()
+            .map(|c| c.get_cursor_offset())
+            .collect::<Vec<_>>();
+        offsets.sort_unstable();
+
+        let lines = text
+            .strip_suffix('\n')
+            .unwrap_or(&text)
+            .split('\n')
+            .collect::<Vec<_>>();
+        let spread = offsets.len() > 1 && lines.len() == offsets.len();
+
+        self.edit_at_cursors(EditKind::Other, |offset, cursor| {
+            let selection = cursor.get_selection_range();
+            let line_start = line_starts
+                .get(&cursor.line)
+                .filter(|_| selection.is_none());
+            let range = match line_start {
+                Some(&start) => start..start,
+                None => selection.unwrap_or(offset..offset),
+            };
+            let mut text = if spread {
+                let index = offsets.partition_point(|&o| o < offset);
+                lines[index.min(lines.len() - 1)].to_string()
+            } else {
+                text.clone()
+            };
+            if line_start.is_some() && !text.ends_with('\n') {
+                text.push('\n');
+            }
+            Some((range, text))
+        });
+
+        // The cursors pasting whole lines end up at the start of their line,
+        // put them back where they were in it.
+        if whole_lines && let Some(path) = self.focused_document_path.clone() {
+            let positions = self
+                .focused_cursors()
+                .iter()
+                .zip(columns)
+                .map(|(c, column)| (c.line, column.unwrap_or(c.column)))
+                .collect::<Vec<_>>();
+            self.set_cursors(path, &positions);
+        }
+    }
+
+    /// Puts what was copied in the clipboard, remembering whether it is
+    /// whole lines to paste them above the cursor line.
+    fn set_clipboard_text(&mut self, clips: Clips) {
+        self.whole_line_clip = clips.whole_lines.then(|| clips.text.clone());
+        self.clipboard.lock().unwrap().set_text(clips.text);
+    }
+
+    fn clips(&self) -> Option<Clips> {
+        let path = self.focused_document_path.as_ref()?;
+        let cursors = self.focused_cursors();
+        if cursors.is_empty() {
+            return None;
+        }
+
+        let documents = self.documents.lock().unwrap();
+        let document = documents.get(path)?;
+
+        let whole_lines = cursors.iter().all(|c| c.get_selection_range().is_none());
+        let mut ranges = cursors
+            .iter()
+            .filter_map(|c| {
+                if whole_lines {
+                    Some(document.line_range(c.line))
+                } else {
+                    c.get_selection_range()
+                }
+            })
+            .collect::<Vec<_>>();
+        ranges.sort_by_key(|range| range.start);
+        // Cursors on the same line copy it once.
+        ranges.dedup();
+
+        let texts = ranges.iter().map(|range| document.text_in(range.clone()));
+        let text = if whole_lines {
+            // Lines keep their own line endings.
+            texts.collect()
+        } else {
+            texts.collect::<Vec<_>>().join("\n")
+        };
+
+        Some(Clips { whole_lines, text })
+    }
+
+    /// The range of each line holding a cursor of the focused document.
+    fn line_ranges(&self) -> HashMap<usize, Range<usize>> {
+        let Some(path) = self.focused_document_path.as_ref() else {
+            return HashMap::new();
+        };
+        let documents = self.documents.lock().unwrap();
+        let Some(document) = documents.get(path) else {
+            return HashMap::new();
+        };
+        self.focused_cursors()
+            .iter()
+            .map(|c| (c.line, document.line_range(c.line)))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::super::{EditorState, test_support::test_state};
+
+    /// An editor with `text` open in `a.txt`, and its path.
+    fn state_with(text: &str) -> (EditorState, PathBuf) {
+        let mut state = test_state(&[("a.txt", text)]);
+        let path = state.open_loaded("a.txt");
+        (state, path)
+    }
+
+    /// Replaces the cursors of `path` by one per `(anchor, head)` selection.
+    fn select(
+        state: &mut EditorState,
+        path: &PathBuf,
+        selections: &[((usize, usize), (usize, usize))],
+    ) {
+        let heads = selections.iter().map(|(_, head)| *head).collect::<Vec<_>>();
+        state.set_cursors(path.clone(), &heads);
+        for (cursor, (anchor, head)) in state
+            .document_cursors
+            .get_mut(path)
+            .unwrap()
+            .iter_mut()
+            .zip(selections)
+        {
+            cursor.select(*anchor, *head);
+        }
+    }
+
+    fn clipboard_text(state: &EditorState) -> Option<String> {
+        state.clipboard.lock().unwrap().get_text()
+    }
+
+    #[test]
+    fn copy_puts_one_line_per_cursor_in_document_order() {
+        let (mut state, path) = state_with("one two\nthree four\n");
+        select(&mut state, &path, &[((1, 0), (1, 5)), ((0, 0), (0, 3))]);
+
+        state.copy();
+        assert_eq!(clipboard_text(&state).as_deref(), Some("one\nthree"));
+        assert_eq!(state.text(&path), "one two\nthree four\n");
+    }
+
+    #[test]
+    fn cut_deletes_every_selection() {
+        let (mut state, path) = state_with("one two\nthree four\n");
+        select(&mut state, &path, &[((0, 0), (0, 4)), ((1, 0), (1, 6))]);
+
+        state.cut();
+        assert_eq!(clipboard_text(&state).as_deref(), Some("one \nthree "));
+        assert_eq!(state.text(&path), "two\nfour\n");
+        assert_eq!(state.cursor_offsets(&path), vec![0, 4]);
+    }
+
+    #[test]
+    fn cut_without_selection_deletes_the_cursor_lines() {
+        let (mut state, path) = state_with("one\ntwo\nthree\n");
+        state.set_cursors(path.clone(), &[(0, 1), (2, 2)]);
+
+        state.cut();
+        assert_eq!(clipboard_text(&state).as_deref(), Some("one\nthree\n"));
+        assert_eq!(state.text(&path), "two\n");
+    }
+
+    #[test]
+    fn paste_gives_each_cursor_its_line_when_the_counts_match() {
+        let (mut state, path) = state_with("a\nb\n");
+        state.set_cursors(path.clone(), &[(1, 1), (0, 1)]);
+        state.clipboard.lock().unwrap().set_text("1\n2".to_string());
+
+        state.paste();
+        assert_eq!(state.text(&path), "a1\nb2\n");
+    }
+
+    #[test]
+    fn paste_gives_every_cursor_all_lines_when_the_counts_differ() {
+        let (mut state, path) = state_with("a\nb\n");
+        state.set_cursors(path.clone(), &[(0, 1), (1, 1)]);
+        state
+            .clipboard
+            .lock()
+            .unwrap()
+            .set_text("1\n2\n3".to_string());
+
+        state.paste();
+        assert_eq!(state.text(&path), "a1\n2\n3\nb1\n2\n3\n");
+    }
+
+    #[test]
+    fn whole_lines_are_pasted_above_the_cursor_line() {
+        let (mut state, path) = state_with("one\ntwo\n");
+        state.set_cursors(path.clone(), &[(0, 1)]);
+        state.copy();
+        assert_eq!(clipboard_text(&state).as_deref(), Some("one\n"));
+
+        state.set_cursors(path.clone(), &[(1, 2)]);
+        state.paste();
+        assert_eq!(state.text(&path), "one\none\ntwo\n");
+        // The cursor stays where it was in its line, which moved down.
+        let cursors = state.get_document_cursors(&path).unwrap();
+        assert_eq!((cursors[0].line, cursors[0].column), (2, 2));
+    }
+
+    #[test]
+    fn whole_lines_replace_a_selection() {
+        let (mut state, path) = state_with("one\ntwo\n");
+        state.set_cursors(path.clone(), &[(0, 0)]);
+        state.copy();
+
+        select(&mut state, &path, &[((1, 0), (1, 3))]);
+        state.paste();
+        assert_eq!(state.text(&path), "one\none\n\n");
+    }
+}
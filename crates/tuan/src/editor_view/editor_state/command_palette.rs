@@ -0,0 +1,121 @@
+use super::action::EditorAction;
+use crate::fuzzy;
+
+/// How many actions run from the palette are remembered, to be listed first.
+const RECENT_ACTIONS_LIMIT: usize = 20;
+
+/// The command palette, listing the available actions that match what was typed.
+#[derive(Debug, Clone, Default)]
+pub struct CommandPalette {
+    pub input: String,
+    /// The matching actions, best first.
+    pub items: Vec<PaletteItem>,
+}
+
+/// An action of the command palette.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PaletteItem {
+    pub action: EditorAction,
+    /// The category and title of the action, e.g. "File: Save".
+    pub label: String,
+    /// The key bound to the action, if any.
+    pub key: Option<String>,
+}
+
+impl super::EditorState {
+    /// Opens the command palette, or closes it when already open.
+    pub fn show_command_palette(&mut self) {
+        if self.command_palette.take().is_some() {
+            return;
+        }
+        self.command_palette = Some(CommandPalette::default());
+        self.filter_command_palette();
+    }
+
+    pub fn close_command_palette(&mut self) {
+        self.command_palette = None;
+    }
+
+    pub fn set_command_palette_input(&mut self, input: String) {
+        if let Some(palette) = self.command_palette.as_mut() {
+            palette.input = input;
+        }
+        self.filter_command_palette();
+    }
+
+    /// Lists the available actions matching the input: the recently used
+    /// ones first when it is empty, the best matches first otherwise.
+    fn filter_command_palette(&mut self) {
+        let Some(input) = self.command_palette.as_ref().map(|p| p.input.clone()) else {
+            return;
+        };
+        let recent_rank = |action: &EditorAction| {
+            self.recent_actions
+                .iter()
+                .position(|recent| recent == action)
+                .unwrap_or(usize::MAX)
+        };
+
+        let mut matches = EditorAction::ALL
+            .iter()
+            .enumerate()
+            .filter(|(_, action)| {
+                **action != EditorAction::ShowCommandPalette && action.is_available(self)
+            })
+            .filter_map(|(index, action)| {
+                let label = format!("{}: {}", action.category().title(), action.title());
+                let score = if input.is_empty() {
+                    0
+                } else {
+                    fuzzy::fuzzy_match(&input, &label)?.score
+                };
+                Some((score, recent_rank(action), index, label))
+            })
+            .collect::<Vec<_>>();
+        matches.sort_by(
+            |(a_score, a_rank, a_index, _), (b_score, b_rank, b_index, _)| {
+                b_score
+                    .cmp(a_score)
+                    .then(a_rank.cmp(b_rank))
+                    .then(a_index.cmp(b_index))
+            },
+        );
+
+        let items = matches
+            .into_iter()
+            .map(|(_, _, index, label)| {
+                let action = EditorAction::ALL[index].clone();
+                PaletteItem {
+                    key: self.keybindings.key_for(&action).map(str::to_string),
+                    action,
+                    label,
+                }
+            })
+            .collect();
+        if let Some(palette) = self.command_palette.as_mut() {
+            palette.items = items;
+        }
+    }
+
+    /// Runs the best match of the palette, e.g. when Enter is pressed in its input.
+    pub fn run_first_palette_item(&mut self) {
+        let Some(action) = self
+            .command_palette
+            .as_ref()
+            .and_then(|palette| palette.items.first())
+            .map(|item| item.action.clone())
+        else {
+            return;
+        };
+        self.run_palette_action(action);
+    }
+
+    /// Closes the palette and runs `action`, remembering it as recently used.
+    pub fn run_palette_action(&mut self, action: EditorAction) {
+        self.command_palette = None;
+        self.recent_actions.retain(|recent| *recent != action);
+        self.recent_actions.insert(0, action.clone());
+        self.recent_actions.truncate(RECENT_ACTIONS_LIMIT);
+        self.handle_action(&action);
+    }
+}
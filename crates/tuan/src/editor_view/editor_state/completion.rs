@@ -0,0 +1,588 @@
+use std::{ops::Range, path::PathBuf, sync::Arc};
+
+use lsp_types::{CompletionItem, CompletionResponse, CompletionTextEdit, InsertTextFormat};
+
+use super::{PaneId, lsp::next_request_id};
+use crate::{
+    document::{history::EditKind, snippet::Snippet},
+    fuzzy,
+};
+
+/// The most items shown at once by the popup.
+const VISIBLE_ITEMS: usize = 10;
+
+/// The completion popup, listing the items of the last request that match
+/// what was typed since.
+#[derive(Debug, Clone)]
+pub struct Completion {
+    request_id: usize,
+    pub pane: PaneId,
+    pub path: PathBuf,
+    /// The offset of the start of the completed word, at the first cursor.
+    start: usize,
+    /// The text typed since `start`, the items are filtered with.
+    pub input: String,
+    items: Arc<Vec<CompletionItem>>,
+    /// The indices in `items` of those matching `input`, best first.
+    matches: Vec<usize>,
+    selected: usize,
+}
+
+impl Completion {
+    /// Filters and sorts the items with `input`, selecting the best match.
+    fn filter(&mut self) {
+        let mut matches = self
+            .items
+            .iter()
+            .enumerate()
+            .filter_map(|(index, item)| {
+                let text = item.filter_text.as_deref().unwrap_or(&item.label);
+                let score = fuzzy::fuzzy_match(&self.input, text)?.score;
+                Some((index, score))
+            })
+            .collect::<Vec<_>>();
+        matches.sort_by(|(a, a_score), (b, b_score)| {
+            let sort_text = |index: &usize| {
+                let item = &self.items[*index];
+                item.sort_text.clone().unwrap_or_else(|| item.label.clone())
+            };
+            b_score
+                .cmp(a_score)
+                .then_with(|| sort_text(a).cmp(&sort_text(b)))
+        });
+        self.matches = matches.into_iter().map(|(index, _)| index).collect();
+        self.selected = 0;
+    }
+
+    fn selected_item(&self) -> Option<&CompletionItem> {
+        self.items.get(*self.matches.get(self.selected)?)
+    }
+
+    /// The lines of the popup, scrolled to show the selected item, and the
+    /// index of the selected one among them.
+    pub fn visible_lines(&self) -> (Vec<String>, Option<usize>) {
+        let first = self.selected.saturating_sub(VISIBLE_ITEMS - 1);
+        let lines = self
+            .matches
+            .iter()
+            .skip(first)
+            .take(VISIBLE_ITEMS)
+            .map(|&index| {
+                let item = &self.items[index];
+                match &item.detail {
+                    Some(detail) => format!("{}  {}", item.label, detail),
+                    None => item.label.clone(),
+                }
+            })
+            .collect::<Vec<_>>();
+        let selected = (!lines.is_empty()).then_some(self.selected - first);
+        (lines, selected)
+    }
+}
+
+/// The tabstops of an inserted snippet, visited in turn by `NextTabstop`.
+#[derive(Debug, Clone)]
+pub struct SnippetSession {
+    path: PathBuf,
+    /// The offset ranges of each tabstop, at all the cursors the snippet was inserted at.
+    tabstops: Vec<Vec<Range<usize>>>,
+    current: usize,
+}
+
+impl SnippetSession {
+    /// Moves the tabstops along `edits`, made in offsets of the text before
+    /// them. A tabstop grows with the text typed at its end.
+    fn shift(&mut self, edits: &[(Range<usize>, String)]) {
+        let mut edits = edits.to_vec();
+        edits.sort_by_key(|(range, _)| range.start);
+        for range in self.tabstops.iter_mut().flatten() {
+            let start = shift_offset(range.start, &edits, false);
+            let end = shift_offset(range.end, &edits, true);
+            *range = start..end.max(start);
+        }
+    }
+}
+
+/// Maps an offset of the text before `edits` (sorted by offset) to the text
+/// after them. An insertion at the offset moves it only when `inclusive`.
+pub(super) fn shift_offset(
+    offset: usize,
+    edits: &[(Range<usize>, String)],
+    inclusive: bool,
+) -> usize {
+    let mut shift = 0isize;
+    for (range, text) in edits {
+        if range.start > offset || (range.start == offset && !inclusive) {
+            break;
+        }
+        if range.end > offset {
+            // Inside the replaced text.
+            let start = range.start.saturating_add_signed(shift);
+            return if inclusive { start + text.len() } else { start };
+        }
+        shift += text.len() as isize - range.len() as isize;
+    }
+    offset.saturating_add_signed(shift)
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+/// Characters after which completions are requested even without a word, as in `foo.` or `Foo::`.
+fn is_trigger_char(c: char) -> bool {
+    matches!(c, '.' | ':')
+}
+
+impl super::EditorState {
+    /// Requests completions for the word before the first cursor.
+    pub fn trigger_completion(&mut self) {
+        let Some(offset) = self
+            .focused_cursors()
+            .first()
+            .map(|c| c.get_cursor_offset())
+        else {
+            return;
+        };
+        let Some(start) = self.word_start(offset) else {
+            return;
+        };
+        self.request_completion(start, offset);
+    }
+
+    /// Updates the popup after the text was edited at the cursors, `typed`
+    /// being the inserted character, if any.
+    pub(super) fn update_completion(&mut self, typed: Option<char>) {
+        let Some(path) = self.focused_document_path.clone() else {
+            return;
+        };
+        let Some(offset) = self
+            .focused_cursors()
+            .first()
+            .map(|c| c.get_cursor_offset())
+        else {
+            return;
+        };
+
+        if let Some(completion) = self.completion.as_mut()
+            && completion.path == path
+            && completion.start <= offset
+            && typed.is_none_or(is_word_char)
+        {
+            let input = self
+                .documents
+                .lock()
+                .unwrap()
+                .get(&path)
+                .map(|document| document.text_in(completion.start..offset))
+                .unwrap_or_default();
+            if input.chars().all(is_word_char) {
+                completion.input = input;
+                completion.filter();
+                return;
+            }
+        }
+
+        self.completion = None;
+        match typed {
+            Some(c) if is_word_char(c) => {
+                if let Some(start) = self.word_start(offset) {
+                    self.request_completion(start, offset);
+                }
+            }
+            Some(c) if is_trigger_char(c) => self.request_completion(offset, offset),
+            _ => {}
+        }
+    }
+
+    fn request_completion(&mut self, start: usize, offset: usize) {
+        let Some(path) = self.focused_document_path.clone() else {
+            return;
+        };
+        let Some((input, position)) = self.documents.lock().unwrap().get(&path).map(|document| {
+            (
+                document.text_in(start..offset),
+                document.lsp_position(offset),
+            )
+        }) else {
+            return;
+        };
+
+        let request_id = next_request_id();
+        self.completion = Some(Completion {
+            request_id,
+            pane: self.active_pane,
+            path: path.clone(),
+            start,
+            input: input.clone(),
+            items: Arc::new(Vec::new()),
+            matches: Vec::new(),
+            selected: 0,
+        });
+        self.proxy
+            .proxy_rpc
+            .completion(request_id, path, input, position);
+    }
+
+    /// Shows the items of a completion response, unless a newer request was made since.
+    pub(super) fn set_completion_items(&mut self, request_id: usize, response: CompletionResponse) {
+        let Some(completion) = self
+            .completion
+            .as_mut()
+            .filter(|completion| completion.request_id == request_id)
+        else {
+            return;
+        };
+        completion.items = Arc::new(match response {
+            CompletionResponse::Array(items) => items,
+            CompletionResponse::List(list) => list.items,
+        });
+        completion.filter();
+    }
+
+    /// The offsets of the word around `offset` in the focused document.
+    pub(super) fn word_range(&self, offset: usize) -> Option<Range<usize>> {
+        let start = self.word_start(offset)?;
+        let path = self.focused_document_path.as_ref()?;
+        let documents = self.documents.lock().unwrap();
+        let document = documents.get(path)?;
+        let line_end = document
+            .line_range(document.offset_to_position(offset).0)
+            .end;
+        let word_len = document
+            .text_in(offset..line_end)
+            .chars()
+            .take_while(|c| is_word_char(*c))
+            .map(char::len_utf8)
+            .sum::<usize>();
+        Some(start..offset + word_len)
+    }
+
+    /// The offset where the word ending at `offset` starts.
+    fn word_start(&self, offset: usize) -> Option<usize> {
+        let path = self.focused_document_path.as_ref()?;
+        let documents = self.documents.lock().unwrap();
+        let document = documents.get(path)?;
+        let line_start = document
+            .line_range(document.offset_to_position(offset).0)
+            .start;
+        let word_len = document
+            .text_in(line_start..offset)
+            .chars()
+            .rev()
+            .take_while(|c| is_word_char(*c))
+            .map(char::len_utf8)
+            .sum::<usize>();
+        Some(offset - word_len)
+    }
+
+    pub fn select_next_completion(&mut self) {
+        if let Some(completion) = self.completion.as_mut()
+            && !completion.matches.is_empty()
+        {
+            completion.selected = (completion.selected + 1) % completion.matches.len();
+        }
+    }
+
+    pub fn select_previous_completion(&mut self) {
+        if let Some(completion) = self.completion.as_mut()
+            && !completion.matches.is_empty()
+        {
+            let count = completion.matches.len();
+            completion.selected = (completion.selected + count - 1) % count;
+        }
+    }
+
+    /// Closes the popup and ends the snippet being filled in, if any.
+    pub fn cancel_completion(&mut self) {
+        self.completion = None;
+        self.snippet = None;
+    }
+
+    /// Inserts the selected item at every cursor, replacing the word typed so far.
+    ///
+    /// The item's text edit, if any, is applied around each cursor as it is
+    /// around the first one. Snippets are expanded and their first tabstop selected.
+    pub fn accept_completion(&mut self) {
+        let Some(completion) = self.completion.take() else {
+            return;
+        };
+        let Some(item) = completion.selected_item().cloned() else {
+            return;
+        };
+        let path = completion.path;
+        if self.focused_document_path.as_ref() != Some(&path) {
+            return;
+        }
+        let cursors = self
+            .document_cursors
+            .get(&path)
+            .cloned()
+            .unwrap_or_default();
+        let Some(primary_offset) = cursors.first().map(|c| c.get_cursor_offset()) else {
+            return;
+        };
+
+        let mut documents = self.documents.lock().unwrap();
+        let Some(document) = documents.get_mut(&path) else {
+            return;
+        };
+
+        let (range, new_text) = match &item.text_edit {
+            Some(CompletionTextEdit::Edit(edit)) => (
+                document.offset_of_position(&edit.range.start)
+                    ..document.offset_of_position(&edit.range.end),
+                edit.new_text.clone(),
+            ),
+            Some(CompletionTextEdit::InsertAndReplace(edit)) => (
+                document.offset_of_position(&edit.replace.start)
+                    ..document.offset_of_position(&edit.replace.end),
+                edit.new_text.clone(),
+            ),
+            None => (
+                completion.start..primary_offset,
+                item.insert_text
+                    .clone()
+                    .unwrap_or_else(|| item.label.clone()),
+            ),
+        };
+        // The replaced range relative to the first cursor, to replace the
+        // same around the other cursors.
+        let start_delta = range.start as isize - primary_offset as isize;
+        let end_delta = range.end as isize - primary_offset as isize;
+
+        let snippet = if item.insert_text_format == Some(InsertTextFormat::SNIPPET) {
+            Snippet::parse(&new_text)
+        } else {
+            Snippet {
+                text: new_text,
+                tabstops: Vec::new(),
+            }
+        };
+
+        let mut edits = vec![(range, snippet.text.clone())];
+        edits.extend(cursors[1..].iter().map(|cursor| {
+            let offset = cursor.get_cursor_offset();
+            let start = offset.saturating_add_signed(start_delta);
+            let end = offset.saturating_add_signed(end_delta).max(start);
+            (start..end, snippet.text.clone())
+        }));
+        // Additional edits, e.g. an import, are not repeated at each cursor.
+        for edit in item.additional_text_edits.iter().flatten() {
+            edits.push((
+                document.offset_of_position(&edit.range.start)
+                    ..document.offset_of_position(&edit.range.end),
+                edit.new_text.clone(),
+            ));
+        }
+
+        drop(documents);
+
+        let cursors_before = cursors.iter().map(|c| (c.line, c.column)).collect();
+        let Some((carets, starts)) = self.edit_with_parked_cursors(&path, |document| {
+            let carets = document.edit(edits, EditKind::Other, cursors_before);
+            document.break_undo_group();
+            // Where the snippet text starts, at each cursor.
+            let starts = carets[..cursors.len()]
+                .iter()
+                .map(|&(line, column)| {
+                    document.line_range(line).start + column - snippet.text.len()
+                })
+                .collect::<Vec<_>>();
+            (carets, starts)
+        }) else {
+            return;
+        };
+
+        self.update_styles_with_syntax(path.clone());
+        self.set_cursors(path.clone(), &carets[..cursors.len()]);
+
+        if !snippet.tabstops.is_empty() {
+            let tabstops = snippet
+                .tabstops
+                .iter()
+                .map(|ranges| {
+                    starts
+                        .iter()
+                        .flat_map(|start| {
+                            ranges
+                                .iter()
+                                .map(move |range| start + range.start..start + range.end)
+                        })
+                        .collect()
+                })
+                .collect();
+            self.snippet = Some(SnippetSession {
+                path,
+                tabstops,
+                current: 0,
+            });
+            self.select_tabstop();
+        }
+    }
+
+    /// Accepts the selected completion if the popup is open, or else moves to
+    /// the next tabstop of the snippet being filled in.
+    pub fn next_tabstop(&mut self) {
+        if self.completion.is_some() {
+            self.accept_completion();
+            return;
+        }
+        if let Some(snippet) = self.snippet.as_mut() {
+            snippet.current += 1;
+            self.select_tabstop();
+        }
+    }
+
+    pub fn previous_tabstop(&mut self) {
+        if let Some(snippet) = self.snippet.as_mut() {
+            snippet.current = snippet.current.saturating_sub(1);
+            self.select_tabstop();
+        }
+    }
+
+    /// Selects the ranges of the current tabstop, one cursor each, and ends
+    /// the snippet once its last tabstop is reached.
+    fn select_tabstop(&mut self) {
+        let Some(snippet) = self.snippet.clone() else {
+            return;
+        };
+        if self.focused_document_path.as_ref() != Some(&snippet.path) {
+            self.snippet = None;
+            return;
+        }
+        let Some(ranges) = snippet.tabstops.get(snippet.current) else {
+            self.snippet = None;
+            return;
+        };
+        if snippet.current + 1 == snippet.tabstops.len() {
+            self.snippet = None;
+        }
+
+        let Some(positions) = self
+            .documents
+            .lock()
+            .unwrap()
+            .get(&snippet.path)
+            .map(|document| {
+                ranges
+                    .iter()
+                    .map(|range| {
+                        (
+                            document.offset_to_position(range.start),
+                            document.offset_to_position(range.end),
+                        )
+                    })
+                    .collect::<Vec<_>>()
+            })
+        else {
+            return;
+        };
+
+        let heads = positions.iter().map(|(_, head)| *head).collect::<Vec<_>>();
+        self.set_cursors(snippet.path.clone(), &heads);
+        if let Some(cursors) = self.document_cursors.get_mut(&snippet.path) {
+            for (cursor, (anchor, head)) in cursors.iter_mut().zip(positions) {
+                if anchor != head {
+                    cursor.select(anchor, head);
+                }
+            }
+        }
+    }
+
+    /// Keeps the snippet tabstops in place when the cursors edit `path`.
+    pub(super) fn shift_snippet(&mut self, path: &PathBuf, edits: &[(Range<usize>, String)]) {
+        if let Some(snippet) = self.snippet.as_mut()
+            && snippet.path == *path
+        {
+            snippet.shift(edits);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use lsp_types::{Position, TextEdit};
+
+    use super::super::{EditorState, test_support::test_state};
+    use super::*;
+
+    /// A completion at `start` offering only `item`.
+    fn completion(
+        state: &EditorState,
+        path: &PathBuf,
+        start: usize,
+        item: CompletionItem,
+    ) -> Completion {
+        let mut completion = Completion {
+            request_id: 0,
+            pane: state.active_pane,
+            path: path.clone(),
+            start,
+            input: String::new(),
+            items: Arc::new(vec![item]),
+            matches: Vec::new(),
+            selected: 0,
+        };
+        completion.filter();
+        completion
+    }
+
+    #[test]
+    fn accepting_replaces_the_word_at_each_cursor() {
+        let mut state = test_state(&[("a.txt", "fo\nfo\n")]);
+        let path = state.open_loaded("a.txt");
+        state.set_cursors(path.clone(), &[(0, 2), (1, 2)]);
+        let item = CompletionItem {
+            label: "foo".to_string(),
+            ..Default::default()
+        };
+        state.completion = Some(completion(&state, &path, 0, item));
+
+        state.accept_completion();
+        assert_eq!(state.text(&path), "foo\nfoo\n");
+        assert_eq!(state.cursor_offsets(&path), vec![3, 7]);
+    }
+
+    #[test]
+    fn accepting_replaces_the_range_of_the_text_edit_as_given() {
+        let mut state = test_state(&[("a.txt", "fo\nfo\n")]);
+        let path = state.open_loaded("a.txt");
+        state.set_cursors(path.clone(), &[(0, 2), (1, 2)]);
+        // The edit ends before the cursor: what follows it is kept.
+        let item = CompletionItem {
+            label: "foo".to_string(),
+            text_edit: Some(CompletionTextEdit::Edit(TextEdit {
+                range: lsp_types::Range::new(Position::new(0, 0), Position::new(0, 1)),
+                new_text: "foo".to_string(),
+            })),
+            ..Default::default()
+        };
+        state.completion = Some(completion(&state, &path, 0, item));
+
+        state.accept_completion();
+        assert_eq!(state.text(&path), "fooo\nfooo\n");
+    }
+
+    #[test]
+    fn shift_offset_moves_offsets_after_the_edits() {
+        let edits = [(2..4, "xyz".to_string()), (6..6, "ab".to_string())];
+
+        assert_eq!(shift_offset(1, &edits, false), 1);
+        assert_eq!(shift_offset(2, &edits, false), 2);
+        assert_eq!(shift_offset(4, &edits, false), 5);
+        assert_eq!(shift_offset(6, &edits, false), 7);
+        assert_eq!(shift_offset(8, &edits, false), 11);
+    }
+
+    #[test]
+    fn shift_offset_inclusive_moves_past_insertions() {
+        let edits = [(2..4, "xyz".to_string()), (6..6, "ab".to_string())];
+
+        // Inside a replaced range: at its start, or after its new text.
+        assert_eq!(shift_offset(3, &edits, false), 2);
+        assert_eq!(shift_offset(3, &edits, true), 5);
+        assert_eq!(shift_offset(2, &edits, true), 5);
+        assert_eq!(shift_offset(6, &edits, true), 9);
+    }
+}
@@ -6,6 +6,14 @@ impl super::EditorState {
         self.document_cursors.get(path).cloned()
     }
 
+    /// The cursors of the focused document.
+    pub fn focused_cursors(&self) -> &[cursor::Cursor] {
+        self.focused_document_path
+            .as_ref()
+            .and_then(|path| self.document_cursors.get(path))
+            .map_or(&[], Vec::as_slice)
+    }
+
     pub fn add_cursor(&mut self, path: PathBuf, position: &(usize, usize)) {
         self.document_cursors
             .entry(path)
@@ -19,7 +27,105 @@ impl super::EditorState {
             ));
     }
 
+    /// Moves the cursors of every pane on `path` into its text, e.g. the ones
+    /// placed at a position given before it was loaded.
+    pub(super) fn clamp_cursors(&mut self, path: &PathBuf) {
+        let documents = self.documents.lock().unwrap();
+        let Some(document) = documents.get(path) else {
+            return;
+        };
+        let parked = self
+            .parked_panes
+            .values_mut()
+            .flat_map(|pane| pane.document_cursors_mut(path));
+        for cursor in self
+            .document_cursors
+            .get_mut(path)
+            .into_iter()
+            .flatten()
+            .chain(parked)
+        {
+            (cursor.line, cursor.column) = document.clamp_position((cursor.line, cursor.column));
+            cursor.anchor = cursor.anchor.map(|anchor| document.clamp_position(anchor));
+        }
+    }
+
+    /// Replaces the cursors of `path` by one cursor per `(line, column)` position.
+    pub fn set_cursors(&mut self, path: PathBuf, positions: &[(usize, usize)]) {
+        let cursors = positions
+            .iter()
+            .map(|(line, column)| {
+                let mut cursor = cursor::Cursor::new(
+                    *line,
+                    *column,
+                    self.documents.clone(),
+                    Some(path.clone()),
+                    self.config.clone(),
+                );
+                cursor.blink_state = cursor::BlinkState::Move;
+                cursor
+            })
+            .collect();
+        self.document_cursors.insert(path, cursors);
+    }
+
+    /// Replaces the cursors of the focused document by a single one selecting all its text.
+    pub fn select_all(&mut self) {
+        let Some(document) = self.get_focused_document() else {
+            return;
+        };
+        let end = document.offset_to_position(document.buffer.len());
+
+        self.set_cursors(document.path.clone(), &[end]);
+        if let Some(cursor) = self
+            .document_cursors
+            .get_mut(&document.path)
+            .and_then(|cursors| cursors.first_mut())
+        {
+            cursor.select((0, 0), end);
+        }
+    }
+
+    /// Replaces the cursors of `path` by a single one selecting the whole lines
+    /// from `anchor_line` to `line`, its head on the side of `line`.
+    pub fn select_lines(&mut self, path: PathBuf, anchor_line: usize, line: usize) {
+        let Some((anchor, head)) = self.documents.lock().unwrap().get(&path).map(|document| {
+            let last_line = document.count_lines().saturating_sub(1);
+            let (anchor_line, line) = (anchor_line.min(last_line), line.min(last_line));
+            let end_of = |line: usize| {
+                if line < last_line {
+                    (line + 1, 0)
+                } else {
+                    (line, document.get_line_length(line))
+                }
+            };
+            if anchor_line <= line {
+                ((anchor_line, 0), end_of(line))
+            } else {
+                (end_of(anchor_line), (line, 0))
+            }
+        }) else {
+            return;
+        };
+
+        self.line_selection_anchor = Some(anchor_line);
+        self.set_cursors(path.clone(), &[head]);
+        if let Some(cursor) = self
+            .document_cursors
+            .get_mut(&path)
+            .and_then(|cursors| cursors.first_mut())
+        {
+            cursor.select(anchor, head);
+        }
+    }
+
+    /// Stops extending the selection of whole lines, when the pointer is released.
+    pub fn end_line_selection(&mut self) {
+        self.line_selection_anchor = None;
+    }
+
     pub fn clear_cursors(&mut self, path: PathBuf) {
+        self.completion = None;
         if let Some(cursors) = self.document_cursors.get_mut(&path) {
             cursors.clear();
         }
@@ -0,0 +1,315 @@
+use std::{ops::Range, path::PathBuf, sync::Arc};
+
+use lsp_types::{DiagnosticSeverity, PublishDiagnosticsParams};
+
+/// A diagnostic of an open document, located by buffer offsets.
+#[derive(Debug, Clone)]
+pub struct DiagnosticSpan {
+    pub range: Range<usize>,
+    /// The line the diagnostic starts on.
+    pub line: usize,
+    pub severity: DiagnosticSeverity,
+    pub message: String,
+}
+
+/// A row of the problems panel.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Problem {
+    pub path: PathBuf,
+    /// The LSP position of the diagnostic, as `(line, character)`.
+    pub position: (usize, usize),
+    pub severity: DiagnosticSeverity,
+    pub message: String,
+}
+
+impl super::EditorState {
+    /// Replaces the diagnostics of a file with the ones published by a language server.
+    pub(super) fn set_diagnostics(&mut self, params: PublishDiagnosticsParams) {
+        let Ok(path) = params.uri.to_file_path() else {
+            tracing::debug!("Ignoring diagnostics of {}", params.uri);
+            return;
+        };
+        if params.diagnostics.is_empty() {
+            self.diagnostics.remove(&path);
+        } else {
+            self.diagnostics.insert(path, Arc::new(params.diagnostics));
+        }
+    }
+
+    /// The diagnostics of the open document at `path`, sorted by offset.
+    pub fn document_diagnostics(&self, path: &PathBuf) -> Vec<DiagnosticSpan> {
+        let Some(diagnostics) = self.diagnostics.get(path) else {
+            return Vec::new();
+        };
+        let documents = self.documents.lock().unwrap();
+        let Some(document) = documents.get(path) else {
+            return Vec::new();
+        };
+
+        let mut spans = diagnostics
+            .iter()
+            .map(|diagnostic| {
+                let start = document.offset_of_position(&diagnostic.range.start);
+                let end = document.offset_of_position(&diagnostic.range.end);
+                DiagnosticSpan {
+                    range: start..end.max(start),
+                    line: document.offset_to_position(start).0,
+                    severity: diagnostic.severity.unwrap_or(DiagnosticSeverity::ERROR),
+                    message: diagnostic.message.clone(),
+                }
+            })
+            .collect::<Vec<_>>();
+        spans.sort_by_key(|span| (span.range.start, span.severity));
+        spans
+    }
+
+    /// The messages of the diagnostics of `path` under `(line, column)`.
+    pub(super) fn diagnostics_at(
+        &self,
+        path: &PathBuf,
+        (line, column): (usize, usize),
+    ) -> Vec<String> {
+        let Some(offset) = self
+            .documents
+            .lock()
+            .unwrap()
+            .get(path)
+            .map(|document| document.line_range(line).start + column)
+        else {
+            return Vec::new();
+        };
+
+        self.document_diagnostics(path)
+            .into_iter()
+            .filter(|span| {
+                span.range.contains(&offset)
+                    || (span.range.is_empty() && span.range.start == offset)
+            })
+            .map(|span| span.message)
+            .collect()
+    }
+
+    /// All the diagnostics of the workspace, by file and position.
+    pub fn problems(&self) -> Vec<Problem> {
+        let mut problems = self
+            .diagnostics
+            .iter()
+            .flat_map(|(path, diagnostics)| {
+                diagnostics.iter().map(|diagnostic| Problem {
+                    path: path.clone(),
+                    position: (
+                        diagnostic.range.start.line as usize,
+                        diagnostic.range.start.character as usize,
+                    ),
+                    severity: diagnostic.severity.unwrap_or(DiagnosticSeverity::ERROR),
+                    message: diagnostic.message.clone(),
+                })
+            })
+            .collect::<Vec<_>>();
+        problems.sort_by(|a, b| (&a.path, a.position).cmp(&(&b.path, b.position)));
+        problems
+    }
+
+    /// The number of errors and warnings in the workspace.
+    pub fn problem_counts(&self) -> (usize, usize) {
+        self.diagnostics
+            .values()
+            .flat_map(|diagnostics| diagnostics.iter())
+            .fold((0, 0), |(errors, warnings), diagnostic| {
+                match diagnostic.severity.unwrap_or(DiagnosticSeverity::ERROR) {
+                    DiagnosticSeverity::ERROR => (errors + 1, warnings),
+                    DiagnosticSeverity::WARNING => (errors, warnings + 1),
+                    _ => (errors, warnings),
+                }
+            })
+    }
+
+    pub fn toggle_problems(&mut self) {
+        self.problems_visible = !self.problems_visible;
+    }
+
+    /// Opens the file of `problem` with the cursor on it.
+    pub fn jump_to_problem(&mut self, problem: Problem) {
+        let (line, character) = problem.position;
+        let position = self.editor_position(
+            &problem.path,
+            lsp_types::Position {
+                line: line as u32,
+                character: character as u32,
+            },
+        );
+        self.navigate_to(problem.path, position);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use lsp_types::{
+        Diagnostic, DiagnosticSeverity, Position, PublishDiagnosticsParams, Range, Url,
+    };
+    use tuan_rpc::core::CoreNotification;
+
+    use super::super::{EditorState, test_support::test_state};
+
+    fn diagnostic(line: u32, character: u32, severity: DiagnosticSeverity) -> Diagnostic {
+        let position = Position { line, character };
+        Diagnostic {
+            range: Range {
+                start: position,
+                end: Position {
+                    line,
+                    character: character + 1,
+                },
+            },
+            severity: Some(severity),
+            message: format!("{line}:{character}"),
+            ..Default::default()
+        }
+    }
+
+    /// Publishes `diagnostics` from the proxy as a language server does, then
+    /// handles the notification.
+    fn publish(state: &mut EditorState, path: &PathBuf, diagnostics: Vec<Diagnostic>) {
+        state
+            .proxy
+            .core_rpc
+            .notification(CoreNotification::PublishDiagnostics {
+                diagnostics: PublishDiagnosticsParams {
+                    uri: Url::from_file_path(path).unwrap(),
+                    diagnostics,
+                    version: None,
+                },
+            });
+        let notification = state.handle_next_notification();
+        assert!(matches!(
+            notification,
+            CoreNotification::PublishDiagnostics { .. }
+        ));
+    }
+
+    #[test]
+    fn diagnostics_are_stored_per_path() {
+        let mut state = test_state(&[("a.rs", "fn a() {}\n"), ("b.rs", "fn b() {}\n")]);
+        let a = state.workspace_path.join("a.rs");
+        let b = state.workspace_path.join("b.rs");
+
+        publish(
+            &mut state,
+            &a,
+            vec![
+                diagnostic(0, 0, DiagnosticSeverity::ERROR),
+                diagnostic(0, 3, DiagnosticSeverity::WARNING),
+            ],
+        );
+        publish(
+            &mut state,
+            &b,
+            vec![diagnostic(0, 3, DiagnosticSeverity::ERROR)],
+        );
+        assert_eq!(state.diagnostics[&a].len(), 2);
+        assert_eq!(state.diagnostics[&b].len(), 1);
+        assert_eq!(state.problem_counts(), (2, 1));
+
+        // A new publication replaces the diagnostics of its file only.
+        publish(
+            &mut state,
+            &a,
+            vec![diagnostic(0, 4, DiagnosticSeverity::WARNING)],
+        );
+        assert_eq!(state.problem_counts(), (1, 1));
+
+        // An empty one clears them.
+        publish(&mut state, &a, Vec::new());
+        assert!(!state.diagnostics.contains_key(&a));
+        assert_eq!(state.problem_counts(), (1, 0));
+    }
+
+    #[test]
+    fn document_diagnostics_are_located_in_the_document() {
+        let mut state = test_state(&[("a.rs", "fn a() {}\nfn b() {}\n")]);
+        let a = state.open_loaded("a.rs");
+
+        publish(
+            &mut state,
+            &a,
+            vec![
+                diagnostic(1, 3, DiagnosticSeverity::WARNING),
+                diagnostic(0, 3, DiagnosticSeverity::ERROR),
+            ],
+        );
+        let spans = state.document_diagnostics(&a);
+        assert_eq!(
+            spans
+                .iter()
+                .map(|span| (span.range.clone(), span.line))
+                .collect::<Vec<_>>(),
+            vec![(3..4, 0), (13..14, 1)]
+        );
+    }
+
+    #[test]
+    fn problems_are_sorted_by_path_then_position() {
+        let mut state = test_state(&[("a.rs", "fn a() {}\n"), ("b.rs", "fn b() {}\n")]);
+        let a = state.workspace_path.join("a.rs");
+        let b = state.workspace_path.join("b.rs");
+
+        publish(
+            &mut state,
+            &b,
+            vec![diagnostic(0, 0, DiagnosticSeverity::ERROR)],
+        );
+        publish(
+            &mut state,
+            &a,
+            vec![
+                diagnostic(2, 0, DiagnosticSeverity::ERROR),
+                diagnostic(0, 5, DiagnosticSeverity::WARNING),
+                diagnostic(0, 1, DiagnosticSeverity::ERROR),
+            ],
+        );
+        let problems = state
+            .problems()
+            .into_iter()
+            .map(|problem| (problem.path, problem.position))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            problems,
+            vec![
+                (a.clone(), (0, 1)),
+                (a.clone(), (0, 5)),
+                (a, (2, 0)),
+                (b, (0, 0)),
+            ]
+        );
+    }
+
+    #[test]
+    fn jumping_to_a_problem_opens_its_file_at_its_position() {
+        let mut state = test_state(&[("a.rs", "fn a() {}\n"), ("b.rs", "fn b() {}\nfn c() {}\n")]);
+        let a = state.open_loaded("a.rs");
+        let b = state.workspace_path.join("b.rs");
+
+        publish(
+            &mut state,
+            &b,
+            vec![diagnostic(1, 3, DiagnosticSeverity::ERROR)],
+        );
+        let problem = state.problems().remove(0);
+        state.jump_to_problem(problem);
+
+        assert_eq!(state.focused_document_path, Some(b.clone()));
+        let cursors = state.get_document_cursors(&b).unwrap();
+        assert_eq!(
+            cursors
+                .iter()
+                .map(|cursor| (cursor.line, cursor.column))
+                .collect::<Vec<_>>(),
+            vec![(1, 3)]
+        );
+        // Going back returns to where the jump started.
+        state.navigate_back();
+        assert_eq!(state.focused_document_path, Some(a));
+    }
+}
@@ -1,8 +1,48 @@
+use std::ops::Range;
+
+use crate::{document::history::EditKind, editor_view::paint::cursor::Cursor};
+
 impl super::EditorState {
     pub fn insert_character(&mut self, ch: char) {
-        let focused_document_path = match self.focused_document_path.clone() {
-            Some(p) => p,
-            None => return,
+        let s = ch.to_string();
+        self.edit_at_cursors(EditKind::InsertChars, |offset, cursor| {
+            let range = cursor.get_selection_range().unwrap_or(offset..offset);
+            Some((range, s.clone()))
+        });
+        self.update_completion(Some(ch));
+        self.update_signature_help(ch);
+        self.hover = None;
+    }
+
+    pub fn delete_character(&mut self) {
+        self.edit_at_cursors(EditKind::Delete, |offset, cursor| {
+            match cursor.get_selection_range() {
+                Some(range) => Some((range, String::new())),
+                None => (offset > 0).then(|| (offset - 1..offset, String::new())),
+            }
+        });
+        self.update_completion(None);
+    }
+
+    pub fn undo(&mut self) {
+        self.revert_focused_document(|document| document.undo());
+    }
+
+    pub fn redo(&mut self) {
+        self.revert_focused_document(|document| document.redo());
+    }
+
+    /// Applies one edit per cursor on the focused document, as a single undoable
+    /// step, then moves each cursor after its inserted text.
+    ///
+    /// `f` receives the offset of the cursor and returns the range to replace,
+    /// or `None` to leave the cursor where it is, moved along by the other edits.
+    pub(super) fn edit_at_cursors<F>(&mut self, kind: EditKind, f: F)
+    where
+        F: Fn(usize, &Cursor) -> Option<(Range<usize>, String)>,
+    {
+        let Some(focused_document_path) = self.focused_document_path.clone() else {
+            return;
         };
 
         let cursors = self
@@ -11,64 +51,86 @@ impl super::EditorState {
             .cloned()
             .unwrap_or_default();
 
-        let positions = cursors
+        let edits = cursors
             .iter()
-            .map(|c| c.get_cursor_offset())
+            .map(|c| f(c.get_cursor_offset(), c))
             .collect::<Vec<_>>();
+        if edits.iter().all(Option::is_none) {
+            return;
+        }
+        // The cursors without an edit get an empty one, which only moves them.
+        let edits = cursors
+            .iter()
+            .zip(edits)
+            .map(|(c, edit)| {
+                let offset = c.get_cursor_offset();
+                edit.unwrap_or((offset..offset, String::new()))
+            })
+            .collect::<Vec<_>>();
+        let cursors_before = cursors.iter().map(|c| (c.line, c.column)).collect();
+        self.shift_snippet(&focused_document_path, &edits);
 
-        let mut documents = self.documents.lock().unwrap();
-        let document = match documents.get_mut(&focused_document_path) {
-            Some(d) => d,
-            None => return,
+        let Some(cursors_after) = self
+            .edit_with_parked_cursors(&focused_document_path, |document| {
+                document.edit(edits, kind, cursors_before)
+            })
+        else {
+            return;
         };
 
-        let s = ch.to_string();
-        for position in positions {
-            document.insert_character_at(position, &s);
-        }
+        self.update_styles_with_syntax(focused_document_path.clone());
+        self.set_cursors(focused_document_path, &cursors_after);
+    }
 
-        drop(documents);
+    fn revert_focused_document<F>(&mut self, f: F)
+    where
+        F: FnOnce(&mut crate::document::Document) -> Option<Vec<(usize, usize)>>,
+    {
+        let Some(focused_document_path) = self.focused_document_path.clone() else {
+            return;
+        };
 
-        if let Some(cursors) = self.document_cursors.get_mut(&focused_document_path) {
-            for c in cursors {
-                c.move_x_at(c.column + 1);
-            }
-        }
+        let Some(Some(cursors)) = self.edit_with_parked_cursors(&focused_document_path, f) else {
+            return;
+        };
+        self.update_styles_with_syntax(focused_document_path.clone());
+        self.set_cursors(focused_document_path, &cursors);
     }
+}
 
-    pub fn delete_character(&mut self) {
-        let focused_document_path = match self.focused_document_path.clone() {
-            Some(p) => p,
-            None => return,
-        };
+#[cfg(test)]
+mod tests {
+    use super::super::test_support::test_state;
 
-        let cursors = self
-            .document_cursors
-            .get(&focused_document_path)
-            .cloned()
-            .unwrap_or_default();
+    #[test]
+    fn cursors_without_an_edit_are_kept_and_moved_along() {
+        let mut state = test_state(&[("a.txt", "ab\ncd\n")]);
+        let path = state.open_loaded("a.txt");
+        state.set_cursors(path.clone(), &[(0, 0), (1, 1)]);
 
-        let positions = cursors
-            .iter()
-            .map(|c| c.get_cursor_offset())
-            .collect::<Vec<_>>();
+        // Nothing to delete before the first cursor.
+        state.delete_character();
+        assert_eq!(state.text(&path), "ab\nd\n");
+        assert_eq!(state.cursor_offsets(&path), vec![0, 3]);
 
-        let mut documents = self.documents.lock().unwrap();
-        let document = match documents.get_mut(&focused_document_path) {
-            Some(d) => d,
-            None => return,
-        };
+        state.insert_character('x');
+        assert_eq!(state.text(&path), "xab\nxd\n");
+        assert_eq!(state.cursor_offsets(&path), vec![1, 5]);
+    }
 
-        for position in positions {
-            document.delete_character_at(position);
-        }
+    #[test]
+    fn undo_and_redo_restore_every_cursor() {
+        let mut state = test_state(&[("a.txt", "ab\ncd\n")]);
+        let path = state.open_loaded("a.txt");
+        state.set_cursors(path.clone(), &[(0, 0), (1, 1)]);
 
-        drop(documents);
+        state.delete_character();
+        state.undo();
+        assert_eq!(state.text(&path), "ab\ncd\n");
+        assert_eq!(state.cursor_offsets(&path), vec![0, 4]);
 
-        if let Some(cursors) = self.document_cursors.get_mut(&focused_document_path) {
-            for c in cursors {
-                c.move_x_at(c.column - 1);
-            }
-        }
+        state.redo();
+        assert_eq!(state.text(&path), "ab\nd\n");
+        assert_eq!(state.cursor_offsets(&path), vec![0, 3]);
     }
 }
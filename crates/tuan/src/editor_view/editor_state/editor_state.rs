@@ -1,77 +1,215 @@
-use crate::keybindings::Keybindings;
-use crate::{
-    document,
-    editor_view::{EditorConfig, paint::cursor},
-    proxy, workspace,
-};
 use std::{
     collections::HashMap,
     path::PathBuf,
     sync::{Arc, Mutex},
 };
 
+use crossbeam_channel::{Receiver, Sender};
+use lsp_types::Diagnostic;
+use tuan_rpc::proxy::ProxyStatus;
+
+use super::{
+    action::EditorAction,
+    command_palette::CommandPalette,
+    completion::{Completion, SnippetSession},
+    find::FindBar,
+    hover::{HoverTimer, InfoPopup},
+    lsp::LspResponse,
+    navigation::{LocationList, NavigationHistory},
+    open::OnLoad,
+    panes::{Pane, PaneId, PaneLayout},
+    quick_open::QuickOpen,
+    refactoring::{CodeActionList, RenameInput},
+    search::ProjectSearch,
+    styles::StylesWorker,
+    workspace_edit::PendingEdit,
+};
+use crate::{
+    clipboard::{Clipboard, SystemClipboard},
+    config_watcher::ConfigWatcher,
+    document,
+    editor_view::{EditorConfig, paint::cursor},
+    explorer::{self, Explorer},
+    file_index::FileIndex,
+    keybindings::Keybindings,
+    proxy,
+    settings::Settings,
+    workspace,
+};
+
 #[derive(Clone)]
 pub struct EditorState {
     pub(super) proxy: proxy::ProxyData,
+    /// The last connection status reported by the proxy.
+    pub proxy_status: Option<ProxyStatus>,
     pub config: Arc<EditorConfig>,
     pub documents: Arc<Mutex<HashMap<PathBuf, document::Document>>>,
+    pub(super) styles_worker: StylesWorker,
+    /// What to do with each document being loaded by the proxy, once it is.
+    pub(super) loading: Arc<Mutex<HashMap<PathBuf, Vec<OnLoad>>>>,
     pub focused_document_path: Option<PathBuf>,
+    /// The paths of the documents shown as tabs, in tab order.
+    pub open_documents: Vec<PathBuf>,
+    /// A dirty document the user asked to close, awaiting confirmation.
+    pub close_confirmation: Option<PathBuf>,
+    /// An explorer entry the user asked to delete, awaiting confirmation.
+    pub delete_confirmation: Option<PathBuf>,
+    pub layout: PaneLayout,
+    pub active_pane: PaneId,
+    /// The state of the inactive panes, see [`Pane`].
+    pub(super) parked_panes: HashMap<PaneId, Pane>,
+    pub explorer: Arc<Mutex<Explorer>>,
+    pub explorer_visible: bool,
+    /// The diagnostics published by the language servers, by file.
+    pub diagnostics: HashMap<PathBuf, Arc<Vec<Diagnostic>>>,
+    pub problems_visible: bool,
+    /// The pane and `(line, column)` under the pointer.
+    pub hover_position: Option<(PaneId, (usize, usize))>,
+    /// The line where the selection of whole lines started, while dragging in the gutter.
+    pub line_selection_anchor: Option<usize>,
+    pub completion: Option<Completion>,
+    /// The snippet inserted by the last completion, while its tabstops are visited.
+    pub snippet: Option<SnippetSession>,
+    pub hover: Option<InfoPopup>,
+    pub signature_help: Option<InfoPopup>,
+    /// The locations found by the last navigation request, when there are several.
+    pub locations: Option<LocationList>,
+    pub(super) navigation_history: NavigationHistory,
+    /// The rename bar, while the new name of a symbol is typed.
+    pub rename: Option<RenameInput>,
+    /// The path typed in the save-as bar, relative to the workspace.
+    pub save_as: Option<String>,
+    pub code_actions: Option<CodeActionList>,
+    /// A workspace edit awaiting confirmation, see [`Self::apply_pending_edit`].
+    pub pending_edit: Option<PendingEdit>,
+    pub find: Option<FindBar>,
+    /// The project search panel, when shown.
+    pub search: Option<ProjectSearch>,
+    pub command_palette: Option<CommandPalette>,
+    /// The actions last run from the command palette, most recent first.
+    pub(super) recent_actions: Vec<EditorAction>,
+    /// Every file of the workspace, kept up to date for the quick open.
+    pub(super) file_index: FileIndex,
+    pub quick_open: Option<QuickOpen>,
+    /// The files last opened or focused, most recent first.
+    pub(super) recent_files: Vec<PathBuf>,
+    pub(super) lsp_response_tx: Sender<LspResponse>,
+    pub(super) hover_timer: HoverTimer,
+    pub(super) lsp_response_rx: Receiver<LspResponse>,
     pub document_scrollings: HashMap<PathBuf, (f64, f64)>,
+    /// The height of each pane, shared with the snapshots painting them.
+    pub(super) viewport_heights: Arc<Mutex<HashMap<PaneId, f64>>>,
     pub document_cursors: HashMap<PathBuf, Vec<cursor::Cursor>>,
     pub keybindings: Keybindings,
+    pub workspace_path: PathBuf,
+    /// The last successfully loaded settings, `config` is derived from them.
+    pub settings: Settings,
+    pub(crate) config_watcher: ConfigWatcher,
+    /// The error of the last configuration reload, if it failed.
+    pub config_error: Option<String>,
+    /// A message for the user shown in the status bar, e.g. why an action did nothing.
+    pub status_message: Option<String>,
+    pub clipboard: Arc<Mutex<dyn Clipboard>>,
+    /// The text last copied as whole lines, pasted above the cursor line
+    /// while the clipboard still holds it.
+    pub(super) whole_line_clip: Option<String>,
 }
 
 impl EditorState {
-    pub fn new(workspace_path: PathBuf, editor_config: Arc<EditorConfig>) -> Self {
+    pub fn new(
+        workspace_path: PathBuf,
+        settings: Settings,
+        editor_config: Arc<EditorConfig>,
+    ) -> Self {
         let workspace = Arc::new(workspace::LapceWorkspace {
             kind: workspace::LapceWorkspaceType::Local,
-            path: Some(workspace_path),
+            path: Some(workspace_path.clone()),
             ..Default::default()
         });
         let (term_tx, term_rx) = crossbeam_channel::unbounded();
 
         let proxy = proxy::new_proxy(workspace, vec![], vec![], HashMap::new(), term_tx);
+        Self::with_proxy(workspace_path, settings, editor_config, proxy)
+    }
 
-        let keybinds = Keybindings::new().expect("Failed to create keybinds");
-
-        // Spawn a thread to log all data received in proxy.notification_rx
-        std::thread::spawn({
-            let notification_rx = proxy.notification_rx.clone();
-            move || {
-                while let Ok(data) = notification_rx.recv() {
-                    tracing::debug!("Received notification: {:?}", data);
-                }
-            }
+    /// The editor of `workspace_path`, talking to the proxy through `proxy`.
+    pub(super) fn with_proxy(
+        workspace_path: PathBuf,
+        settings: Settings,
+        editor_config: Arc<EditorConfig>,
+        proxy: proxy::ProxyData,
+    ) -> Self {
+        let mut config_error = None;
+        let keybinds = Keybindings::new().unwrap_or_else(|err| {
+            let error = format!(
+                "Failed to load {}: {err}",
+                Keybindings::config_path().display()
+            );
+            tracing::error!("{error}");
+            config_error = Some(error);
+            Keybindings::default()
         });
 
-        std::thread::spawn({
-            let core_rpc = proxy.core_rpc.clone();
-            let core_rx = core_rpc.rx().clone();
-            move || {
-                while let Ok(notification) = core_rx.recv() {
-                    match notification {
-                        tuan_rpc::core::CoreRpc::Request(id, req) => {
-                            tracing::debug!("CoreRpc::Request - id: {:?}, req: {:?}", id, req);
-                        }
-                        tuan_rpc::core::CoreRpc::Notification(notif) => {
-                            tracing::debug!("CoreRpc::Notification - notif: {:?}", notif);
-                        }
-                        tuan_rpc::core::CoreRpc::Shutdown => {
-                            tracing::debug!("CoreRpc::Shutdown");
-                        }
-                    }
-                }
-            }
-        });
+        let documents = Arc::new(Mutex::new(HashMap::<PathBuf, document::Document>::new()));
+        let explorer = Arc::new(Mutex::new(Explorer::new(workspace_path.clone())));
+        explorer::list_dir(&explorer, &proxy.proxy_rpc, workspace_path.clone());
+
+        let (lsp_response_tx, lsp_response_rx) = crossbeam_channel::unbounded();
 
-        Self {
+        let active_pane = PaneId::next();
+        let state = Self {
             proxy,
+            proxy_status: None,
             keybindings: keybinds,
             config: editor_config,
-            documents: Arc::new(Mutex::new(HashMap::new())),
+            styles_worker: StylesWorker::new(documents.clone()),
+            documents,
+            loading: Arc::new(Mutex::new(HashMap::new())),
             focused_document_path: None,
+            open_documents: Vec::new(),
+            close_confirmation: None,
+            delete_confirmation: None,
+            layout: PaneLayout::Pane(active_pane),
+            active_pane,
+            parked_panes: HashMap::new(),
+            explorer,
+            explorer_visible: true,
+            diagnostics: HashMap::new(),
+            problems_visible: false,
+            hover_position: None,
+            line_selection_anchor: None,
+            completion: None,
+            snippet: None,
+            hover: None,
+            signature_help: None,
+            locations: None,
+            navigation_history: NavigationHistory::default(),
+            rename: None,
+            save_as: None,
+            code_actions: None,
+            pending_edit: None,
+            find: None,
+            search: None,
+            command_palette: None,
+            recent_actions: Vec::new(),
+            file_index: FileIndex::new(workspace_path.clone()),
+            quick_open: None,
+            recent_files: Vec::new(),
+            hover_timer: HoverTimer::new(lsp_response_tx.clone()),
+            lsp_response_tx,
+            lsp_response_rx,
             document_scrollings: HashMap::new(),
+            viewport_heights: Arc::new(Mutex::new(HashMap::new())),
             document_cursors: HashMap::new(),
-        }
+            workspace_path,
+            settings,
+            config_watcher: ConfigWatcher::new(),
+            config_error,
+            status_message: None,
+            clipboard: Arc::new(Mutex::new(SystemClipboard::new())),
+            whole_line_clip: None,
+        };
+        state.config_watcher.watch(state.config_files());
+        state
     }
 }
@@ -0,0 +1,221 @@
+use std::path::{Path, PathBuf};
+
+use tuan_rpc::proxy::ProxyResponse;
+
+use super::LspResponse;
+use crate::explorer::{self, Input, InputKind};
+
+impl super::EditorState {
+    pub fn toggle_explorer(&mut self) {
+        self.explorer_visible = !self.explorer_visible;
+    }
+
+    /// Selects `path`, expanding or collapsing it if it is a directory, opening it otherwise.
+    pub fn click_explorer_entry(&mut self, path: PathBuf) {
+        let (is_dir, must_list) = {
+            let mut explorer = self.explorer.lock().unwrap();
+            explorer.selected = Some(path.clone());
+            if explorer.is_dir(&path) {
+                (true, explorer.toggle(&path))
+            } else {
+                (false, false)
+            }
+        };
+
+        if must_list {
+            explorer::list_dir(&self.explorer, &self.proxy.proxy_rpc, path);
+        } else if !is_dir {
+            if self.open_documents.contains(&path) {
+                self.focus_tab(path);
+            } else {
+                self.open_file_at(path, (0, 0));
+            }
+        }
+    }
+
+    /// Shows the focused document in the explorer.
+    pub fn reveal_active_file(&mut self) {
+        let Some(path) = self.focused_document_path.clone() else {
+            return;
+        };
+        self.explorer_visible = true;
+        let dirs = self.explorer.lock().unwrap().reveal(&path);
+        for dir in dirs {
+            explorer::list_dir(&self.explorer, &self.proxy.proxy_rpc, dir);
+        }
+    }
+
+    pub fn start_new_file(&mut self) {
+        let mut explorer = self.explorer.lock().unwrap();
+        let dir = explorer.target_dir();
+        explorer.input = Some(Input {
+            kind: InputKind::NewFile { dir },
+            name: String::new(),
+        });
+    }
+
+    pub fn start_new_directory(&mut self) {
+        let mut explorer = self.explorer.lock().unwrap();
+        let dir = explorer.target_dir();
+        explorer.input = Some(Input {
+            kind: InputKind::NewDirectory { dir },
+            name: String::new(),
+        });
+    }
+
+    pub fn start_explorer_rename(&mut self) {
+        let mut explorer = self.explorer.lock().unwrap();
+        let Some(path) = explorer.selected.clone() else {
+            return;
+        };
+        let name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        explorer.input = Some(Input {
+            kind: InputKind::Rename { path },
+            name,
+        });
+    }
+
+    pub fn set_explorer_input(&mut self, name: String) {
+        if let Some(input) = self.explorer.lock().unwrap().input.as_mut() {
+            input.name = name;
+        }
+    }
+
+    pub fn cancel_explorer_input(&mut self) {
+        self.explorer.lock().unwrap().input = None;
+    }
+
+    /// Creates or renames the entry named in the explorer input.
+    pub fn submit_explorer_input(&mut self) {
+        let Some(input) = self.explorer.lock().unwrap().input.take() else {
+            return;
+        };
+        let name = input.name.trim();
+        if name.is_empty() {
+            return;
+        }
+
+        let proxy_rpc = &self.proxy.proxy_rpc;
+        match input.kind {
+            InputKind::NewFile { dir } => {
+                let path = dir.join(name);
+                proxy_rpc.create_file(path.clone(), self.after_explorer_change(dir, path));
+            }
+            InputKind::NewDirectory { dir } => {
+                let path = dir.join(name);
+                proxy_rpc.create_directory(path.clone(), self.after_explorer_change(dir, path));
+            }
+            InputKind::Rename { path } => {
+                let Some(dir) = path.parent().map(PathBuf::from) else {
+                    return;
+                };
+                let new_path = dir.join(name);
+                proxy_rpc.rename_path(
+                    path,
+                    new_path.clone(),
+                    self.after_explorer_change(dir, new_path),
+                );
+            }
+        }
+    }
+
+    /// Asks to confirm moving the selected entry to the trash.
+    pub fn delete_selected_entry(&mut self) {
+        let Some(path) = self.explorer.lock().unwrap().selected.clone() else {
+            return;
+        };
+        if path != self.workspace_path {
+            self.delete_confirmation = Some(path);
+        }
+    }
+
+    pub fn cancel_delete(&mut self) {
+        self.delete_confirmation = None;
+    }
+
+    /// Moves the entry awaiting confirmation to the trash, then closes the
+    /// tabs of the files it held.
+    pub fn confirm_delete(&mut self) {
+        let Some(path) = self.delete_confirmation.take() else {
+            return;
+        };
+        let Some(dir) = path.parent().map(PathBuf::from) else {
+            return;
+        };
+        self.explorer.lock().unwrap().selected = None;
+
+        let after_change = self.after_explorer_change(dir.clone(), dir);
+        let lsp_response_tx = self.lsp_response_tx.clone();
+        self.proxy
+            .proxy_rpc
+            .trash_path(path.clone(), move |result| {
+                if result.is_ok()
+                    && let Err(err) = lsp_response_tx.send(LspResponse::Trashed(path))
+                {
+                    tracing::error!("Failed to send the trashed path: {err}");
+                }
+                after_change(result);
+            });
+    }
+
+    /// Closes the tabs of the files at or under `path`, which was trashed.
+    pub(super) fn close_trashed_tabs(&mut self, path: &Path) {
+        let trashed = self
+            .open_documents
+            .iter()
+            .filter(|open| open.starts_with(path))
+            .cloned()
+            .collect::<Vec<_>>();
+        for open in trashed {
+            self.close_tab(open, true);
+        }
+    }
+
+    /// Lists `dir` again once the change is done, and selects `selected`.
+    fn after_explorer_change(
+        &self,
+        dir: PathBuf,
+        selected: PathBuf,
+    ) -> impl FnOnce(Result<ProxyResponse, tuan_rpc::RpcError>) + Send + 'static {
+        let explorer = self.explorer.clone();
+        let proxy_rpc = self.proxy.proxy_rpc.clone();
+        move |result| match result {
+            Ok(_) => {
+                explorer.lock().unwrap().selected = Some(selected);
+                explorer::list_dir(&explorer, &proxy_rpc, dir);
+            }
+            Err(err) => tracing::error!("Failed to update {:?}: {}", selected, err.message),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::test_support::test_state;
+
+    #[test]
+    fn deleting_asks_first() {
+        let mut state = test_state(&[("a.txt", "a")]);
+        let path = state.workspace_path.join("a.txt");
+        state.explorer.lock().unwrap().selected = Some(path.clone());
+
+        state.delete_selected_entry();
+        assert_eq!(state.delete_confirmation.as_ref(), Some(&path));
+        state.cancel_delete();
+        assert!(state.delete_confirmation.is_none());
+        assert!(path.exists());
+    }
+
+    #[test]
+    fn trashing_a_directory_closes_the_tabs_under_it() {
+        let mut state = test_state(&[("src/a.txt", "a"), ("b.txt", "b")]);
+        state.open_loaded("src/a.txt");
+        let b = state.open_loaded("b.txt");
+
+        state.close_trashed_tabs(&state.workspace_path.join("src"));
+        assert_eq!(state.open_documents, vec![b]);
+    }
+}
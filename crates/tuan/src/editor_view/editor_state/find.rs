@@ -0,0 +1,501 @@
+use std::{
+    collections::HashMap,
+    ops::Range,
+    path::PathBuf,
+    sync::{Arc, Mutex},
+};
+
+use regex::{Captures, Regex, RegexBuilder};
+
+use crate::document::{Document, highlight::shift_offset, history::Change};
+
+/// The find/replace bar of the focused document.
+#[derive(Debug, Clone, Default)]
+pub struct FindBar {
+    pub query: String,
+    pub replacement: String,
+    /// Whether the query is a regex rather than literal text.
+    pub regex: bool,
+    pub case_sensitive: bool,
+    pub whole_word: bool,
+    pub replace_visible: bool,
+    /// Why the query is not a valid regex.
+    pub error: Option<String>,
+    pattern: Option<Regex>,
+    /// The matches of the pattern in each document, caught up with its edits
+    /// by searching the edited lines again.
+    matches: Arc<Mutex<HashMap<PathBuf, Matches>>>,
+}
+
+#[derive(Debug)]
+struct Matches {
+    rev: u64,
+    ranges: Arc<Vec<Range<usize>>>,
+}
+
+impl FindBar {
+    /// Compiles the query with the current modes, dropping the matches found so far.
+    fn update_pattern(&mut self) {
+        self.matches.lock().unwrap().clear();
+        self.error = None;
+        self.pattern = None;
+        if self.query.is_empty() {
+            return;
+        }
+
+        match build_pattern(
+            &self.query,
+            self.regex,
+            self.case_sensitive,
+            self.whole_word,
+        ) {
+            Ok(pattern) => self.pattern = Some(pattern),
+            Err(err) => self.error = Some(err.to_string()),
+        }
+    }
+
+    /// The byte ranges of the non-empty matches in `document`, sorted.
+    fn matches(&self, document: &Document) -> Arc<Vec<Range<usize>>> {
+        let Some(pattern) = &self.pattern else {
+            return Arc::default();
+        };
+        let rev = document.buffer.rev();
+        let mut matches = self.matches.lock().unwrap();
+        let cached = matches.get(&document.path);
+        if let Some(cached) = cached.filter(|cached| cached.rev == rev) {
+            return cached.ranges.clone();
+        }
+
+        let changes = cached
+            .filter(|_| !self.can_span_lines())
+            .and_then(|cached| Some((cached, document.changes_since(cached.rev)?)));
+        let ranges = Arc::new(match changes {
+            Some((cached, changes)) => {
+                find_in_changed_lines(pattern, document, &cached.ranges, &changes)
+            }
+            None => find_in(pattern, &document_text(document), 0),
+        });
+        matches.insert(
+            document.path.clone(),
+            Matches {
+                rev,
+                ranges: ranges.clone(),
+            },
+        );
+        ranges
+    }
+
+    /// Whether a match may span several lines, in which case searching the
+    /// edited lines again is not enough to catch up with an edit.
+    fn can_span_lines(&self) -> bool {
+        if !self.regex {
+            return self.query.contains('\n');
+        }
+        // Conservatively, the escapes and classes that can match a line break.
+        [
+            "\\n", "\\s", "\\W", "\\D", "\\P", "\\x", "\\u", "[^", "(?s", "[[:",
+        ]
+        .iter()
+        .any(|token| self.query.contains(token))
+    }
+
+    /// The text replacing the match at `range` of `text`, where `$1` or
+    /// `${name}` stand for the groups of a regex.
+    fn replacement_for(&self, text: &str, range: &Range<usize>) -> String {
+        if !self.regex {
+            return self.replacement.clone();
+        }
+        let captures = self
+            .pattern
+            .as_ref()
+            .and_then(|pattern| pattern.captures_at(text, range.start))
+            .filter(|captures| captures.get_match().range() == *range);
+        match captures {
+            Some(captures) => expand_replacement(&captures, &self.replacement),
+            None => self.replacement.clone(),
+        }
+    }
+}
+
+/// The non-empty matches of `pattern` in `text`, shifted by `offset`.
+fn find_in(pattern: &Regex, text: &str, offset: usize) -> Vec<Range<usize>> {
+    pattern
+        .find_iter(text)
+        .filter(|found| !found.is_empty())
+        .map(|found| found.start() + offset..found.end() + offset)
+        .collect()
+}
+
+/// The matches of `pattern` in `document`, from the `matches` found before
+/// `changes`: the lines they edited are searched again, the other matches are
+/// only moved along.
+///
+/// Only for patterns matching within a line.
+fn find_in_changed_lines(
+    pattern: &Regex,
+    document: &Document,
+    matches: &[Range<usize>],
+    changes: &[Arc<Vec<Change>>],
+) -> Vec<Range<usize>> {
+    let mut matches = matches.to_vec();
+    // The edited text, in offsets of the text after the changes seen so far.
+    let mut edited: Vec<Range<usize>> = Vec::new();
+    for step in changes {
+        for range in matches.iter_mut().chain(edited.iter_mut()) {
+            *range = shift_offset(range.start, step)..shift_offset(range.end, step);
+        }
+        let mut shift = 0isize;
+        for change in step.iter() {
+            let start = change.start.saturating_add_signed(shift);
+            edited.push(start..start + change.inserted.len());
+            shift += change.inserted.len() as isize - change.deleted.len() as isize;
+        }
+    }
+
+    // Whole lines are searched again, as a match may start before an edit.
+    let mut lines = edited
+        .into_iter()
+        .map(|range| {
+            let (first, _) = document.offset_to_position(range.start);
+            let (last, _) = document.offset_to_position(range.end);
+            document.line_range(first).start..document.line_range(last).end
+        })
+        .collect::<Vec<_>>();
+    lines.sort_by_key(|range| range.start);
+    let mut merged: Vec<Range<usize>> = Vec::new();
+    for range in lines {
+        match merged.last_mut() {
+            Some(last) if range.start <= last.end => last.end = last.end.max(range.end),
+            _ => merged.push(range),
+        }
+    }
+
+    // Matches whose text was deleted end up empty.
+    matches.retain(|found| {
+        !found.is_empty()
+            && !merged
+                .iter()
+                .any(|lines| found.start < lines.end && lines.start < found.end)
+    });
+    for lines in merged {
+        matches.extend(find_in(
+            pattern,
+            &document.text_in(lines.clone()),
+            lines.start,
+        ));
+    }
+    matches.sort_by_key(|found| found.start);
+    matches
+}
+
+/// Compiles a search `query`, literal text unless `regex`.
+pub(super) fn build_pattern(
+    query: &str,
+    regex: bool,
+    case_sensitive: bool,
+    whole_word: bool,
+) -> Result<Regex, regex::Error> {
+    let pattern = if regex {
+        query.to_string()
+    } else {
+        regex::escape(query)
+    };
+    let pattern = if whole_word {
+        format!(r"\b(?:{pattern})\b")
+    } else {
+        pattern
+    };
+    RegexBuilder::new(&pattern)
+        .case_insensitive(!case_sensitive)
+        .multi_line(true)
+        .build()
+}
+
+/// The `replacement` of a regex match, with `$1` or `${name}` standing for its groups.
+pub(super) fn expand_replacement(captures: &Captures<'_>, replacement: &str) -> String {
+    let mut expanded = String::new();
+    captures.expand(replacement, &mut expanded);
+    expanded
+}
+
+pub(super) fn document_text(document: &Document) -> String {
+    document
+        .buffer
+        .slice_to_cow(0..document.buffer.len())
+        .into_owned()
+}
+
+impl super::EditorState {
+    /// Opens the find bar, or the find/replace bar when `replace`, searching
+    /// for the selected text if any.
+    pub fn open_find(&mut self, replace: bool) {
+        let selection = self.focused_document_path.as_ref().and_then(|path| {
+            let range = self.focused_cursors().first()?.get_selection_range()?;
+            let documents = self.documents.lock().unwrap();
+            Some(documents.get(path)?.text_in(range))
+        });
+
+        let find = self.find.get_or_insert_with(FindBar::default);
+        find.replace_visible |= replace;
+        if let Some(selection) = selection.filter(|text| !text.contains('\n')) {
+            find.query = if find.regex {
+                regex::escape(&selection)
+            } else {
+                selection
+            };
+            find.update_pattern();
+        }
+    }
+
+    pub fn close_find(&mut self) {
+        self.find = None;
+    }
+
+    pub fn set_find_query(&mut self, query: String) {
+        if let Some(find) = self.find.as_mut() {
+            find.query = query;
+            find.update_pattern();
+        }
+    }
+
+    pub fn set_find_replacement(&mut self, replacement: String) {
+        if let Some(find) = self.find.as_mut() {
+            find.replacement = replacement;
+        }
+    }
+
+    pub fn toggle_find_regex(&mut self) {
+        if let Some(find) = self.find.as_mut() {
+            find.regex = !find.regex;
+            find.update_pattern();
+        }
+    }
+
+    pub fn toggle_find_case_sensitive(&mut self) {
+        if let Some(find) = self.find.as_mut() {
+            find.case_sensitive = !find.case_sensitive;
+            find.update_pattern();
+        }
+    }
+
+    pub fn toggle_find_whole_word(&mut self) {
+        if let Some(find) = self.find.as_mut() {
+            find.whole_word = !find.whole_word;
+            find.update_pattern();
+        }
+    }
+
+    /// The matches of the find bar in the document at `path`, none when it is closed.
+    pub fn find_matches(&self, path: &PathBuf) -> Arc<Vec<Range<usize>>> {
+        let Some(find) = &self.find else {
+            return Arc::default();
+        };
+        match self.documents.lock().unwrap().get(path) {
+            Some(document) => find.matches(document),
+            None => Arc::default(),
+        }
+    }
+
+    /// The number of matches in the focused document, and which one is
+    /// selected, e.g. "3 of 12".
+    pub fn find_summary(&self) -> String {
+        let (Some(path), Some(find)) = (&self.focused_document_path, &self.find) else {
+            return String::new();
+        };
+        if find.error.is_some() {
+            return "Invalid regex".to_string();
+        }
+        let matches = self.find_matches(path);
+        if matches.is_empty() {
+            return "No results".to_string();
+        }
+        let selected = self
+            .focused_cursors()
+            .first()
+            .and_then(|cursor| cursor.get_selection_range())
+            .and_then(|selection| matches.iter().position(|range| *range == selection));
+        match selected {
+            Some(index) => format!("{} of {}", index + 1, matches.len()),
+            None => format!("{} results", matches.len()),
+        }
+    }
+
+    /// Selects the first match after the first cursor, wrapping around.
+    pub fn find_next(&mut self) {
+        self.find_step(true);
+    }
+
+    /// Selects the last match before the first cursor, wrapping around.
+    pub fn find_previous(&mut self) {
+        self.find_step(false);
+    }
+
+    fn find_step(&mut self, forward: bool) {
+        let Some(path) = self.focused_document_path.clone() else {
+            return;
+        };
+        let matches = self.find_matches(&path);
+        if matches.is_empty() {
+            return;
+        }
+        let selection = self.first_selection();
+        let index = if forward {
+            let index = matches.partition_point(|range| range.start < selection.end);
+            if index == matches.len() { 0 } else { index }
+        } else {
+            let index = matches.partition_point(|range| range.start < selection.start);
+            index.checked_sub(1).unwrap_or(matches.len() - 1)
+        };
+        self.select_range(path, matches[index].clone());
+    }
+
+    /// The selection of the first cursor of the focused document, empty at a caret.
+    fn first_selection(&self) -> Range<usize> {
+        self.focused_cursors()
+            .first()
+            .map(|cursor| {
+                cursor.get_selection_range().unwrap_or_else(|| {
+                    let offset = cursor.get_cursor_offset();
+                    offset..offset
+                })
+            })
+            .unwrap_or(0..0)
+    }
+
+    /// Selects `range` of `path` with a single cursor, scrolling to it.
+    fn select_range(&mut self, path: PathBuf, range: Range<usize>) {
+        let Some((start, end)) = self.documents.lock().unwrap().get(&path).map(|document| {
+            (
+                document.offset_to_position(range.start),
+                document.offset_to_position(range.end),
+            )
+        }) else {
+            return;
+        };
+        self.set_cursors(path.clone(), &[end]);
+        if let Some(cursor) = self
+            .document_cursors
+            .get_mut(&path)
+            .and_then(|cursors| cursors.first_mut())
+        {
+            cursor.select(start, end);
+        }
+        self.reveal_line(&path, start.0);
+    }
+
+    /// Replaces the selected match, if any, and selects the next one.
+    pub fn replace_one(&mut self) {
+        let (Some(path), Some(find)) = (self.focused_document_path.clone(), self.find.clone())
+        else {
+            return;
+        };
+        let matches = self.find_matches(&path);
+        let selection = self.first_selection();
+        if matches
+            .binary_search_by_key(&selection.start, |range| range.start)
+            .is_ok_and(|index| matches[index] == selection)
+        {
+            let Some(replacement) = self
+                .documents
+                .lock()
+                .unwrap()
+                .get(&path)
+                .map(|document| find.replacement_for(&document_text(document), &selection))
+            else {
+                return;
+            };
+            self.edit_document(path, vec![(selection, replacement)]);
+        }
+        self.find_next();
+    }
+
+    /// Replaces every match of the focused document, as a single undoable step.
+    pub fn replace_all(&mut self) {
+        let (Some(path), Some(find)) = (self.focused_document_path.clone(), self.find.clone())
+        else {
+            return;
+        };
+        let matches = self.find_matches(&path);
+        if matches.is_empty() {
+            return;
+        }
+        let Some(edits) = self.documents.lock().unwrap().get(&path).map(|document| {
+            let text = document_text(document);
+            matches
+                .iter()
+                .map(|range| (range.clone(), find.replacement_for(&text, range)))
+                .collect::<Vec<_>>()
+        }) else {
+            return;
+        };
+        tracing::info!("Replacing {} matches in {:?}", edits.len(), path);
+        self.edit_document(path, edits);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::test_support::test_state;
+
+    #[test]
+    fn matches_follow_the_edits() {
+        let mut state = test_state(&[("a.txt", "foo bar\nbar foo\nfoo\n")]);
+        let path = state.open_loaded("a.txt");
+        state.open_find(false);
+        state.set_find_query("foo".to_string());
+        assert_eq!(*state.find_matches(&path), vec![0..3, 12..15, 16..19]);
+
+        // Typing on the second line moves the matches after it.
+        state.set_cursors(path.clone(), &[(1, 0)]);
+        state.insert_character('x');
+        assert_eq!(*state.find_matches(&path), vec![0..3, 13..16, 17..20]);
+
+        // Completing a match on an edited line finds it.
+        state.set_cursors(path.clone(), &[(0, 4)]);
+        for ch in "foo ".chars() {
+            state.insert_character(ch);
+        }
+        assert_eq!(state.text(&path), "foo foo bar\nxbar foo\nfoo\n");
+        assert_eq!(*state.find_matches(&path), vec![0..3, 4..7, 17..20, 21..24]);
+
+        // Breaking one drops it.
+        state.set_cursors(path.clone(), &[(2, 1)]);
+        state.delete_character();
+        assert_eq!(*state.find_matches(&path), vec![0..3, 4..7, 17..20]);
+
+        // Undoing goes back to the matches of the previous text.
+        state.undo();
+        assert_eq!(*state.find_matches(&path), vec![0..3, 4..7, 17..20, 21..24]);
+    }
+
+    #[test]
+    fn matches_are_kept_per_document() {
+        let mut state = test_state(&[("a.txt", "foo\n"), ("b.txt", "a foo\n")]);
+        let a = state.open_loaded("a.txt");
+        let b = state.open_loaded("b.txt");
+        state.open_find(false);
+        state.set_find_query("foo".to_string());
+
+        let a_matches = state.find_matches(&a);
+        let b_matches = state.find_matches(&b);
+        assert_eq!(*a_matches, vec![0..3]);
+        assert_eq!(*b_matches, vec![2..5]);
+        // Asking for the other document again does not search it again.
+        assert!(std::sync::Arc::ptr_eq(&a_matches, &state.find_matches(&a)));
+        assert!(std::sync::Arc::ptr_eq(&b_matches, &state.find_matches(&b)));
+    }
+
+    #[test]
+    fn patterns_spanning_lines_search_everything_again() {
+        let mut state = test_state(&[("a.txt", "a\nb\n")]);
+        let path = state.open_loaded("a.txt");
+        state.open_find(false);
+        state.toggle_find_regex();
+        state.set_find_query(r"a\nb".to_string());
+        assert_eq!(*state.find_matches(&path), vec![0..3]);
+
+        state.set_cursors(path.clone(), &[(1, 1)]);
+        state.insert_character('c');
+        assert_eq!(*state.find_matches(&path), vec![0..3]);
+    }
+}
@@ -0,0 +1,412 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
+
+use crossbeam_channel::{RecvTimeoutError, Sender};
+
+use lsp_types::{
+    Documentation, HoverContents, MarkedString, MarkupContent, MarkupKind, ParameterLabel,
+    SignatureHelp,
+};
+use tuan_rpc::proxy::ProxyResponse;
+
+use super::{
+    PaneId,
+    lsp::{LspResponse, next_request_id},
+};
+use crate::{
+    document::{RangeStyle, highlight},
+    editor_view::EditorConfig,
+    markdown::{self, RichText},
+    theme::Style,
+};
+
+/// How long the pointer rests on a character before its hover information is requested.
+const HOVER_DELAY: Duration = Duration::from_millis(500);
+
+/// Sends [`LspResponse::HoverDelayElapsed`] once the pointer rested on a
+/// position for [`HOVER_DELAY`], from a single thread for all the moves.
+#[derive(Clone)]
+pub(super) struct HoverTimer {
+    tx: Sender<Option<(PaneId, (usize, usize))>>,
+}
+
+impl HoverTimer {
+    /// Starts the timer thread, which stops once every clone of the timer is dropped.
+    pub(super) fn new(lsp_response_tx: Sender<LspResponse>) -> Self {
+        let (tx, rx) = crossbeam_channel::unbounded::<Option<(PaneId, (usize, usize))>>();
+        std::thread::Builder::new()
+            .name("hover timer".to_string())
+            .spawn(move || {
+                let mut pending = None;
+                loop {
+                    let received = match pending {
+                        Some(_) => rx.recv_timeout(HOVER_DELAY),
+                        None => rx.recv().map_err(|_| RecvTimeoutError::Disconnected),
+                    };
+                    match received {
+                        Ok(position) => pending = position,
+                        Err(RecvTimeoutError::Timeout) => {
+                            if let Some((pane, position)) = pending.take() {
+                                let _ = lsp_response_tx
+                                    .send(LspResponse::HoverDelayElapsed { pane, position });
+                            }
+                        }
+                        Err(RecvTimeoutError::Disconnected) => return,
+                    }
+                }
+            })
+            .expect("Failed to start the hover timer thread");
+        Self { tx }
+    }
+
+    /// Waits again for the pointer to rest on `position` of a pane, or stops
+    /// waiting with `None`.
+    pub(super) fn restart(&self, position: Option<(PaneId, (usize, usize))>) {
+        let _ = self.tx.send(position);
+    }
+}
+
+/// A popup of language server information at a position of a document:
+/// the hover information or the signature help.
+#[derive(Debug, Clone)]
+pub struct InfoPopup {
+    request_id: usize,
+    pub pane: PaneId,
+    pub path: PathBuf,
+    /// The `(line, column)` the popup is shown at.
+    pub position: (usize, usize),
+    /// Whether the pointer opened the popup, rather than a key.
+    pub from_pointer: bool,
+    /// `None` until the language server answers.
+    pub content: Option<Arc<RichText>>,
+}
+
+impl super::EditorState {
+    /// Shows the hover information of the character under the first cursor.
+    pub fn show_hover(&mut self) {
+        let Some(cursor) = self.focused_cursors().first() else {
+            return;
+        };
+        let position = (cursor.line, cursor.column);
+        self.request_hover(position, false);
+    }
+
+    /// Requests the hover information at `position` in the focused document.
+    fn request_hover(&mut self, position: (usize, usize), from_pointer: bool) {
+        let Some(path) = self.focused_document_path.clone() else {
+            return;
+        };
+        let Some(lsp_position) = self.documents.lock().unwrap().get(&path).map(|document| {
+            let line_start = document.line_range(position.0).start;
+            document.lsp_position(line_start + position.1)
+        }) else {
+            return;
+        };
+
+        let request_id = next_request_id();
+        self.hover = Some(InfoPopup {
+            request_id,
+            pane: self.active_pane,
+            path: path.clone(),
+            position,
+            from_pointer,
+            content: None,
+        });
+
+        let lsp_response_tx = self.lsp_response_tx.clone();
+        let config = self.config.clone();
+        let hover_path = path.clone();
+        self.proxy
+            .proxy_rpc
+            .get_hover(request_id, path, lsp_position, move |result| {
+                let content = match result {
+                    Ok(ProxyResponse::HoverResponse { hover, .. }) => {
+                        render_hover(hover.contents, &hover_path, &config)
+                    }
+                    Ok(response) => {
+                        tracing::debug!("Unexpected response to hover: {response:?}");
+                        return;
+                    }
+                    Err(err) => {
+                        tracing::debug!("Failed to get hover: {}", err.message);
+                        return;
+                    }
+                };
+                if let Err(err) = lsp_response_tx.send(LspResponse::Hover {
+                    request_id,
+                    content,
+                }) {
+                    tracing::error!("{:?}", err);
+                }
+            });
+    }
+
+    /// Shows the hover information of a request, unless the popup was closed since.
+    pub(super) fn set_hover_content(&mut self, request_id: usize, content: RichText) {
+        if let Some(hover) = self
+            .hover
+            .as_mut()
+            .filter(|hover| hover.request_id == request_id)
+        {
+            hover.content = (!content.is_empty()).then(|| Arc::new(content));
+        }
+    }
+
+    /// Sets the `(line, column)` under the pointer in `pane`, `None` when it left the text.
+    ///
+    /// The hover information opened by the pointer closes, and is requested
+    /// again once the pointer rests on the new position.
+    pub fn set_hover_position(&mut self, pane: PaneId, position: Option<(usize, usize)>) {
+        let position = position.map(|position| (pane, position));
+        if position == self.hover_position {
+            return;
+        }
+        self.hover_position = position;
+        if self.hover.as_ref().is_some_and(|hover| hover.from_pointer) {
+            self.hover = None;
+        }
+        // The hover information is requested once the pointer rests there.
+        self.hover_timer.restart(position);
+    }
+
+    /// Requests the hover information at `position` of `pane` if the pointer is still there.
+    pub(super) fn hover_delay_elapsed(&mut self, pane: PaneId, position: (usize, usize)) {
+        if self.hover_position != Some((pane, position)) {
+            return;
+        }
+        self.with_pane(pane, |state| state.request_hover(position, true));
+    }
+
+    /// Requests, updates or closes the signature help after `typed` was
+    /// inserted: it opens at `(`, is updated at `,` and closes at `)`.
+    pub(super) fn update_signature_help(&mut self, typed: char) {
+        match typed {
+            '(' | ',' => self.request_signature_help(),
+            ')' => self.signature_help = None,
+            _ => {}
+        }
+    }
+
+    fn request_signature_help(&mut self) {
+        let Some(path) = self.focused_document_path.clone() else {
+            return;
+        };
+        let Some(cursor) = self.focused_cursors().first() else {
+            return;
+        };
+        let position = (cursor.line, cursor.column);
+        let offset = cursor.get_cursor_offset();
+        let Some((buffer_id, lsp_position)) = self
+            .documents
+            .lock()
+            .unwrap()
+            .get(&path)
+            .map(|document| (document.buffer_id, document.lsp_position(offset)))
+        else {
+            return;
+        };
+
+        let request_id = next_request_id();
+        // Keep showing the previous signature until the new one arrives.
+        let content = self
+            .signature_help
+            .as_ref()
+            .and_then(|signature_help| signature_help.content.clone());
+        self.signature_help = Some(InfoPopup {
+            request_id,
+            pane: self.active_pane,
+            path: path.clone(),
+            position,
+            from_pointer: false,
+            content,
+        });
+
+        let lsp_response_tx = self.lsp_response_tx.clone();
+        let config = self.config.clone();
+        self.proxy
+            .proxy_rpc
+            .get_signature(buffer_id, lsp_position, move |result| {
+                let content = match result {
+                    Ok(ProxyResponse::GetSignature { resp }) => {
+                        render_signature_help(resp, &path, &config)
+                    }
+                    Ok(response) => {
+                        tracing::debug!("Unexpected response to signature help: {response:?}");
+                        return;
+                    }
+                    Err(err) => {
+                        tracing::debug!("Failed to get signature help: {}", err.message);
+                        return;
+                    }
+                };
+                if let Err(err) = lsp_response_tx.send(LspResponse::SignatureHelp {
+                    request_id,
+                    content,
+                }) {
+                    tracing::error!("{:?}", err);
+                }
+            });
+    }
+
+    /// Shows the signature of a request, or closes the popup when there is
+    /// none, unless it was closed since.
+    pub(super) fn set_signature_help_content(
+        &mut self,
+        request_id: usize,
+        content: Option<RichText>,
+    ) {
+        if !self
+            .signature_help
+            .as_ref()
+            .is_some_and(|signature_help| signature_help.request_id == request_id)
+        {
+            return;
+        }
+        match content {
+            Some(content) => {
+                if let Some(signature_help) = self.signature_help.as_mut() {
+                    signature_help.content = Some(Arc::new(content));
+                }
+            }
+            None => self.signature_help = None,
+        }
+    }
+}
+
+fn render_hover(contents: HoverContents, path: &Path, config: &EditorConfig) -> RichText {
+    let render_marked = |marked: MarkedString| match marked {
+        MarkedString::String(text) => markdown::render(&text, path, config),
+        MarkedString::LanguageString(code) => {
+            let styles = highlight::highlight_code(&code.value, Some(&code.language), path, config);
+            let mut text = RichText::default();
+            text.push_styled(&code.value, styles);
+            text
+        }
+    };
+    match contents {
+        HoverContents::Scalar(marked) => render_marked(marked),
+        HoverContents::Array(marked) => {
+            marked
+                .into_iter()
+                .map(render_marked)
+                .fold(RichText::default(), |mut text, part| {
+                    text.append(part);
+                    text
+                })
+        }
+        HoverContents::Markup(markup) => render_markup(markup, path, config),
+    }
+}
+
+fn render_markup(markup: MarkupContent, path: &Path, config: &EditorConfig) -> RichText {
+    match markup.kind {
+        MarkupKind::Markdown => markdown::render(&markup.value, path, config),
+        MarkupKind::PlainText => RichText::plain(markup.value),
+    }
+}
+
+fn render_documentation(
+    documentation: Documentation,
+    path: &Path,
+    config: &EditorConfig,
+) -> RichText {
+    match documentation {
+        Documentation::String(text) => RichText::plain(text),
+        Documentation::MarkupContent(markup) => render_markup(markup, path, config),
+    }
+}
+
+/// Renders the active signature, highlighted in the language of `path`, with
+/// its active parameter in bold and the documentation of both below it.
+fn render_signature_help(
+    help: SignatureHelp,
+    path: &Path,
+    config: &EditorConfig,
+) -> Option<RichText> {
+    let active_signature = help.active_signature.unwrap_or(0) as usize;
+    let active_parameter = help.active_parameter;
+    let mut signatures = help.signatures;
+    if active_signature >= signatures.len() {
+        return None;
+    }
+    let signature = signatures.swap_remove(active_signature);
+
+    let mut text = RichText::default();
+    let styles = highlight::highlight_code(&signature.label, None, path, config);
+    text.push_styled(&signature.label, styles);
+
+    let parameter = signature
+        .active_parameter
+        .or(active_parameter)
+        .and_then(|index| signature.parameters.as_ref()?.get(index as usize).cloned());
+    if let Some(parameter) = parameter {
+        let range = match &parameter.label {
+            ParameterLabel::Simple(label) => signature
+                .label
+                .find(label.as_str())
+                .map(|start| start..start + label.len()),
+            ParameterLabel::LabelOffsets([start, end]) => {
+                utf16_to_byte_offset(&signature.label, *start as usize)
+                    .zip(utf16_to_byte_offset(&signature.label, *end as usize))
+                    .map(|(start, end)| start..end)
+            }
+        };
+        if let Some(range) = range {
+            text.styles.push(RangeStyle {
+                start: range.start,
+                end: range.end,
+                style: Style::match_highlight(),
+            });
+        }
+        if let Some(documentation) = parameter.documentation {
+            text.append(render_documentation(documentation, path, config));
+        }
+    }
+    if let Some(documentation) = signature.documentation {
+        text.append(render_documentation(documentation, path, config));
+    }
+    Some(text)
+}
+
+/// The byte offset in `text` of an offset counted in UTF-16 code units.
+fn utf16_to_byte_offset(text: &str, offset: usize) -> Option<usize> {
+    let mut utf16 = 0;
+    for (index, c) in text.char_indices() {
+        if utf16 >= offset {
+            return (utf16 == offset).then_some(index);
+        }
+        utf16 += c.len_utf16();
+    }
+    (utf16 == offset).then_some(text.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_hover_timer_only_fires_for_the_last_position() {
+        let (tx, rx) = crossbeam_channel::unbounded();
+        let timer = HoverTimer::new(tx);
+        let pane = PaneId::next();
+
+        timer.restart(Some((pane, (0, 1))));
+        timer.restart(Some((pane, (0, 2))));
+        let response = rx.recv_timeout(HOVER_DELAY * 4).unwrap();
+        assert!(matches!(
+            response,
+            LspResponse::HoverDelayElapsed {
+                position: (0, 2),
+                ..
+            }
+        ));
+
+        // Leaving the text cancels the delay.
+        timer.restart(Some((pane, (0, 3))));
+        timer.restart(None);
+        assert!(rx.recv_timeout(HOVER_DELAY * 2).is_err());
+    }
+}
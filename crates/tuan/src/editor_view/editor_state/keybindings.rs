@@ -20,7 +20,7 @@ impl super::EditorState {
 
         if let Some(action) = action {
             self.handle_action(&action);
-        } else if modifiers == Modifiers::empty() && let KeybindsKey::Char(c) = key {
+        } else if (modifiers & !Modifiers::SHIFT).is_empty() && let KeybindsKey::Char(c) = key {
             tracing::debug!("Insert character: {:?}", c);
             self.insert_character(c);
         }
@@ -0,0 +1,147 @@
+use std::{
+    path::PathBuf,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use crossbeam_channel::Receiver;
+use lsp_types::CodeActionOrCommand;
+use tuan_rpc::plugin::PluginId;
+
+use super::{
+    PaneId,
+    navigation::{LocationItem, NavigationKind},
+    search::SearchMatch,
+    workspace_edit::PendingEdit,
+};
+use crate::markdown::RichText;
+
+/// A new id for a request, whose response is dropped when a later request
+/// of the same kind was made meanwhile.
+pub(super) fn next_request_id() -> usize {
+    static REQUEST_ID_COUNTER: AtomicUsize = AtomicUsize::new(0);
+    REQUEST_ID_COUNTER.fetch_add(1, Ordering::Relaxed)
+}
+
+/// A response of the proxy to an LSP request of the editor.
+///
+/// The proxy answers on its own thread, so responses are sent through a
+/// channel to be applied on the UI thread by [`super::EditorState::handle_lsp_response`].
+#[derive(Debug)]
+pub enum LspResponse {
+    Locations {
+        kind: NavigationKind,
+        locations: Vec<LocationItem>,
+    },
+    Hover {
+        request_id: usize,
+        content: RichText,
+    },
+    SignatureHelp {
+        request_id: usize,
+        content: Option<RichText>,
+    },
+    /// Not a response, but sent the same way by the hover timer: the pointer
+    /// rested long enough on `position` to request its hover information.
+    HoverDelayElapsed {
+        pane: PaneId,
+        position: (usize, usize),
+    },
+    /// Not a response either: the document at `path` was loaded, so the
+    /// cursors placed in it before can be moved into its text.
+    DocumentLoaded(PathBuf),
+    /// Not a response either: the document at `path` was saved as `new_path`
+    /// at revision `rev`, and is now edited there.
+    SavedAs {
+        path: PathBuf,
+        new_path: PathBuf,
+        rev: u64,
+    },
+    /// Not a response either: the explorer entry at this path was trashed.
+    Trashed(PathBuf),
+    /// The current name of the symbol to rename, when the server gives one.
+    PrepareRename {
+        path: PathBuf,
+        position: lsp_types::Position,
+        placeholder: Option<String>,
+    },
+    CodeActions {
+        plugin_id: PluginId,
+        actions: Vec<CodeActionOrCommand>,
+    },
+    WorkspaceEdit(PendingEdit),
+    /// The matches of a project search in one file.
+    SearchResults {
+        request_id: usize,
+        path: PathBuf,
+        matches: Vec<SearchMatch>,
+    },
+    SearchFinished {
+        request_id: usize,
+        error: Option<String>,
+    },
+    /// Something the user should know, e.g. why a request did nothing.
+    Message(String),
+}
+
+impl super::EditorState {
+    /// The responses of the proxy, to be forwarded to [`Self::handle_lsp_response`] on the UI thread.
+    pub fn lsp_response_rx(&self) -> Receiver<LspResponse> {
+        self.lsp_response_rx.clone()
+    }
+
+    pub fn handle_lsp_response(&mut self, response: LspResponse) {
+        match response {
+            LspResponse::Locations { kind, locations } => self.show_locations(kind, locations),
+            LspResponse::Hover {
+                request_id,
+                content,
+            } => self.set_hover_content(request_id, content),
+            LspResponse::SignatureHelp {
+                request_id,
+                content,
+            } => self.set_signature_help_content(request_id, content),
+            LspResponse::HoverDelayElapsed { pane, position } => {
+                self.hover_delay_elapsed(pane, position)
+            }
+            LspResponse::DocumentLoaded(path) => self.clamp_cursors(&path),
+            LspResponse::SavedAs {
+                path,
+                new_path,
+                rev,
+            } => self.saved_as(path, new_path, rev),
+            LspResponse::Trashed(path) => self.close_trashed_tabs(&path),
+            LspResponse::PrepareRename {
+                path,
+                position,
+                placeholder,
+            } => self.show_rename_input(path, position, placeholder),
+            LspResponse::CodeActions { plugin_id, actions } => {
+                self.set_code_actions(plugin_id, actions)
+            }
+            LspResponse::WorkspaceEdit(edit) => self.set_pending_edit(edit),
+            LspResponse::SearchResults {
+                request_id,
+                path,
+                matches,
+            } => self.add_search_results(request_id, path, matches),
+            LspResponse::SearchFinished { request_id, error } => {
+                self.finish_search(request_id, error)
+            }
+            LspResponse::Message(message) => self.show_message(message),
+        }
+    }
+
+    /// The focused document and the LSP position of its first cursor, where
+    /// requests about the code under the cursor are made.
+    pub(super) fn lsp_request_position(&self) -> Option<(PathBuf, lsp_types::Position)> {
+        let path = self.focused_document_path.clone()?;
+        let offset = self.focused_cursors().first()?.get_cursor_offset();
+        let position = self
+            .documents
+            .lock()
+            .unwrap()
+            .get(&path)?
+            .lsp_position(offset);
+        Some((path, position))
+    }
+}
@@ -1,11 +1,44 @@
-mod editor_state;
+pub(crate) mod action;
+mod clipboard;
+mod command_palette;
+mod completion;
 mod cursors;
-mod scrolling;
+mod diagnostics;
+mod editing;
+mod editor_state;
+mod explorer;
+mod find;
 mod focus;
-mod styles;
-mod open;
+mod hover;
 mod keybindings;
-pub(crate) mod action;
-mod editing;
+mod lsp;
+mod navigation;
+mod notifications;
+mod open;
+mod panes;
+mod quick_open;
+mod refactoring;
+mod reload;
+mod save;
+mod scrolling;
+mod search;
+mod styles;
+mod tabs;
+#[cfg(test)]
+mod test_support;
+mod workspace_edit;
 
+pub use command_palette::{CommandPalette, PaletteItem};
+pub use completion::{Completion, SnippetSession};
+pub use diagnostics::{DiagnosticSpan, Problem};
 pub use editor_state::*;
+pub use find::FindBar;
+pub use hover::InfoPopup;
+pub use lsp::LspResponse;
+pub use navigation::{LocationItem, LocationList, NavigationKind};
+pub use panes::{PaneId, PaneLayout, PaneView};
+pub use quick_open::{QuickOpen, QuickOpenItem};
+pub use refactoring::{CodeActionList, RenameInput};
+pub use search::{ProjectSearch, SearchFile, SearchMatch};
+pub use tabs::Tab;
+pub use workspace_edit::{EditPreview, FileEdit, PendingEdit};
@@ -0,0 +1,256 @@
+use std::{collections::HashMap, path::PathBuf, sync::Mutex};
+
+use lsp_types::{GotoDefinitionResponse, Location};
+use tuan_rpc::proxy::ProxyResponse;
+
+use super::lsp::{LspResponse, next_request_id};
+use crate::document::Document;
+
+/// How many places the back/forward history remembers.
+const HISTORY_LIMIT: usize = 100;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NavigationKind {
+    Definition,
+    TypeDefinition,
+    References,
+    Implementation,
+}
+
+impl NavigationKind {
+    pub fn title(self) -> &'static str {
+        match self {
+            Self::Definition => "Definitions",
+            Self::TypeDefinition => "Type definitions",
+            Self::References => "References",
+            Self::Implementation => "Implementations",
+        }
+    }
+}
+
+/// A location found by a navigation request, with the text of its line.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LocationItem {
+    pub path: PathBuf,
+    pub position: lsp_types::Position,
+    pub preview: String,
+}
+
+/// The locations of a navigation request that found several, to pick one from.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LocationList {
+    pub kind: NavigationKind,
+    pub items: Vec<LocationItem>,
+}
+
+/// The places jumped from and back from, as `(line, column)` in a file.
+#[derive(Debug, Clone, Default)]
+pub struct NavigationHistory {
+    back: Vec<(PathBuf, (usize, usize))>,
+    forward: Vec<(PathBuf, (usize, usize))>,
+}
+
+impl NavigationHistory {
+    pub(super) fn can_go_back(&self) -> bool {
+        !self.back.is_empty()
+    }
+
+    pub(super) fn can_go_forward(&self) -> bool {
+        !self.forward.is_empty()
+    }
+}
+
+impl super::EditorState {
+    pub fn go_to_definition(&mut self) {
+        self.request_locations(NavigationKind::Definition);
+    }
+
+    pub fn go_to_type_definition(&mut self) {
+        self.request_locations(NavigationKind::TypeDefinition);
+    }
+
+    pub fn find_references(&mut self) {
+        self.request_locations(NavigationKind::References);
+    }
+
+    pub fn go_to_implementation(&mut self) {
+        self.request_locations(NavigationKind::Implementation);
+    }
+
+    /// Asks the language server for the locations of `kind` of the symbol under the first cursor.
+    fn request_locations(&mut self, kind: NavigationKind) {
+        let Some((path, position)) = self.lsp_request_position() else {
+            return;
+        };
+        let callback = {
+            let lsp_response_tx = self.lsp_response_tx.clone();
+            let documents = self.documents.clone();
+            move |result| {
+                let locations = match result {
+                    Ok(ProxyResponse::GetDefinitionResponse { definition, .. })
+                    | Ok(ProxyResponse::GetTypeDefinition { definition, .. }) => {
+                        locations_of(definition)
+                    }
+                    Ok(ProxyResponse::GetReferencesResponse { references }) => references,
+                    Ok(ProxyResponse::GotoImplementationResponse { resp, .. }) => {
+                        resp.map(locations_of).unwrap_or_default()
+                    }
+                    Ok(response) => {
+                        tracing::debug!("Unexpected response to {kind:?}: {response:?}");
+                        return;
+                    }
+                    Err(err) => {
+                        tracing::error!("Failed to find {kind:?}: {}", err.message);
+                        return;
+                    }
+                };
+                let locations = location_items(locations, &documents);
+                if let Err(err) = lsp_response_tx.send(LspResponse::Locations { kind, locations }) {
+                    tracing::error!("{:?}", err);
+                }
+            }
+        };
+
+        let proxy_rpc = &self.proxy.proxy_rpc;
+        let request_id = next_request_id();
+        match kind {
+            NavigationKind::Definition => {
+                proxy_rpc.get_definition(request_id, path, position, callback)
+            }
+            NavigationKind::TypeDefinition => {
+                proxy_rpc.get_type_definition(request_id, path, position, callback)
+            }
+            NavigationKind::References => proxy_rpc.get_references(path, position, callback),
+            NavigationKind::Implementation => {
+                proxy_rpc.go_to_implementation(path, position, callback)
+            }
+        }
+    }
+
+    /// Jumps to the only location found, or lists them to pick one.
+    pub(super) fn show_locations(&mut self, kind: NavigationKind, items: Vec<LocationItem>) {
+        match items.as_slice() {
+            [] => {
+                tracing::info!("No {} found", kind.title().to_lowercase());
+                self.locations = None;
+            }
+            [item] => {
+                self.locations = None;
+                self.jump_to_location(item.clone());
+            }
+            _ => self.locations = Some(LocationList { kind, items }),
+        }
+    }
+
+    pub fn jump_to_location(&mut self, item: LocationItem) {
+        let position = self.editor_position(&item.path, item.position);
+        self.navigate_to(item.path, position);
+    }
+
+    pub fn close_locations(&mut self) {
+        self.locations = None;
+    }
+
+    /// Opens `path` with the cursor at `position`, remembering where it was
+    /// to come back with [`Self::navigate_back`].
+    pub(super) fn navigate_to(&mut self, path: PathBuf, position: (usize, usize)) {
+        if let Some(current) = self.current_location() {
+            let history = &mut self.navigation_history;
+            if history.back.last() != Some(&current) {
+                history.back.push(current);
+            }
+            if history.back.len() > HISTORY_LIMIT {
+                history.back.remove(0);
+            }
+            history.forward.clear();
+        }
+        self.open_file_at(path, position);
+    }
+
+    pub fn navigate_back(&mut self) {
+        let Some(entry) = self.navigation_history.back.pop() else {
+            return;
+        };
+        if let Some(current) = self.current_location() {
+            self.navigation_history.forward.push(current);
+        }
+        self.open_file_at(entry.0, entry.1);
+    }
+
+    pub fn navigate_forward(&mut self) {
+        let Some(entry) = self.navigation_history.forward.pop() else {
+            return;
+        };
+        if let Some(current) = self.current_location() {
+            self.navigation_history.back.push(current);
+        }
+        self.open_file_at(entry.0, entry.1);
+    }
+
+    fn current_location(&self) -> Option<(PathBuf, (usize, usize))> {
+        let path = self.focused_document_path.clone()?;
+        let cursor = self.focused_cursors().first()?;
+        Some((path, (cursor.line, cursor.column)))
+    }
+
+    /// Converts an LSP position in `path` to a `(line, column)` position.
+    ///
+    /// The character is counted in UTF-16 code units, which only an open
+    /// document can convert: it is used as the column otherwise.
+    pub(super) fn editor_position(
+        &self,
+        path: &PathBuf,
+        position: lsp_types::Position,
+    ) -> (usize, usize) {
+        self.documents
+            .lock()
+            .unwrap()
+            .get(path)
+            .map(|document| document.offset_to_position(document.offset_of_position(&position)))
+            .unwrap_or((position.line as usize, position.character as usize))
+    }
+}
+
+fn locations_of(response: GotoDefinitionResponse) -> Vec<Location> {
+    match response {
+        GotoDefinitionResponse::Scalar(location) => vec![location],
+        GotoDefinitionResponse::Array(locations) => locations,
+        GotoDefinitionResponse::Link(links) => links
+            .into_iter()
+            .map(|link| Location {
+                uri: link.target_uri,
+                range: link.target_selection_range,
+            })
+            .collect(),
+    }
+}
+
+/// Adds the text of their line to `locations`, from the open documents or else from the disk.
+fn location_items(
+    locations: Vec<Location>,
+    documents: &Mutex<HashMap<PathBuf, Document>>,
+) -> Vec<LocationItem> {
+    let mut files = HashMap::<PathBuf, Option<String>>::new();
+    locations
+        .into_iter()
+        .filter_map(|location| {
+            let path = location.uri.to_file_path().ok()?;
+            let line = location.range.start.line as usize;
+            let preview = match documents.lock().unwrap().get(&path) {
+                Some(document) => document.text_in(document.line_range(line)),
+                None => files
+                    .entry(path.clone())
+                    .or_insert_with(|| std::fs::read_to_string(&path).ok())
+                    .as_deref()
+                    .and_then(|content| content.lines().nth(line))
+                    .unwrap_or_default()
+                    .to_string(),
+            };
+            Some(LocationItem {
+                path,
+                position: location.range.start,
+                preview: preview.trim().to_string(),
+            })
+        })
+        .collect()
+}
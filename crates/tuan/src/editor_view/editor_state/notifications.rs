@@ -0,0 +1,72 @@
+use crossbeam_channel::Receiver;
+use tuan_rpc::{
+    core::{CoreNotification, FileChanged},
+    proxy::ProxyStatus,
+};
+
+use crate::explorer;
+
+impl super::EditorState {
+    /// The notifications sent by the proxy, to be forwarded to
+    /// [`Self::handle_notification`] on the UI thread.
+    pub fn notification_rx(&self) -> Receiver<CoreNotification> {
+        self.proxy.notification_rx.clone()
+    }
+
+    /// Applies a notification of the proxy to the editor.
+    pub fn handle_notification(&mut self, notification: CoreNotification) {
+        match notification {
+            CoreNotification::ProxyStatus { status } => {
+                tracing::info!("Proxy status: {:?}", status);
+                self.proxy_status = Some(status);
+            }
+            CoreNotification::OpenFileChanged { path, content } => {
+                let reload = match self.documents.lock().unwrap().get_mut(&path) {
+                    Some(document) => {
+                        let modified = matches!(content, FileChanged::Change(_));
+                        document.set_disk_change(content);
+                        modified && !document.is_dirty()
+                    }
+                    None => false,
+                };
+                // Without changes to lose, the document simply follows the file;
+                // the conflict bar is only for dirty documents.
+                if reload {
+                    self.reload_disk_change(path);
+                }
+            }
+            CoreNotification::CompletionResponse {
+                request_id, resp, ..
+            } => {
+                self.set_completion_items(request_id, resp);
+            }
+            CoreNotification::PublishDiagnostics { diagnostics } => {
+                self.set_diagnostics(diagnostics);
+            }
+            CoreNotification::WorkspaceFileChange { .. } => {
+                explorer::refresh(&self.explorer, &self.proxy.proxy_rpc);
+            }
+            notification => {
+                tracing::debug!("Unhandled notification: {:?}", notification);
+            }
+        }
+    }
+
+    /// Shows `message` in the status bar until dismissed or replaced.
+    pub(super) fn show_message(&mut self, message: String) {
+        self.status_message = Some(message);
+    }
+
+    pub fn dismiss_message(&mut self) {
+        self.status_message = None;
+    }
+
+    /// A short description of the proxy connection, for the status bar.
+    pub fn proxy_status_text(&self) -> &'static str {
+        match self.proxy_status {
+            None | Some(ProxyStatus::Connecting) => "Proxy connecting…",
+            Some(ProxyStatus::Connected) => "Proxy connected",
+            Some(ProxyStatus::Disconnected) => "Proxy disconnected",
+        }
+    }
+}
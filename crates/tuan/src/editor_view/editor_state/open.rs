@@ -2,32 +2,147 @@ use std::path::PathBuf;
 
 use tuan_rpc::{buffer::BufferId, proxy::ProxyResponse};
 
+use super::LspResponse;
 use crate::document;
 
+/// Changes a document once loaded, e.g. to apply edits to it.
+pub(super) type OnLoad = Box<dyn FnOnce(&mut document::Document) + Send>;
+
 impl super::EditorState {
-    pub fn open_file(&self, path: PathBuf) {
-        if let Some(document) = self.documents.lock().unwrap().get(&path) {
+    /// Opens `path` in a new tab, or does nothing if it is already open.
+    pub fn open_file(&mut self, path: PathBuf) {
+        self.open_file_with(path, |_| {});
+    }
+
+    /// Opens `path` like [`Self::open_file`], then lets `on_load` change the
+    /// document once loaded, e.g. to apply edits to it.
+    pub(super) fn open_file_with(
+        &mut self,
+        path: PathBuf,
+        on_load: impl FnOnce(&mut document::Document) + Send + 'static,
+    ) {
+        if self.open_documents.contains(&path) {
             return;
         }
+        self.add_tab(path.clone());
+        self.loading
+            .lock()
+            .unwrap()
+            .insert(path.clone(), vec![Box::new(on_load)]);
 
-        self.proxy
-            .proxy_rpc
-            .new_buffer(BufferId::next(), path.clone(), {
-                let documents = self.documents.clone();
-                let config = self.config.clone();
-                let path = path.clone();
-                {
-                    move |result| {
-                        if let Ok(ProxyResponse::NewBufferResponse { content, read_only }) = result
-                        {
-                            let document =
-                                document::Document::new(path.clone(), content, read_only, config);
-                            documents.lock().unwrap().insert(path.clone(), document);
-
-                            Self::update_styles_with_syntax(documents, path);
+        let buffer_id = BufferId::next();
+        self.proxy.proxy_rpc.new_buffer(buffer_id, path.clone(), {
+            let documents = self.documents.clone();
+            let styles_worker = self.styles_worker.clone();
+            let lsp_response_tx = self.lsp_response_tx.clone();
+            let loading = self.loading.clone();
+            let proxy_rpc = self.proxy.proxy_rpc.clone();
+            let config = self.config.clone();
+            let path = path.clone();
+            {
+                move |result| {
+                    if let Ok(ProxyResponse::NewBufferResponse { content, read_only }) = result {
+                        let mut document = document::Document::new(
+                            path.clone(),
+                            buffer_id,
+                            content,
+                            read_only,
+                            proxy_rpc,
+                            config,
+                        );
+                        // Holding `loading` until the document is inserted, so
+                        // that nothing can be queued once the callbacks ran.
+                        let mut loading = loading.lock().unwrap();
+                        for on_load in loading.remove(&path).unwrap_or_default() {
+                            on_load(&mut document);
                         }
+                        documents.lock().unwrap().insert(path.clone(), document);
+                        drop(loading);
+
+                        let _ = lsp_response_tx.send(LspResponse::DocumentLoaded(path.clone()));
+                        styles_worker.update(path);
+                    } else {
+                        tracing::error!("Failed to open {:?}", path);
+                        loading.lock().unwrap().remove(&path);
                     }
                 }
-            });
+            }
+        });
+    }
+
+    /// Lets `on_load` change the document at `path` once loaded, `false` if
+    /// it is not being loaded: it is either loaded already or not open.
+    pub(super) fn queue_on_load(
+        &mut self,
+        path: &PathBuf,
+        on_load: impl FnOnce(&mut document::Document) + Send + 'static,
+    ) -> bool {
+        let mut loading = self.loading.lock().unwrap();
+        match loading.get_mut(path) {
+            Some(callbacks) => {
+                callbacks.push(Box::new(on_load));
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// `position` moved into the text of `path` if it is loaded, e.g. a line
+    /// typed past its end.
+    pub(super) fn clamp_position(
+        &self,
+        path: &PathBuf,
+        position: (usize, usize),
+    ) -> (usize, usize) {
+        self.documents
+            .lock()
+            .unwrap()
+            .get(path)
+            .map_or(position, |document| document.clamp_position(position))
+    }
+
+    /// Opens `path`, focuses it and places a single cursor at `position` (0-based line/column).
+    ///
+    /// A position past the end of the text is moved into it, once the
+    /// document is loaded if it is not yet.
+    pub fn open_file_at(&mut self, path: PathBuf, position: (usize, usize)) {
+        self.open_file(path.clone());
+        self.note_recent_file(&path);
+        let position = self.clamp_position(&path, position);
+
+        // The document is loaded asynchronously, so focus it without waiting for `focus_document`.
+        self.focused_document_path = Some(path.clone());
+        self.clear_cursors(path.clone());
+        self.add_cursor(path, &position);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::super::{LspResponse, test_support::test_state};
+
+    #[test]
+    fn positions_past_the_end_are_moved_into_the_text() {
+        let mut state = test_state(&[("a.txt", "ab\ncd")]);
+        let path = state.workspace_path.join("a.txt");
+        let position = |state: &super::super::EditorState| {
+            let cursor = &state.document_cursors[&path][0];
+            (cursor.line, cursor.column)
+        };
+
+        // Before the document is loaded, then once it is.
+        state.open_file_at(path.clone(), (9, 9));
+        let response = state
+            .lsp_response_rx()
+            .recv_timeout(Duration::from_secs(5))
+            .unwrap();
+        assert!(matches!(&response, LspResponse::DocumentLoaded(loaded) if *loaded == path));
+        state.handle_lsp_response(response);
+        assert_eq!(position(&state), (1, 2));
+
+        state.open_file_at(path.clone(), (0, 9));
+        assert_eq!(position(&state), (0, 2));
     }
 }
@@ -0,0 +1,519 @@
+use std::{
+    collections::HashMap,
+    ops::Range,
+    path::PathBuf,
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicU64, Ordering},
+    },
+};
+
+use xilem::view::Axis;
+
+use super::{DiagnosticSpan, InfoPopup, completion::shift_offset};
+use crate::{
+    document::Document,
+    editor_view::{EditorConfig, paint::cursor::Cursor},
+    markdown::RichText,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PaneId(u64);
+
+impl PaneId {
+    pub fn next() -> Self {
+        static PANE_ID_COUNTER: AtomicU64 = AtomicU64::new(0);
+        Self(PANE_ID_COUNTER.fetch_add(1, Ordering::Relaxed))
+    }
+}
+
+/// How the editor panes are arranged, as a tree of splits.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PaneLayout {
+    Pane(PaneId),
+    /// Children laid out along `axis`: `Horizontal` is side by side.
+    Split {
+        axis: Axis,
+        children: Vec<PaneLayout>,
+    },
+}
+
+impl PaneLayout {
+    /// The panes, in reading order.
+    pub fn panes(&self) -> Vec<PaneId> {
+        match self {
+            Self::Pane(id) => vec![*id],
+            Self::Split { children, .. } => children.iter().flat_map(Self::panes).collect(),
+        }
+    }
+
+    /// Puts `new` right after `target`, splitting it along `axis` unless it
+    /// already is in such a split.
+    fn insert_after(&mut self, target: PaneId, new: PaneId, axis: Axis) -> bool {
+        match self {
+            Self::Pane(id) if *id == target => {
+                *self = Self::Split {
+                    axis,
+                    children: vec![Self::Pane(target), Self::Pane(new)],
+                };
+                true
+            }
+            Self::Pane(_) => false,
+            Self::Split {
+                axis: split_axis,
+                children,
+            } => {
+                if *split_axis == axis
+                    && let Some(index) = children.iter().position(|c| *c == Self::Pane(target))
+                {
+                    children.insert(index + 1, Self::Pane(new));
+                    return true;
+                }
+                children
+                    .iter_mut()
+                    .any(|child| child.insert_after(target, new, axis))
+            }
+        }
+    }
+
+    /// Removes `target`, collapsing the splits left with a single child.
+    fn remove(&mut self, target: PaneId) {
+        let Self::Split { children, .. } = self else {
+            return;
+        };
+        children.retain(|child| *child != Self::Pane(target));
+        for child in children.iter_mut() {
+            child.remove(target);
+        }
+        if children.len() == 1 {
+            *self = children.remove(0);
+        }
+    }
+}
+
+/// The view state of an inactive pane.
+///
+/// The active pane's state lives in the `focused_document_path`,
+/// `document_scrollings` and `document_cursors` fields of the editor state,
+/// so that everything else only deals with the active pane.
+#[derive(Clone, Default)]
+pub struct Pane {
+    focused_document_path: Option<PathBuf>,
+    document_scrollings: HashMap<PathBuf, (f64, f64)>,
+    document_cursors: HashMap<PathBuf, Vec<Cursor>>,
+}
+
+impl Pane {
+    /// Forgets a closed document, focusing `fallback` instead if it was focused.
+    pub(super) fn forget(&mut self, path: &PathBuf, fallback: Option<PathBuf>) {
+        self.document_scrollings.remove(path);
+        self.document_cursors.remove(path);
+        if self.focused_document_path.as_ref() == Some(path) {
+            self.focused_document_path = fallback;
+        }
+    }
+
+    /// Follows a document saved as `new_path`, keeping its cursors and scrolling.
+    pub(super) fn rename(&mut self, path: &PathBuf, new_path: &PathBuf) {
+        rename_document(
+            path,
+            new_path,
+            &mut self.focused_document_path,
+            &mut self.document_scrollings,
+            &mut self.document_cursors,
+        );
+    }
+
+    pub(super) fn cursors_mut(&mut self) -> impl Iterator<Item = &mut Cursor> {
+        self.document_cursors.values_mut().flatten()
+    }
+
+    pub(super) fn document_cursors_mut(
+        &mut self,
+        path: &PathBuf,
+    ) -> impl Iterator<Item = &mut Cursor> {
+        self.document_cursors.get_mut(path).into_iter().flatten()
+    }
+
+    /// The offsets in `document` of the head and anchor of each cursor on it.
+    fn cursor_offsets(&self, document: &Document) -> Option<Vec<(usize, Option<usize>)>> {
+        let offset_of = |(line, column): (usize, usize)| document.line_range(line).start + column;
+        let cursors = self.document_cursors.get(&document.path)?;
+        Some(
+            cursors
+                .iter()
+                .map(|cursor| {
+                    (
+                        offset_of((cursor.line, cursor.column)),
+                        cursor.anchor.map(offset_of),
+                    )
+                })
+                .collect(),
+        )
+    }
+
+    /// Moves the cursors on `path` to `positions`, as `(head, anchor)`.
+    fn move_cursors(
+        &mut self,
+        path: &PathBuf,
+        positions: Vec<((usize, usize), Option<(usize, usize)>)>,
+    ) {
+        let Some(cursors) = self.document_cursors.get_mut(path) else {
+            return;
+        };
+        for (cursor, (head, anchor)) in cursors.iter_mut().zip(positions) {
+            (cursor.line, cursor.column) = head;
+            cursor.anchor = anchor;
+        }
+    }
+}
+
+/// Moves the state of a pane about the document at `path` to `new_path`.
+pub(super) fn rename_document(
+    path: &PathBuf,
+    new_path: &PathBuf,
+    focused_document_path: &mut Option<PathBuf>,
+    document_scrollings: &mut HashMap<PathBuf, (f64, f64)>,
+    document_cursors: &mut HashMap<PathBuf, Vec<Cursor>>,
+) {
+    if let Some(scrolling) = document_scrollings.remove(path) {
+        document_scrollings.insert(new_path.clone(), scrolling);
+    }
+    if let Some(mut cursors) = document_cursors.remove(path) {
+        for cursor in &mut cursors {
+            cursor.set_document_path(new_path.clone());
+        }
+        document_cursors.insert(new_path.clone(), cursors);
+    }
+    if focused_document_path.as_ref() == Some(path) {
+        *focused_document_path = Some(new_path.clone());
+    }
+}
+
+/// What a pane paints, taken from the editor state when the view is rebuilt.
+pub struct PaneView {
+    pub id: PaneId,
+    pub config: Arc<EditorConfig>,
+    /// The document focused in the pane.
+    pub document: Option<Document>,
+    pub scroll: (f64, f64),
+    pub cursors: Vec<Cursor>,
+    pub diagnostics: Vec<DiagnosticSpan>,
+    pub find_matches: Arc<Vec<Range<usize>>>,
+    /// The visible completion items and the selected one, when they show in this pane.
+    pub completion: Option<(Vec<String>, Option<usize>)>,
+    pub hover: Option<InfoPopup>,
+    /// The position under the pointer, when it is over this pane.
+    pub hover_position: Option<(usize, usize)>,
+    /// The messages of the diagnostics under `hover_position`.
+    pub hovered_diagnostics: Vec<String>,
+    pub signature_help: Option<Arc<RichText>>,
+    pub line_selection_anchor: Option<usize>,
+    viewport_heights: Arc<Mutex<HashMap<PaneId, f64>>>,
+}
+
+impl PaneView {
+    /// Records the height of the pane, as last painted.
+    pub fn set_viewport_height(&self, height: f64) {
+        self.viewport_heights
+            .lock()
+            .unwrap()
+            .insert(self.id, height);
+    }
+}
+
+impl super::EditorState {
+    /// Splits the active pane along `axis`, the new pane showing the same
+    /// document with its own scrolling and cursors, and focuses it.
+    pub fn split_pane(&mut self, axis: Axis) {
+        let new = PaneId::next();
+        if !self.layout.insert_after(self.active_pane, new, axis) {
+            return;
+        }
+
+        // The new pane starts as a copy of the active one.
+        let pane = self.active_pane_state();
+        self.parked_panes.insert(self.active_pane, pane);
+        self.active_pane = new;
+    }
+
+    pub fn focus_pane(&mut self, id: PaneId) {
+        let Some(pane) = self.parked_panes.remove(&id) else {
+            return;
+        };
+        let previous = std::mem::replace(&mut self.active_pane, id);
+        let previous_pane = self.swap_active_pane(pane);
+        self.parked_panes.insert(previous, previous_pane);
+    }
+
+    pub fn focus_next_pane(&mut self) {
+        self.cycle_pane(1);
+    }
+
+    pub fn focus_previous_pane(&mut self) {
+        self.cycle_pane(-1);
+    }
+
+    fn cycle_pane(&mut self, offset: isize) {
+        let panes = self.layout.panes();
+        let Some(index) = panes.iter().position(|id| *id == self.active_pane) else {
+            return;
+        };
+        let index = (index as isize + offset).rem_euclid(panes.len() as isize) as usize;
+        self.focus_pane(panes[index]);
+    }
+
+    /// Closes the active pane and focuses its neighbour. The last pane stays.
+    pub fn close_pane(&mut self) {
+        let panes = self.layout.panes();
+        if panes.len() < 2 {
+            return;
+        }
+        let index = panes
+            .iter()
+            .position(|id| *id == self.active_pane)
+            .unwrap_or(0);
+        let closed = self.active_pane;
+        self.focus_pane(panes[index.checked_sub(1).unwrap_or(1)]);
+        self.parked_panes.remove(&closed);
+        self.layout.remove(closed);
+    }
+
+    /// Runs `f` with pane `id` active, e.g. to scroll a pane without focusing it.
+    pub fn with_pane<R>(&mut self, id: PaneId, f: impl FnOnce(&mut Self) -> R) -> R {
+        let active = self.active_pane;
+        self.focus_pane(id);
+        let result = f(self);
+        self.focus_pane(active);
+        result
+    }
+
+    /// What pane `id` shows, for painting it.
+    pub fn pane_view(&self, id: PaneId) -> PaneView {
+        let (path, scrollings, cursors) = match self.parked_panes.get(&id) {
+            Some(pane) => (
+                pane.focused_document_path.as_ref(),
+                &pane.document_scrollings,
+                &pane.document_cursors,
+            ),
+            None => (
+                self.focused_document_path.as_ref(),
+                &self.document_scrollings,
+                &self.document_cursors,
+            ),
+        };
+        let document = path.and_then(|path| self.documents.lock().unwrap().get(path).cloned());
+        let shown_here =
+            |pane: PaneId, popup_path: &PathBuf| pane == id && path == Some(popup_path);
+        let hover_position = self
+            .hover_position
+            .filter(|(pane, _)| *pane == id)
+            .map(|(_, position)| position);
+
+        PaneView {
+            id,
+            config: self.config.clone(),
+            scroll: path
+                .and_then(|path| scrollings.get(path).copied())
+                .unwrap_or((0.0, 0.0)),
+            cursors: path
+                .and_then(|path| cursors.get(path).cloned())
+                .unwrap_or_default(),
+            diagnostics: path
+                .map(|path| self.document_diagnostics(path))
+                .unwrap_or_default(),
+            find_matches: path.map(|path| self.find_matches(path)).unwrap_or_default(),
+            completion: self
+                .completion
+                .as_ref()
+                .filter(|completion| shown_here(completion.pane, &completion.path))
+                .map(|completion| completion.visible_lines()),
+            hover: self
+                .hover
+                .clone()
+                .filter(|hover| shown_here(hover.pane, &hover.path)),
+            hovered_diagnostics: match (path, hover_position) {
+                (Some(path), Some(position)) => self.diagnostics_at(path, position),
+                _ => Vec::new(),
+            },
+            hover_position,
+            signature_help: self
+                .signature_help
+                .as_ref()
+                .filter(|signature_help| shown_here(signature_help.pane, &signature_help.path))
+                .and_then(|signature_help| signature_help.content.clone()),
+            line_selection_anchor: self
+                .line_selection_anchor
+                .filter(|_| id == self.active_pane),
+            viewport_heights: self.viewport_heights.clone(),
+            document,
+        }
+    }
+
+    /// Runs `edit` on the open document at `path`, and moves the cursors of
+    /// the inactive panes on the document along the changes it made, be it
+    /// an edit, an undo or a reload.
+    pub(super) fn edit_with_parked_cursors<R>(
+        &mut self,
+        path: &PathBuf,
+        edit: impl FnOnce(&mut Document) -> R,
+    ) -> Option<R> {
+        let mut documents = self.documents.lock().unwrap();
+        let document = documents.get_mut(path)?;
+        let parked = self
+            .parked_panes
+            .iter()
+            .filter_map(|(id, pane)| Some((*id, pane.cursor_offsets(document)?)))
+            .collect::<Vec<_>>();
+        let rev = document.buffer.rev();
+        let result = edit(&mut *document);
+
+        // Each step in offsets of the text before it. When there were too many
+        // to remember, the cursors are only kept within the text.
+        let steps = document
+            .changes_since(rev)
+            .unwrap_or_default()
+            .iter()
+            .map(|changes| {
+                changes
+                    .iter()
+                    .map(|change| {
+                        let range = change.start..change.start + change.deleted.len();
+                        (range, change.inserted.clone())
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        let position_of = |offset| {
+            let offset = steps
+                .iter()
+                .fold(offset, |offset, edits| shift_offset(offset, edits, false));
+            document.offset_to_position(offset.min(document.buffer.len()))
+        };
+        let moved = parked
+            .into_iter()
+            .map(|(id, offsets)| {
+                let positions = offsets
+                    .into_iter()
+                    .map(|(head, anchor)| (position_of(head), anchor.map(position_of)))
+                    .collect::<Vec<_>>();
+                (id, positions)
+            })
+            .collect::<Vec<_>>();
+        drop(documents);
+
+        for (id, positions) in moved {
+            if let Some(pane) = self.parked_panes.get_mut(&id) {
+                pane.move_cursors(path, positions);
+            }
+        }
+        Some(result)
+    }
+
+    fn active_pane_state(&self) -> Pane {
+        Pane {
+            focused_document_path: self.focused_document_path.clone(),
+            document_scrollings: self.document_scrollings.clone(),
+            document_cursors: self.document_cursors.clone(),
+        }
+    }
+
+    /// Makes `pane` the active pane state, returning the previous one.
+    fn swap_active_pane(&mut self, pane: Pane) -> Pane {
+        Pane {
+            focused_document_path: std::mem::replace(
+                &mut self.focused_document_path,
+                pane.focused_document_path,
+            ),
+            document_scrollings: std::mem::replace(
+                &mut self.document_scrollings,
+                pane.document_scrollings,
+            ),
+            document_cursors: std::mem::replace(&mut self.document_cursors, pane.document_cursors),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use xilem::view::Axis;
+
+    use super::super::test_support::test_state;
+
+    #[test]
+    fn edits_move_the_cursors_of_other_panes_on_the_document() {
+        let mut state = test_state(&[("a.txt", "ab\ncd\n")]);
+        let path = state.open_loaded("a.txt");
+        state.set_cursors(path.clone(), &[(1, 1)]);
+        let first = state.active_pane;
+        state.split_pane(Axis::Horizontal);
+        let second = state.active_pane;
+
+        state.set_cursors(path.clone(), &[(1, 0)]);
+        state.insert_character('x');
+        assert_eq!(state.text(&path), "ab\nxcd\n");
+        let first_cursors = state.pane_view(first).cursors;
+        assert_eq!(
+            first_cursors
+                .iter()
+                .map(|cursor| (cursor.line, cursor.column))
+                .collect::<Vec<_>>(),
+            vec![(1, 2)]
+        );
+
+        state.focus_pane(first);
+        assert_eq!(state.cursor_offsets(&path), vec![5]);
+        assert_eq!(state.pane_view(second).cursors.len(), 1);
+        assert_eq!(state.pane_view(second).cursors[0].column, 1);
+    }
+
+    #[test]
+    fn undo_and_redo_move_the_cursors_of_other_panes_on_the_document() {
+        let mut state = test_state(&[("a.txt", "ab\ncd\n")]);
+        let path = state.open_loaded("a.txt");
+        let first = state.active_pane;
+        state.split_pane(Axis::Horizontal);
+        let second = state.active_pane;
+
+        state.set_cursors(path.clone(), &[(0, 0)]);
+        state.insert_character('x');
+        assert_eq!(state.text(&path), "xab\ncd\n");
+        let move_first_cursor = |state: &mut super::super::EditorState, position| {
+            state.focus_pane(first);
+            state.set_cursors(path.clone(), &[position]);
+            state.focus_pane(second);
+        };
+        let first_cursor = |state: &super::super::EditorState| {
+            let cursors = state.pane_view(first).cursors;
+            (cursors[0].line, cursors[0].column)
+        };
+
+        move_first_cursor(&mut state, (1, 1));
+        state.undo();
+        assert_eq!(state.text(&path), "ab\ncd\n");
+        assert_eq!(first_cursor(&state), (1, 1));
+        state.redo();
+        assert_eq!(state.text(&path), "xab\ncd\n");
+        assert_eq!(first_cursor(&state), (1, 1));
+
+        // On the same line, the cursor moves along the undone text.
+        move_first_cursor(&mut state, (0, 3));
+        state.undo();
+        assert_eq!(first_cursor(&state), (0, 2));
+    }
+
+    #[test]
+    fn pane_views_show_their_own_document() {
+        let mut state = test_state(&[("a.txt", "a\n"), ("b.txt", "b\n")]);
+        let a = state.open_loaded("a.txt");
+        let first = state.active_pane;
+        state.split_pane(Axis::Vertical);
+        let b = state.open_loaded("b.txt");
+
+        let document_path = |state: &super::super::EditorState, id| {
+            state.pane_view(id).document.map(|document| document.path)
+        };
+        assert_eq!(document_path(&state, first), Some(a));
+        assert_eq!(document_path(&state, state.active_pane), Some(b));
+    }
+}
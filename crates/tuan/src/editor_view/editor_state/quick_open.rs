@@ -0,0 +1,218 @@
+use std::{
+    cmp::Reverse,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+use crate::{cli::split_position, document::RangeStyle, fuzzy, markdown::RichText, theme::Style};
+
+/// How many files are listed at most.
+const MAX_ITEMS: usize = 100;
+/// How many recently opened files are remembered, to rank them higher.
+const RECENT_FILES_LIMIT: usize = 30;
+/// The bonus of the most recently opened file, decreasing with older ones.
+const RECENT_BONUS: i64 = 64;
+
+/// The quick open, finding a workspace file by fuzzy-matching its path.
+///
+/// The input may end with `:line` or `:line:column` to jump there, and
+/// `:line` alone jumps to a line of the focused document.
+#[derive(Debug, Clone, Default)]
+pub struct QuickOpen {
+    pub input: String,
+    /// The items of the last input, until it or the index changes.
+    items: Arc<Mutex<Option<Items>>>,
+}
+
+#[derive(Debug)]
+struct Items {
+    input: String,
+    index_rev: u64,
+    items: Arc<Vec<QuickOpenItem>>,
+}
+
+/// A file matching the input of the quick open.
+#[derive(Debug, Clone)]
+pub struct QuickOpenItem {
+    pub path: PathBuf,
+    /// The path relative to the workspace, with the matched chars underlined.
+    pub label: Arc<RichText>,
+}
+
+impl QuickOpen {
+    /// The part of the input matched against paths, and the 1-based line
+    /// and column to jump to.
+    fn query(&self) -> (&str, Option<usize>, Option<usize>) {
+        let input = self.input.trim();
+        match input.strip_prefix(':') {
+            Some(line) => {
+                let (line, column) = line.split_once(':').unwrap_or((line, ""));
+                ("", line.parse().ok(), column.parse().ok())
+            }
+            None => split_position(input),
+        }
+    }
+}
+
+/// `relative` with the chars at `indices` in bold and underlined.
+fn highlighted(relative: &str, indices: &[usize]) -> RichText {
+    let mut text = RichText::plain(relative);
+    text.styles = relative
+        .char_indices()
+        .enumerate()
+        .filter(|(index, _)| indices.contains(index))
+        .map(|(_, (start, c))| RangeStyle {
+            start,
+            end: start + c.len_utf8(),
+            style: Style::match_highlight(),
+        })
+        .collect();
+    text
+}
+
+impl super::EditorState {
+    pub fn toggle_quick_open(&mut self) {
+        self.quick_open = match self.quick_open.take() {
+            Some(_) => None,
+            None => Some(QuickOpen::default()),
+        };
+    }
+
+    pub fn close_quick_open(&mut self) {
+        self.quick_open = None;
+    }
+
+    pub fn set_quick_open_input(&mut self, input: String) {
+        if let Some(quick_open) = self.quick_open.as_mut() {
+            quick_open.input = input;
+        }
+    }
+
+    /// The files matching the input, best first; the recently opened ones
+    /// first when nothing is typed.
+    pub fn quick_open_items(&self) -> Arc<Vec<QuickOpenItem>> {
+        let Some(quick_open) = &self.quick_open else {
+            return Arc::default();
+        };
+        let index_rev = self.file_index.rev();
+        let mut cached = quick_open.items.lock().unwrap();
+        if let Some(cached) = cached
+            .as_ref()
+            .filter(|cached| cached.input == quick_open.input && cached.index_rev == index_rev)
+        {
+            return cached.items.clone();
+        }
+
+        let (query, _, _) = quick_open.query();
+        let recent_bonus = |path: &Path| {
+            self.recent_files
+                .iter()
+                .position(|recent| recent == path)
+                .map_or(0, |rank| RECENT_BONUS - 2 * rank as i64)
+        };
+        let files = self.file_index.files();
+        let mut matches = files
+            .iter()
+            .filter_map(|path| {
+                let relative = path.strip_prefix(&self.workspace_path).unwrap_or(path);
+                let relative = relative.to_string_lossy();
+                let (score, indices) = if query.is_empty() {
+                    (0, Vec::new())
+                } else {
+                    let found = fuzzy::fuzzy_match(query, &relative)?;
+                    (found.score, found.indices)
+                };
+                Some((score + recent_bonus(path), path, relative, indices))
+            })
+            .collect::<Vec<_>>();
+        // Stable, so that equally good matches stay sorted by path.
+        matches.sort_by_key(|(score, ..)| Reverse(*score));
+
+        let items = Arc::new(
+            matches
+                .into_iter()
+                .take(MAX_ITEMS)
+                .map(|(_, path, relative, indices)| QuickOpenItem {
+                    path: path.clone(),
+                    label: Arc::new(highlighted(&relative, &indices)),
+                })
+                .collect::<Vec<_>>(),
+        );
+        *cached = Some(Items {
+            input: quick_open.input.clone(),
+            index_rev,
+            items: items.clone(),
+        });
+        items
+    }
+
+    /// Whether the workspace files are still being listed.
+    pub fn quick_open_indexing(&self) -> bool {
+        self.file_index.is_indexing()
+    }
+
+    /// Opens the best match, or jumps to the typed line of the focused
+    /// document when the input is only `:line`.
+    pub fn open_first_quick_open_item(&mut self) {
+        let Some((query, line, column)) = self.quick_open.as_ref().map(|quick_open| {
+            let (query, line, column) = quick_open.query();
+            (query.to_string(), line, column)
+        }) else {
+            return;
+        };
+        if query.is_empty()
+            && let Some(line) = line
+        {
+            let Some(path) = self.focused_document_path.clone() else {
+                return;
+            };
+            self.quick_open = None;
+            let column = column.unwrap_or(1).saturating_sub(1);
+            self.navigate_to(path, (line.saturating_sub(1), column));
+            return;
+        }
+        if let Some(item) = self.quick_open_items().first() {
+            self.open_quick_open_item(item.path.clone());
+        }
+    }
+
+    /// Opens `path`, at the line typed after the query if any.
+    pub fn open_quick_open_item(&mut self, path: PathBuf) {
+        let Some(quick_open) = self.quick_open.take() else {
+            return;
+        };
+        match quick_open.query() {
+            (_, Some(line), column) => {
+                let column = column.unwrap_or(1).saturating_sub(1);
+                self.navigate_to(path, (line.saturating_sub(1), column));
+            }
+            _ if self.open_documents.contains(&path) => self.focus_tab(path),
+            _ => self.open_file_at(path, (0, 0)),
+        }
+    }
+
+    /// Remembers `path` as the most recently opened file.
+    pub(super) fn note_recent_file(&mut self, path: &Path) {
+        self.recent_files.retain(|recent| recent != path);
+        self.recent_files.insert(0, path.to_path_buf());
+        self.recent_files.truncate(RECENT_FILES_LIMIT);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::test_support::test_state;
+
+    #[test]
+    fn a_line_past_the_end_goes_to_the_last_line() {
+        let mut state = test_state(&[("a.txt", "ab\ncd")]);
+        let path = state.open_loaded("a.txt");
+
+        state.toggle_quick_open();
+        state.set_quick_open_input(":99:99".to_string());
+        state.open_first_quick_open_item();
+
+        assert!(state.quick_open.is_none());
+        assert_eq!(state.cursor_offsets(&path), vec![5]);
+    }
+}
@@ -0,0 +1,288 @@
+use std::path::PathBuf;
+
+use lsp_types::{CodeAction, CodeActionOrCommand, PrepareRenameResponse};
+use tuan_rpc::{plugin::PluginId, proxy::ProxyResponse};
+
+use super::{lsp::LspResponse, workspace_edit::PendingEdit};
+
+/// The new name of the symbol being renamed, as typed in the rename bar.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RenameInput {
+    path: PathBuf,
+    position: lsp_types::Position,
+    pub name: String,
+}
+
+/// The code actions offered at the cursor, to pick one from.
+#[derive(Debug, Clone)]
+pub struct CodeActionList {
+    plugin_id: PluginId,
+    pub actions: Vec<CodeActionOrCommand>,
+}
+
+impl super::EditorState {
+    /// Opens the rename bar for the symbol under the first cursor, filled
+    /// with its current name.
+    pub fn start_symbol_rename(&mut self) {
+        let Some((path, position)) = self.lsp_request_position() else {
+            return;
+        };
+        let lsp_response_tx = self.lsp_response_tx.clone();
+        let documents = self.documents.clone();
+        let prepare_path = path.clone();
+        self.proxy
+            .proxy_rpc
+            .prepare_rename(path, position, move |result| {
+                let placeholder = match result {
+                    Ok(ProxyResponse::PrepareRename { resp }) => match resp {
+                        PrepareRenameResponse::Range(range) => documents
+                            .lock()
+                            .unwrap()
+                            .get(&prepare_path)
+                            .map(|document| {
+                                document.text_in(
+                                    document.offset_of_position(&range.start)
+                                        ..document.offset_of_position(&range.end),
+                                )
+                            }),
+                        PrepareRenameResponse::RangeWithPlaceholder { placeholder, .. } => {
+                            Some(placeholder)
+                        }
+                        PrepareRenameResponse::DefaultBehavior { .. } => None,
+                    },
+                    Ok(response) => {
+                        tracing::debug!("Unexpected response to prepare rename: {response:?}");
+                        return;
+                    }
+                    // Not all servers support preparing a rename: try the word under the cursor.
+                    Err(err) => {
+                        tracing::debug!("Failed to prepare rename: {}", err.message);
+                        None
+                    }
+                };
+                if let Err(err) = lsp_response_tx.send(LspResponse::PrepareRename {
+                    path: prepare_path,
+                    position,
+                    placeholder,
+                }) {
+                    tracing::error!("{:?}", err);
+                }
+            });
+    }
+
+    /// Opens the rename bar with `placeholder`, or the word at `position` without one.
+    pub(super) fn show_rename_input(
+        &mut self,
+        path: PathBuf,
+        position: lsp_types::Position,
+        placeholder: Option<String>,
+    ) {
+        if self.focused_document_path.as_ref() != Some(&path) {
+            return;
+        }
+        let offset = self
+            .documents
+            .lock()
+            .unwrap()
+            .get(&path)
+            .map(|document| document.offset_of_position(&position));
+        let name = placeholder.or_else(|| {
+            let range = self.word_range(offset?)?;
+            let documents = self.documents.lock().unwrap();
+            Some(documents.get(&path)?.text_in(range))
+        });
+        self.rename = Some(RenameInput {
+            path,
+            position,
+            name: name.unwrap_or_default(),
+        });
+    }
+
+    pub fn set_rename_input(&mut self, name: String) {
+        if let Some(rename) = self.rename.as_mut() {
+            rename.name = name;
+        }
+    }
+
+    pub fn cancel_rename(&mut self) {
+        self.rename = None;
+    }
+
+    /// Asks the language server for the edits of the rename, to be previewed.
+    pub fn submit_rename(&mut self) {
+        let Some(rename) = self.rename.take() else {
+            return;
+        };
+        let name = rename.name.trim().to_string();
+        if name.is_empty() {
+            return;
+        }
+
+        let lsp_response_tx = self.lsp_response_tx.clone();
+        let documents = self.documents.clone();
+        let title = format!("Rename to `{name}`");
+        self.proxy
+            .proxy_rpc
+            .rename(rename.path, rename.position, name, move |result| {
+                let edit = match result {
+                    Ok(ProxyResponse::Rename { edit }) => edit,
+                    Ok(response) => {
+                        tracing::debug!("Unexpected response to rename: {response:?}");
+                        return;
+                    }
+                    Err(err) => {
+                        tracing::error!("Failed to rename: {}", err.message);
+                        return;
+                    }
+                };
+                let edit = PendingEdit::new(title, edit, &documents);
+                if let Err(err) = lsp_response_tx.send(LspResponse::WorkspaceEdit(edit)) {
+                    tracing::error!("{:?}", err);
+                }
+            });
+    }
+
+    /// Asks for the quick fixes and refactorings at the start of the
+    /// selection of the first cursor, or at the cursor.
+    pub fn show_code_actions(&mut self) {
+        let Some(path) = self.focused_document_path.clone() else {
+            return;
+        };
+        let Some(cursor) = self.focused_cursors().first() else {
+            return;
+        };
+        let offset = cursor
+            .get_selection_range()
+            .map_or_else(|| cursor.get_cursor_offset(), |range| range.start);
+        let Some(position) = self
+            .documents
+            .lock()
+            .unwrap()
+            .get(&path)
+            .map(|document| document.lsp_position(offset))
+        else {
+            return;
+        };
+        // The diagnostics at the position, for the server to offer their fixes.
+        let diagnostics = self
+            .diagnostics
+            .get(&path)
+            .map(|diagnostics| {
+                diagnostics
+                    .iter()
+                    .filter(|diagnostic| {
+                        diagnostic.range.start <= position && position <= diagnostic.range.end
+                    })
+                    .cloned()
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let lsp_response_tx = self.lsp_response_tx.clone();
+        self.proxy
+            .proxy_rpc
+            .get_code_actions(path, position, diagnostics, move |result| {
+                let (plugin_id, actions) = match result {
+                    Ok(ProxyResponse::GetCodeActionsResponse { plugin_id, resp }) => {
+                        (plugin_id, resp)
+                    }
+                    Ok(response) => {
+                        tracing::debug!("Unexpected response to code actions: {response:?}");
+                        return;
+                    }
+                    Err(err) => {
+                        tracing::error!("Failed to get code actions: {}", err.message);
+                        return;
+                    }
+                };
+                if let Err(err) =
+                    lsp_response_tx.send(LspResponse::CodeActions { plugin_id, actions })
+                {
+                    tracing::error!("{:?}", err);
+                }
+            });
+    }
+
+    pub(super) fn set_code_actions(
+        &mut self,
+        plugin_id: PluginId,
+        actions: Vec<CodeActionOrCommand>,
+    ) {
+        if actions.is_empty() {
+            tracing::info!("No code actions available");
+            self.code_actions = None;
+            return;
+        }
+        self.code_actions = Some(CodeActionList { plugin_id, actions });
+    }
+
+    pub fn close_code_actions(&mut self) {
+        self.code_actions = None;
+    }
+
+    /// Previews the edit of the code action at `index` of the list, resolving
+    /// it first when the server left it out.
+    pub fn pick_code_action(&mut self, index: usize) {
+        let Some(list) = self.code_actions.take() else {
+            return;
+        };
+        let Some(action) = list.actions.into_iter().nth(index) else {
+            return;
+        };
+        let action = match action {
+            CodeActionOrCommand::CodeAction(action) => action,
+            CodeActionOrCommand::Command(command) => {
+                self.show_message(unsupported_command(&command.title));
+                return;
+            }
+        };
+
+        match action.edit {
+            Some(edit) => {
+                let edit = PendingEdit::new(action.title, edit, &self.documents);
+                self.set_pending_edit(edit);
+            }
+            None if action.command.is_some() => {
+                self.show_message(unsupported_command(&action.title));
+            }
+            None => self.resolve_code_action(list.plugin_id, action),
+        }
+    }
+
+    fn resolve_code_action(&mut self, plugin_id: PluginId, action: CodeAction) {
+        let lsp_response_tx = self.lsp_response_tx.clone();
+        let documents = self.documents.clone();
+        self.proxy
+            .proxy_rpc
+            .code_action_resolve(action, plugin_id, move |result| {
+                let action = match result {
+                    Ok(ProxyResponse::CodeActionResolveResponse { item }) => *item,
+                    Ok(response) => {
+                        tracing::debug!("Unexpected response to code action resolve: {response:?}");
+                        return;
+                    }
+                    Err(err) => {
+                        tracing::error!("Failed to resolve the code action: {}", err.message);
+                        return;
+                    }
+                };
+                let response = match action.edit {
+                    Some(edit) => {
+                        LspResponse::WorkspaceEdit(PendingEdit::new(action.title, edit, &documents))
+                    }
+                    None if action.command.is_some() => {
+                        LspResponse::Message(unsupported_command(&action.title))
+                    }
+                    None => LspResponse::Message(format!("\"{}\" changes nothing", action.title)),
+                };
+                if let Err(err) = lsp_response_tx.send(response) {
+                    tracing::error!("{:?}", err);
+                }
+            });
+    }
+}
+
+/// Why the code action titled `title` does nothing: running commands is not supported.
+fn unsupported_command(title: &str) -> String {
+    format!("\"{title}\" runs a command, which is not supported")
+}
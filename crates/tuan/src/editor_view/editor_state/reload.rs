@@ -0,0 +1,107 @@
+use std::{collections::HashSet, path::PathBuf, sync::Arc};
+
+use crate::{
+    config_watcher::ConfigWatcher, editor_view::EditorConfig, keybindings::Keybindings,
+    settings::Settings,
+};
+
+impl super::EditorState {
+    /// The files whose changes trigger a configuration reload.
+    pub(super) fn config_files(&self) -> Vec<PathBuf> {
+        let mut files = vec![
+            Settings::user_settings_path(),
+            Settings::workspace_settings_path(&self.workspace_path),
+            Keybindings::config_path(),
+        ];
+        files.extend(self.settings.theme.clone());
+        files
+    }
+
+    /// Reloads the configuration affected by `changed_files`.
+    ///
+    /// When a file fails to load, the previous configuration is kept and the
+    /// error is stored in `config_error`.
+    pub fn reload_config(&mut self, changed_files: &HashSet<PathBuf>) {
+        let keybindings_path = ConfigWatcher::normalize(&Keybindings::config_path());
+        let keybindings_changed = keybindings_path
+            .as_ref()
+            .is_some_and(|p| changed_files.contains(p));
+        let settings_changed = changed_files
+            .iter()
+            .any(|path| Some(path) != keybindings_path.as_ref());
+
+        let mut errors = Vec::new();
+
+        if keybindings_changed {
+            match Keybindings::new() {
+                Ok(keybindings) => {
+                    tracing::info!("Reloaded keybindings");
+                    self.keybindings = keybindings;
+                }
+                Err(err) => errors.push(format!(
+                    "Failed to reload {}: {err}",
+                    Keybindings::config_path().display()
+                )),
+            }
+        }
+
+        if settings_changed {
+            if let Err(err) = self.reload_settings() {
+                errors.push(err);
+            }
+        }
+
+        self.config_error = (!errors.is_empty()).then(|| errors.join("\n"));
+        for error in &errors {
+            tracing::error!("{error}");
+        }
+    }
+
+    fn reload_settings(&mut self) -> Result<(), String> {
+        let (settings, errors) = Settings::load(&self.workspace_path);
+        if !errors.is_empty() {
+            return Err(errors
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join("\n"));
+        }
+
+        let config = EditorConfig::from_settings(&settings).map_err(|err| err.to_string())?;
+
+        tracing::info!("Reloaded settings");
+        self.settings = settings;
+        self.set_config(Arc::new(config));
+        // The theme path may have changed.
+        self.config_watcher.watch(self.config_files());
+        Ok(())
+    }
+
+    /// Swaps the config of the editor, its documents and cursors, and restyles the documents.
+    pub fn set_config(&mut self, config: Arc<EditorConfig>) {
+        self.config = config.clone();
+
+        let paths = {
+            let mut documents = self.documents.lock().unwrap();
+            for document in documents.values_mut() {
+                document.set_config(config.clone());
+            }
+            documents.keys().cloned().collect::<Vec<_>>()
+        };
+
+        for cursors in self.document_cursors.values_mut() {
+            for cursor in cursors {
+                cursor.set_editor_config(config.clone());
+            }
+        }
+        for pane in self.parked_panes.values_mut() {
+            for cursor in pane.cursors_mut() {
+                cursor.set_editor_config(config.clone());
+            }
+        }
+
+        for path in paths {
+            self.update_styles_with_syntax(path);
+        }
+    }
+}
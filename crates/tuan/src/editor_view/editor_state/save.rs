@@ -0,0 +1,279 @@
+use std::path::PathBuf;
+
+use super::{LspResponse, panes::rename_document};
+use crate::document::DiskChange;
+
+impl super::EditorState {
+    pub fn save(&mut self) {
+        if let Some(path) = self.focused_document_path.clone() {
+            self.save_document(path, false);
+        }
+    }
+
+    pub fn save_all(&mut self) {
+        let paths = self
+            .documents
+            .lock()
+            .unwrap()
+            .values()
+            .filter(|document| document.is_dirty())
+            .map(|document| document.path.clone())
+            .collect::<Vec<_>>();
+        for path in paths {
+            self.save_document(path, false);
+        }
+    }
+
+    /// Writes the document at `path` through the proxy.
+    ///
+    /// When the file changed on disk since it was opened, nothing is written
+    /// unless `overwrite` is set: the user is offered to overwrite or reload instead.
+    pub fn save_document(&mut self, path: PathBuf, overwrite: bool) {
+        let rev = {
+            let documents = self.documents.lock().unwrap();
+            let Some(document) = documents.get(&path) else {
+                return;
+            };
+            if document.disk_change.is_some() && !overwrite {
+                tracing::warn!("Not saving {:?}: the file changed on disk", path);
+                return;
+            }
+            document.buffer.rev()
+        };
+
+        self.proxy.proxy_rpc.save(rev, path.clone(), true, {
+            let documents = self.documents.clone();
+            move |result| match result {
+                Ok(_) => {
+                    if let Some(document) = documents.lock().unwrap().get_mut(&path) {
+                        document.mark_saved(path.clone(), rev);
+                    }
+                    tracing::debug!("Saved {:?} at revision {rev}", path);
+                }
+                Err(err) => tracing::error!("Failed to save {:?}: {}", path, err.message),
+            }
+        });
+    }
+
+    /// Opens the save-as bar, with the path of the focused document to edit.
+    pub fn start_save_as(&mut self) {
+        let Some(path) = self.focused_document_path.as_ref() else {
+            return;
+        };
+        let relative = path.strip_prefix(&self.workspace_path).unwrap_or(path);
+        self.save_as = Some(relative.to_string_lossy().into_owned());
+    }
+
+    pub fn set_save_as_input(&mut self, input: String) {
+        if let Some(save_as) = self.save_as.as_mut() {
+            *save_as = input;
+        }
+    }
+
+    pub fn cancel_save_as(&mut self) {
+        self.save_as = None;
+    }
+
+    /// Saves the focused document at the path typed in the save-as bar,
+    /// relative to the workspace unless absolute.
+    pub fn submit_save_as(&mut self) {
+        let Some(input) = self.save_as.take() else {
+            return;
+        };
+        let input = input.trim();
+        if input.is_empty() {
+            return;
+        }
+        self.save_as(self.workspace_path.join(input));
+    }
+
+    /// Writes the focused document to `new_path`, which it is then edited as
+    /// once the proxy saved it.
+    ///
+    /// Refuses to save over a document open in another tab, whose unsaved
+    /// edits would be lost.
+    pub fn save_as(&mut self, new_path: PathBuf) {
+        let Some(path) = self.focused_document_path.clone() else {
+            return;
+        };
+        if new_path == path {
+            self.save_document(path, false);
+            return;
+        }
+        if self.open_documents.contains(&new_path) {
+            self.show_message(format!(
+                "{} is already open, close it before saving over it",
+                new_path.display()
+            ));
+            return;
+        }
+        let Some(document) = self.get_focused_document() else {
+            return;
+        };
+        let rev = document.buffer.rev();
+        let content = document.buffer.text().to_string();
+
+        self.proxy.proxy_rpc.save_buffer_as(
+            document.buffer_id,
+            new_path.clone(),
+            rev,
+            content,
+            true,
+            {
+                let lsp_response_tx = self.lsp_response_tx.clone();
+                move |result| {
+                    let response = match result {
+                        Ok(_) => LspResponse::SavedAs {
+                            path,
+                            new_path,
+                            rev,
+                        },
+                        Err(err) => {
+                            let message = format!(
+                                "Failed to save {} as {}: {}",
+                                path.display(),
+                                new_path.display(),
+                                err.message
+                            );
+                            tracing::error!("{message}");
+                            LspResponse::Message(message)
+                        }
+                    };
+                    if let Err(err) = lsp_response_tx.send(response) {
+                        tracing::error!("Failed to send the save as result: {err}");
+                    }
+                }
+            },
+        );
+    }
+
+    /// Edits the document at `path`, saved at revision `rev`, as `new_path`:
+    /// its tab, cursors and scrolling follow it, in every pane.
+    pub(super) fn saved_as(&mut self, path: PathBuf, new_path: PathBuf, rev: u64) {
+        if self.open_documents.contains(&new_path) {
+            self.show_message(format!(
+                "Saved as {}, which was opened meanwhile",
+                new_path.display()
+            ));
+            return;
+        }
+        {
+            let mut documents = self.documents.lock().unwrap();
+            let Some(mut document) = documents.remove(&path) else {
+                return;
+            };
+            document.mark_saved(new_path.clone(), rev);
+            documents.insert(new_path.clone(), document);
+        }
+
+        rename_document(
+            &path,
+            &new_path,
+            &mut self.focused_document_path,
+            &mut self.document_scrollings,
+            &mut self.document_cursors,
+        );
+        for pane in self.parked_panes.values_mut() {
+            pane.rename(&path, &new_path);
+        }
+        if let Some(tab) = self.open_documents.iter_mut().find(|p| **p == path) {
+            *tab = new_path;
+        }
+    }
+
+    /// Resolves a disk conflict by writing the buffer over the file on disk.
+    pub fn overwrite_disk_change(&mut self, path: PathBuf) {
+        self.save_document(path, true);
+    }
+
+    /// Resolves a disk conflict by replacing the buffer with the file on disk.
+    pub fn reload_disk_change(&mut self, path: PathBuf) {
+        let cursors = self
+            .document_cursors
+            .get(&path)
+            .map(|cursors| cursors.iter().map(|c| (c.line, c.column)).collect())
+            .unwrap_or_default();
+
+        let Some(Some(cursors)) =
+            self.edit_with_parked_cursors(&path, |document| document.reload_from_disk(cursors))
+        else {
+            return;
+        };
+        self.set_cursors(path.clone(), &cursors);
+        self.update_styles_with_syntax(path);
+    }
+
+    /// The focused document's path and disk change, if it changed on disk.
+    pub fn focused_disk_change(&self) -> Option<(PathBuf, DiskChange)> {
+        let document = self.get_focused_document()?;
+        Some((document.path, document.disk_change?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use xilem::view::Axis;
+
+    use super::super::{LspResponse, test_support::test_state};
+
+    #[test]
+    fn a_failed_save_as_keeps_the_document_where_it_was() {
+        let mut state = test_state(&[("a.txt", "a")]);
+        let path = state.open_loaded("a.txt");
+
+        // The fake proxy fails to save.
+        state.save_as(state.workspace_path.join("b.txt"));
+        assert_eq!(state.focused_document_path.as_ref(), Some(&path));
+        let response = loop {
+            let response = state
+                .lsp_response_rx()
+                .recv_timeout(Duration::from_secs(5))
+                .unwrap();
+            if !matches!(response, LspResponse::DocumentLoaded(_)) {
+                break response;
+            }
+        };
+        assert!(matches!(response, LspResponse::Message(_)));
+        state.handle_lsp_response(response);
+
+        assert_eq!(state.open_documents, vec![path.clone()]);
+        assert_eq!(state.focused_document_path.as_ref(), Some(&path));
+        assert!(state.get_focused_document().is_some());
+    }
+
+    #[test]
+    fn saved_as_moves_the_tab_selections_and_other_panes() {
+        let mut state = test_state(&[("a.txt", "ab")]);
+        let path = state.open_loaded("a.txt");
+        state.document_cursors.get_mut(&path).unwrap()[0].select((0, 0), (0, 2));
+        let first = state.active_pane;
+        state.split_pane(Axis::Horizontal);
+
+        let new_path = state.workspace_path.join("b.txt");
+        state.saved_as(path.clone(), new_path.clone(), 0);
+
+        assert_eq!(state.open_documents, vec![new_path.clone()]);
+        assert_eq!(state.focused_document_path.as_ref(), Some(&new_path));
+        assert_eq!(state.text(&new_path), "ab");
+        assert_eq!(state.cursor_offsets(&new_path), vec![2]);
+        assert_eq!(state.document_cursors[&new_path][0].anchor, Some((0, 0)));
+        let first_view = state.pane_view(first);
+        assert_eq!(first_view.document.map(|d| d.path), Some(new_path));
+        assert_eq!(first_view.cursors[0].anchor, Some((0, 0)));
+    }
+
+    #[test]
+    fn save_as_refuses_a_path_open_in_another_tab() {
+        let mut state = test_state(&[("a.txt", "a"), ("b.txt", "b")]);
+        let a = state.open_loaded("a.txt");
+        let b = state.open_loaded("b.txt");
+        state.focus_tab(a.clone());
+
+        state.save_as(b.clone());
+        assert!(state.status_message.is_some());
+        assert_eq!(state.open_documents, vec![a.clone(), b.clone()]);
+        assert_eq!(state.text(&b), "b");
+    }
+}
@@ -1,5 +1,8 @@
 use std::path::PathBuf;
 
+/// How many lines are kept visible around a line scrolled into view.
+const REVEAL_MARGIN: usize = 3;
+
 impl super::EditorState {
     pub fn scroll_document(&mut self, path: &PathBuf, delta: (f64, f64)) {
         let (x, y) = self
@@ -13,4 +16,39 @@ impl super::EditorState {
     pub fn get_document_scroll(&self, path: &PathBuf) -> Option<(f64, f64)> {
         self.document_scrollings.get(path).cloned()
     }
+
+    /// Records the height of the active pane, as last painted.
+    pub fn set_viewport_height(&self, height: f64) {
+        self.viewport_heights
+            .lock()
+            .unwrap()
+            .insert(self.active_pane, height);
+    }
+
+    /// Scrolls the active pane vertically so that `line` of `path` is shown
+    /// with a few lines around it, unless it already is.
+    pub(super) fn reveal_line(&mut self, path: &PathBuf, line: usize) {
+        let line_height = self.config.real_line_height() as f64;
+        let margin = REVEAL_MARGIN as f64 * line_height;
+        // Before the pane is painted, the line goes to its top.
+        let height = self
+            .viewport_heights
+            .lock()
+            .unwrap()
+            .get(&self.active_pane)
+            .copied()
+            .unwrap_or(0.0)
+            .max(line_height + 2.0 * margin);
+
+        let (_, y) = self
+            .document_scrollings
+            .entry(path.clone())
+            .or_insert((0.0, 0.0));
+        let line_top = line as f64 * line_height;
+        if line_top - margin < -*y {
+            *y = -(line_top - margin).max(0.0);
+        } else if line_top + line_height + margin > -*y + height {
+            *y = -(line_top + line_height + margin - height).max(0.0);
+        }
+    }
 }
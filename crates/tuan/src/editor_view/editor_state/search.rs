@@ -0,0 +1,378 @@
+use std::{
+    collections::HashMap,
+    ops::Range,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use ignore::overrides::{Override, OverrideBuilder};
+use lapce_xi_rope::Rope;
+use lsp_types::{TextEdit, Url, WorkspaceEdit};
+use tuan_core::buffer::rope_text::{RopeText, RopeTextRef};
+use tuan_rpc::proxy::ProxyResponse;
+
+use super::{
+    find::{build_pattern, expand_replacement},
+    lsp::{LspResponse, next_request_id},
+    workspace_edit::PendingEdit,
+};
+use crate::{
+    document::{RangeStyle, highlight},
+    editor_view::EditorConfig,
+    markdown::RichText,
+    theme::Style,
+};
+
+/// How many matches are shown at most, the rest being counted only.
+const MAX_SHOWN_MATCHES: usize = 2000;
+
+/// The project search panel, searching the files of the workspace through the proxy.
+#[derive(Debug, Clone, Default)]
+pub struct ProjectSearch {
+    pub query: String,
+    pub replacement: String,
+    /// Whether the query is a regex rather than literal text.
+    pub regex: bool,
+    pub case_sensitive: bool,
+    /// Comma-separated globs of the files to search, all of them when empty.
+    pub include: String,
+    /// Comma-separated globs of the files not to search.
+    pub exclude: String,
+    /// Set while the proxy searches.
+    pub searching: bool,
+    pub error: Option<String>,
+    request_id: usize,
+    /// The files with matches, in the order they were found.
+    pub files: Vec<SearchFile>,
+}
+
+/// The matches found in a file.
+#[derive(Debug, Clone)]
+pub struct SearchFile {
+    pub path: PathBuf,
+    /// Whether replacing in files changes this file.
+    pub included: bool,
+    pub matches: Vec<SearchMatch>,
+}
+
+/// A match of the project search, with its line highlighted in the language of its file.
+#[derive(Debug, Clone)]
+pub struct SearchMatch {
+    /// The 0-based line of the match.
+    pub line: usize,
+    /// The byte column of the match.
+    pub column: usize,
+    pub preview: Arc<RichText>,
+}
+
+impl ProjectSearch {
+    pub fn match_count(&self) -> usize {
+        self.files.iter().map(|file| file.matches.len()).sum()
+    }
+}
+
+/// Builds the filter of the include and exclude globs, relative to `root`.
+fn glob_filter(root: &Path, include: &str, exclude: &str) -> Result<Override, ignore::Error> {
+    let globs = |globs: &str| {
+        globs
+            .split(',')
+            .map(str::trim)
+            .filter(|glob| !glob.is_empty())
+            .map(str::to_string)
+            .collect::<Vec<_>>()
+    };
+    let mut builder = OverrideBuilder::new(root);
+    for glob in globs(include) {
+        builder.add(&glob)?;
+    }
+    for glob in globs(exclude) {
+        builder.add(&format!("!{glob}"))?;
+    }
+    builder.build()
+}
+
+/// The line of a match highlighted in the language of `path`, without its
+/// indentation, the match itself in bold and underlined.
+fn preview(
+    line: &str,
+    range: Range<usize>,
+    path: &Path,
+    config: &EditorConfig,
+) -> Option<RichText> {
+    let line = line.trim_end();
+    let indent = line.len() - line.trim_start().len();
+    if range.start < indent
+        || range.end > line.len()
+        || !line.is_char_boundary(range.start)
+        || !line.is_char_boundary(range.end)
+    {
+        return None;
+    }
+    let line = &line[indent..];
+
+    let mut text = RichText::default();
+    let styles = highlight::highlight_code(line, None, path, config);
+    text.push_styled(line, styles);
+    text.styles.push(RangeStyle {
+        start: range.start - indent,
+        end: range.end - indent,
+        style: Style::match_highlight(),
+    });
+    Some(text)
+}
+
+impl super::EditorState {
+    pub fn toggle_search(&mut self) {
+        self.search = match self.search.take() {
+            Some(_) => None,
+            None => Some(ProjectSearch::default()),
+        };
+    }
+
+    pub fn set_search_query(&mut self, query: String) {
+        if let Some(search) = self.search.as_mut() {
+            search.query = query;
+        }
+    }
+
+    pub fn set_search_replacement(&mut self, replacement: String) {
+        if let Some(search) = self.search.as_mut() {
+            search.replacement = replacement;
+        }
+    }
+
+    pub fn set_search_include(&mut self, include: String) {
+        if let Some(search) = self.search.as_mut() {
+            search.include = include;
+        }
+    }
+
+    pub fn set_search_exclude(&mut self, exclude: String) {
+        if let Some(search) = self.search.as_mut() {
+            search.exclude = exclude;
+        }
+    }
+
+    pub fn toggle_search_regex(&mut self) {
+        if let Some(search) = self.search.as_mut() {
+            search.regex = !search.regex;
+        }
+        self.run_search();
+    }
+
+    pub fn toggle_search_case_sensitive(&mut self) {
+        if let Some(search) = self.search.as_mut() {
+            search.case_sensitive = !search.case_sensitive;
+        }
+        self.run_search();
+    }
+
+    /// Searches the workspace for the query, replacing the previous results.
+    ///
+    /// The proxy searches every file of the workspace and answers once, with
+    /// all the matches: the include and exclude globs are applied to that
+    /// answer, so they save highlighting the excluded matches, not searching them.
+    pub fn run_search(&mut self) {
+        let Some(search) = self.search.as_mut() else {
+            return;
+        };
+        search.files.clear();
+        search.error = None;
+        search.searching = false;
+        if search.query.is_empty() {
+            return;
+        }
+        let filter = match glob_filter(&self.workspace_path, &search.include, &search.exclude) {
+            Ok(filter) => filter,
+            Err(err) => {
+                search.error = Some(err.to_string());
+                return;
+            }
+        };
+
+        let request_id = next_request_id();
+        search.request_id = request_id;
+        search.searching = true;
+
+        let lsp_response_tx = self.lsp_response_tx.clone();
+        let config = self.config.clone();
+        self.proxy.proxy_rpc.global_search(
+            search.query.clone(),
+            search.case_sensitive,
+            false,
+            search.regex,
+            move |result| {
+                let matches = match result {
+                    Ok(ProxyResponse::GlobalSearchResponse { matches }) => matches,
+                    Ok(response) => {
+                        tracing::debug!("Unexpected response to global search: {response:?}");
+                        return;
+                    }
+                    Err(err) => {
+                        let _ = lsp_response_tx.send(LspResponse::SearchFinished {
+                            request_id,
+                            error: Some(err.message),
+                        });
+                        return;
+                    }
+                };
+
+                // Not streamed: the search is over, only the highlighting of the
+                // previews is left. The files are still sent one by one, so that the
+                // first ones show while the others are highlighted, skipping the
+                // excluded ones before their matches are highlighted.
+                for (path, matches) in matches {
+                    if filter.matched(&path, false).is_ignore() {
+                        continue;
+                    }
+                    let matches = matches
+                        .into_iter()
+                        .filter_map(|found| {
+                            let preview = preview(
+                                &found.line_content,
+                                found.start..found.end,
+                                &path,
+                                &config,
+                            )?;
+                            Some(SearchMatch {
+                                line: found.line.saturating_sub(1),
+                                column: found.start,
+                                preview: Arc::new(preview),
+                            })
+                        })
+                        .collect::<Vec<_>>();
+                    if matches.is_empty() {
+                        continue;
+                    }
+                    if let Err(err) = lsp_response_tx.send(LspResponse::SearchResults {
+                        request_id,
+                        path,
+                        matches,
+                    }) {
+                        tracing::error!("{:?}", err);
+                        return;
+                    }
+                }
+                let _ = lsp_response_tx.send(LspResponse::SearchFinished {
+                    request_id,
+                    error: None,
+                });
+            },
+        );
+    }
+
+    /// Adds the matches found in a file, unless they are of a previous search.
+    pub(super) fn add_search_results(
+        &mut self,
+        request_id: usize,
+        path: PathBuf,
+        mut matches: Vec<SearchMatch>,
+    ) {
+        let Some(search) = self
+            .search
+            .as_mut()
+            .filter(|search| search.request_id == request_id)
+        else {
+            return;
+        };
+        let shown = search.match_count();
+        if shown >= MAX_SHOWN_MATCHES {
+            return;
+        }
+        matches.truncate(MAX_SHOWN_MATCHES - shown);
+        search.files.push(SearchFile {
+            path,
+            included: true,
+            matches,
+        });
+    }
+
+    pub(super) fn finish_search(&mut self, request_id: usize, error: Option<String>) {
+        if let Some(search) = self
+            .search
+            .as_mut()
+            .filter(|search| search.request_id == request_id)
+        {
+            search.searching = false;
+            search.error = error;
+        }
+    }
+
+    /// Includes or excludes `path` from replacing in files.
+    pub fn set_search_file_included(&mut self, path: PathBuf, included: bool) {
+        if let Some(file) = self
+            .search
+            .as_mut()
+            .and_then(|search| search.files.iter_mut().find(|file| file.path == path))
+        {
+            file.included = included;
+        }
+    }
+
+    pub fn open_search_match(&mut self, path: PathBuf, found: SearchMatch) {
+        self.navigate_to(path, (found.line, found.column));
+    }
+
+    /// Previews replacing the matches of the query in the included files,
+    /// found again in their current text.
+    pub fn replace_in_files(&mut self) {
+        let Some(search) = &self.search else {
+            return;
+        };
+        let pattern = match build_pattern(&search.query, search.regex, search.case_sensitive, false)
+        {
+            Ok(pattern) => pattern,
+            Err(err) => {
+                tracing::error!("Invalid search pattern: {err}");
+                return;
+            }
+        };
+
+        let mut changes = HashMap::new();
+        for file in search.files.iter().filter(|file| file.included) {
+            let text = match self.documents.lock().unwrap().get(&file.path) {
+                Some(document) => document.buffer.text().clone(),
+                None => match std::fs::read_to_string(&file.path) {
+                    Ok(text) => Rope::from(text),
+                    Err(err) => {
+                        tracing::warn!("Skipping {:?}: {err}", file.path);
+                        continue;
+                    }
+                },
+            };
+            let rope_text = RopeTextRef::new(&text);
+            let string = rope_text.slice_to_cow(0..text.len());
+            let edits = pattern
+                .captures_iter(&string)
+                .filter(|captures| !captures.get_match().is_empty())
+                .map(|captures| {
+                    let found = captures.get_match();
+                    TextEdit {
+                        range: lsp_types::Range {
+                            start: rope_text.offset_to_position(found.start()),
+                            end: rope_text.offset_to_position(found.end()),
+                        },
+                        new_text: if search.regex {
+                            expand_replacement(&captures, &search.replacement)
+                        } else {
+                            search.replacement.clone()
+                        },
+                    }
+                })
+                .collect::<Vec<_>>();
+            if let Ok(uri) = Url::from_file_path(&file.path)
+                && !edits.is_empty()
+            {
+                changes.insert(uri, edits);
+            }
+        }
+
+        let edit = WorkspaceEdit {
+            changes: Some(changes),
+            ..Default::default()
+        };
+        let title = format!("Replace `{}` with `{}`", search.query, search.replacement);
+        let edit = PendingEdit::new(title, edit, &self.documents);
+        self.set_pending_edit(edit);
+    }
+}
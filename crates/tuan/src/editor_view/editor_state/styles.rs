@@ -1,23 +1,90 @@
 use std::{
     collections::HashMap,
     path::PathBuf,
-    sync::{Arc, Mutex, RwLock},
+    sync::{Arc, Mutex},
 };
 
+use crossbeam_channel::Sender;
+
 use crate::document;
 
+/// Reparses the documents off the UI thread, one at a time on a single thread.
+///
+/// The reparses asked for a document while the thread is busy are coalesced
+/// into one, which catches up with all the edits made meanwhile.
+#[derive(Clone)]
+pub(crate) struct StylesWorker {
+    tx: Sender<PathBuf>,
+}
+
+impl StylesWorker {
+    /// Starts the thread reparsing the documents of `documents`, which stops
+    /// once every clone of the worker is dropped.
+    pub(crate) fn new(documents: Arc<Mutex<HashMap<PathBuf, document::Document>>>) -> Self {
+        let (tx, rx) = crossbeam_channel::unbounded::<PathBuf>();
+        std::thread::Builder::new()
+            .name("styles".to_string())
+            .spawn(move || {
+                while let Ok(path) = rx.recv() {
+                    let mut paths = vec![path];
+                    for path in rx.try_iter() {
+                        if !paths.contains(&path) {
+                            paths.push(path);
+                        }
+                    }
+                    for path in paths {
+                        update_styles(&documents, &path);
+                    }
+                }
+            })
+            .expect("Failed to start the styles thread");
+        Self { tx }
+    }
+
+    /// Queues a reparse of the document at `path`, which then swaps in its new styles.
+    pub(crate) fn update(&self, path: PathBuf) {
+        let _ = self.tx.send(path);
+    }
+}
+
 impl super::EditorState {
-    pub(super) fn update_styles_with_syntax(
-        documents: Arc<Mutex<HashMap<PathBuf, document::Document>>>,
-        path: PathBuf,
-    ) {
-        std::thread::spawn(move || {
-            let mut docs = documents.lock().unwrap();
-            if let Some(document) = docs.get_mut(&path) {
-                document.update_styles_with_syntax();
-            } else {
-                tracing::debug!("Document not found for path: {:?}", path);
-            }
-        });
+    /// Reparses the document at `path` off the UI thread, then swaps in its new styles.
+    ///
+    /// The document keeps its previous styles, shifted along the edits, until then.
+    pub(super) fn update_styles_with_syntax(&self, path: PathBuf) {
+        self.styles_worker.update(path);
+    }
+}
+
+/// Reparses the document at `path` with the edits made since its last parse.
+fn update_styles(documents: &Mutex<HashMap<PathBuf, document::Document>>, path: &PathBuf) {
+    let Some(highlighter) = documents
+        .lock()
+        .unwrap()
+        .get(path)
+        .map(|document| document.highlighter())
+    else {
+        tracing::debug!("Document not found for path: {:?}", path);
+        return;
+    };
+    let mut highlighter = highlighter.lock().unwrap();
+
+    let Some((rev, text, edits, config)) = documents
+        .lock()
+        .unwrap()
+        .get_mut(path)
+        .map(|document| document.take_syntax_edits())
+    else {
+        return;
+    };
+    let Some(styles) = highlighter.highlight(rev, text, edits, &config) else {
+        return;
+    };
+
+    if let Some(document) = documents.lock().unwrap().get_mut(path) {
+        // Otherwise the document was edited meanwhile, and another reparse is queued.
+        if document.buffer.rev() == rev {
+            document.set_styles(styles);
+        }
     }
 }
@@ -0,0 +1,161 @@
+use std::path::PathBuf;
+
+/// What the tab bar shows for an open document.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Tab {
+    pub path: PathBuf,
+    pub title: String,
+    pub is_dirty: bool,
+    pub is_focused: bool,
+}
+
+impl super::EditorState {
+    /// The open documents, in tab order.
+    pub fn tabs(&self) -> Vec<Tab> {
+        let documents = self.documents.lock().unwrap();
+        self.open_documents
+            .iter()
+            .map(|path| Tab {
+                path: path.clone(),
+                title: path
+                    .file_name()
+                    .map(|name| name.to_string_lossy().into_owned())
+                    .unwrap_or_else(|| path.display().to_string()),
+                is_dirty: documents.get(path).is_some_and(|d| d.is_dirty()),
+                is_focused: self.focused_document_path.as_ref() == Some(path),
+            })
+            .collect()
+    }
+
+    /// Adds a tab for `path` after the focused one, unless it already has one.
+    pub(super) fn add_tab(&mut self, path: PathBuf) {
+        if self.open_documents.contains(&path) {
+            return;
+        }
+        let index = self
+            .focused_tab_index()
+            .map_or(self.open_documents.len(), |index| index + 1);
+        self.open_documents.insert(index, path);
+    }
+
+    pub fn focus_tab(&mut self, path: PathBuf) {
+        if self.open_documents.contains(&path) {
+            self.note_recent_file(&path);
+            self.focused_document_path = Some(path);
+        }
+    }
+
+    pub fn next_tab(&mut self) {
+        self.cycle_tab(1);
+    }
+
+    pub fn previous_tab(&mut self) {
+        self.cycle_tab(-1);
+    }
+
+    fn cycle_tab(&mut self, offset: isize) {
+        let count = self.open_documents.len();
+        let Some(index) = self.focused_tab_index() else {
+            return;
+        };
+        let index = (index as isize + offset).rem_euclid(count as isize) as usize;
+        self.focus_tab(self.open_documents[index].clone());
+    }
+
+    /// Moves the focused tab by `offset` places, staying within the tab bar.
+    pub fn move_tab(&mut self, offset: isize) {
+        let Some(index) = self.focused_tab_index() else {
+            return;
+        };
+        let target = index
+            .saturating_add_signed(offset)
+            .min(self.open_documents.len() - 1);
+        let path = self.open_documents.remove(index);
+        self.open_documents.insert(target, path);
+    }
+
+    /// Closes the tab of `path`, forgetting its document, cursors and scrolling,
+    /// and has the proxy close its buffer.
+    ///
+    /// A dirty document is only closed when `discard` is set; otherwise the
+    /// user is asked to confirm through `close_confirmation`.
+    pub fn close_tab(&mut self, path: PathBuf, discard: bool) {
+        let Some(index) = self.open_documents.iter().position(|p| *p == path) else {
+            return;
+        };
+        let is_dirty = self
+            .documents
+            .lock()
+            .unwrap()
+            .get(&path)
+            .is_some_and(|d| d.is_dirty());
+        if is_dirty && !discard {
+            self.close_confirmation = Some(path);
+            return;
+        }
+
+        self.open_documents.remove(index);
+        self.documents.lock().unwrap().remove(&path);
+        self.proxy.proxy_rpc.close_buffer(path.clone());
+        self.document_cursors.remove(&path);
+        self.document_scrollings.remove(&path);
+        if self.close_confirmation.as_ref() == Some(&path) {
+            self.close_confirmation = None;
+        }
+
+        // Panes showing it focus the tab that took its place, or the new last one.
+        let fallback = self
+            .open_documents
+            .get(index.min(self.open_documents.len().saturating_sub(1)))
+            .cloned();
+        if self.focused_document_path.as_ref() == Some(&path) {
+            self.focused_document_path = fallback.clone();
+        }
+        for pane in self.parked_panes.values_mut() {
+            pane.forget(&path, fallback.clone());
+        }
+    }
+
+    pub fn close_focused_tab(&mut self) {
+        if let Some(path) = self.focused_document_path.clone() {
+            self.close_tab(path, false);
+        }
+    }
+
+    fn focused_tab_index(&self) -> Option<usize> {
+        let focused = self.focused_document_path.as_ref()?;
+        self.open_documents.iter().position(|path| path == focused)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::test_support::test_state;
+
+    #[test]
+    fn cycling_tabs_notes_the_focused_file_as_recent() {
+        let mut state = test_state(&[("a.txt", "a"), ("b.txt", "b")]);
+        let a = state.open_loaded("a.txt");
+        let b = state.open_loaded("b.txt");
+
+        state.next_tab();
+        assert_eq!(state.focused_document_path.as_ref(), Some(&a));
+        assert_eq!(state.recent_files.first(), Some(&a));
+
+        state.previous_tab();
+        assert_eq!(state.focused_document_path.as_ref(), Some(&b));
+        assert_eq!(state.recent_files.first(), Some(&b));
+    }
+
+    #[test]
+    fn closing_a_tab_forgets_its_document() {
+        let mut state = test_state(&[("a.txt", "a"), ("b.txt", "b")]);
+        let a = state.open_loaded("a.txt");
+        let b = state.open_loaded("b.txt");
+
+        state.close_tab(b.clone(), false);
+        assert_eq!(state.open_documents, vec![a.clone()]);
+        assert_eq!(state.focused_document_path, Some(a));
+        assert!(!state.documents.lock().unwrap().contains_key(&b));
+    }
+}
@@ -0,0 +1,119 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicUsize, Ordering},
+    },
+    time::{Duration, Instant},
+};
+
+use tuan_rpc::{
+    RequestId, RpcError,
+    core::CoreNotification,
+    proxy::{ProxyHandler, ProxyNotification, ProxyRequest, ProxyResponse, ProxyRpcHandler},
+};
+
+use super::EditorState;
+use crate::{clipboard::InMemoryClipboard, editor_view::EditorConfig, proxy, settings::Settings};
+
+/// How long to wait for the fake proxy before failing a test.
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+/// A proxy opening files from the disk, and failing every other request.
+struct FakeProxy {
+    proxy_rpc: ProxyRpcHandler,
+}
+
+impl ProxyHandler for FakeProxy {
+    fn handle_notification(&mut self, _rpc: ProxyNotification) {}
+
+    fn handle_request(&mut self, id: RequestId, rpc: ProxyRequest) {
+        let result = match rpc {
+            ProxyRequest::NewBuffer { path, .. } => std::fs::read_to_string(&path)
+                .map(|content| ProxyResponse::NewBufferResponse {
+                    content,
+                    read_only: false,
+                })
+                .map_err(|err| RpcError {
+                    code: 0,
+                    message: err.to_string(),
+                }),
+            rpc => Err(RpcError {
+                code: 0,
+                message: format!("Not supported by the fake proxy: {rpc:?}"),
+            }),
+        };
+        self.proxy_rpc.handle_response(id, result);
+    }
+}
+
+/// An editor on a new workspace holding `files`, as `(relative path, content)`,
+/// with a fake proxy and an in-memory clipboard.
+pub(super) fn test_state(files: &[(&str, &str)]) -> EditorState {
+    static WORKSPACES: AtomicUsize = AtomicUsize::new(0);
+    let workspace_path = std::env::temp_dir().join(format!(
+        "tuan-test-{}-{}",
+        std::process::id(),
+        WORKSPACES.fetch_add(1, Ordering::Relaxed)
+    ));
+    for (relative, content) in files {
+        let path = workspace_path.join(relative);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, content).unwrap();
+    }
+    std::fs::create_dir_all(&workspace_path).unwrap();
+
+    let settings = Settings::default();
+    let editor_config = EditorConfig::from_settings(&settings).unwrap();
+    let proxy = proxy::new_fake_proxy(|proxy_rpc| FakeProxy { proxy_rpc });
+    let mut state =
+        EditorState::with_proxy(workspace_path, settings, Arc::new(editor_config), proxy);
+    state.clipboard = Arc::new(Mutex::new(InMemoryClipboard::default()));
+    state
+}
+
+/// Waits until `done` holds, failing the test after a while.
+pub(super) fn wait_until(mut done: impl FnMut() -> bool) {
+    let start = Instant::now();
+    while !done() {
+        assert!(
+            start.elapsed() < TIMEOUT,
+            "Timed out waiting on the fake proxy"
+        );
+        std::thread::sleep(Duration::from_millis(5));
+    }
+}
+
+impl EditorState {
+    /// Opens the workspace file at `relative` and waits until it is loaded.
+    pub(super) fn open_loaded(&mut self, relative: impl AsRef<Path>) -> PathBuf {
+        let path = self.workspace_path.join(relative);
+        self.open_file_at(path.clone(), (0, 0));
+        let documents = self.documents.clone();
+        wait_until(|| documents.lock().unwrap().contains_key(&path));
+        path
+    }
+
+    /// The text of the open document at `path`.
+    pub(super) fn text(&self, path: &PathBuf) -> String {
+        self.documents.lock().unwrap()[path]
+            .buffer
+            .text()
+            .to_string()
+    }
+
+    /// The offsets of the cursors of the document at `path`.
+    pub(super) fn cursor_offsets(&self, path: &PathBuf) -> Vec<usize> {
+        self.document_cursors[path]
+            .iter()
+            .map(|cursor| cursor.get_cursor_offset())
+            .collect()
+    }
+
+    /// Handles the next notification sent by the proxy, as the UI thread does.
+    pub(super) fn handle_next_notification(&mut self) -> CoreNotification {
+        let notification = self.notification_rx().recv_timeout(TIMEOUT).unwrap();
+        self.handle_notification(notification.clone());
+        notification
+    }
+}
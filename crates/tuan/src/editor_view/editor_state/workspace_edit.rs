@@ -0,0 +1,268 @@
+use std::{collections::HashMap, ops::Range, path::PathBuf, sync::Mutex};
+
+use lapce_xi_rope::Rope;
+use lsp_types::{DocumentChangeOperation, DocumentChanges, OneOf, TextEdit, WorkspaceEdit};
+use tuan_core::buffer::rope_text::{RopeText, RopeTextRef};
+
+use super::completion::shift_offset;
+use crate::document::{Document, history::EditKind};
+
+/// A workspace edit, e.g. of a rename, shown to the user before it is applied.
+#[derive(Debug, Clone)]
+pub struct PendingEdit {
+    pub title: String,
+    pub files: Vec<FileEdit>,
+    /// The file creations, renames and deletions of the edit, which are not supported.
+    pub skipped_operations: usize,
+}
+
+/// The edits of a workspace edit to one file.
+#[derive(Debug, Clone)]
+pub struct FileEdit {
+    pub path: PathBuf,
+    edits: Vec<TextEdit>,
+    pub previews: Vec<EditPreview>,
+}
+
+/// The lines changed by some edits of a file, before and after them.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EditPreview {
+    /// The first changed line.
+    pub line: usize,
+    pub before: String,
+    pub after: String,
+}
+
+impl PendingEdit {
+    /// Collects the text edits of `edit` by file, with a preview of their
+    /// changes computed on the open documents or else on the files on disk.
+    pub fn new(
+        title: String,
+        edit: WorkspaceEdit,
+        documents: &Mutex<HashMap<PathBuf, Document>>,
+    ) -> Self {
+        let mut skipped_operations = 0;
+        let mut edits_by_path = Vec::<(PathBuf, Vec<TextEdit>)>::new();
+        let mut add = |uri: lsp_types::Url, edits: Vec<TextEdit>| {
+            let Ok(path) = uri.to_file_path() else {
+                tracing::warn!("Skipping the edits of {uri}");
+                return;
+            };
+            match edits_by_path.iter_mut().find(|(p, _)| *p == path) {
+                Some((_, existing)) => existing.extend(edits),
+                None => edits_by_path.push((path, edits)),
+            }
+        };
+
+        // `document_changes` is preferred over `changes` when both are given.
+        match edit.document_changes {
+            Some(DocumentChanges::Edits(document_edits)) => {
+                for document_edit in document_edits {
+                    add(
+                        document_edit.text_document.uri,
+                        text_edits(document_edit.edits),
+                    );
+                }
+            }
+            Some(DocumentChanges::Operations(operations)) => {
+                for operation in operations {
+                    match operation {
+                        DocumentChangeOperation::Edit(document_edit) => {
+                            add(
+                                document_edit.text_document.uri,
+                                text_edits(document_edit.edits),
+                            );
+                        }
+                        DocumentChangeOperation::Op(operation) => {
+                            tracing::warn!("Skipping unsupported file operation {operation:?}");
+                            skipped_operations += 1;
+                        }
+                    }
+                }
+            }
+            None => {
+                for (uri, edits) in edit.changes.unwrap_or_default() {
+                    add(uri, edits);
+                }
+            }
+        }
+
+        let files = edits_by_path
+            .into_iter()
+            .filter(|(_, edits)| !edits.is_empty())
+            .map(|(path, edits)| {
+                let text = match documents.lock().unwrap().get(&path) {
+                    Some(document) => document.buffer.text().clone(),
+                    None => Rope::from(std::fs::read_to_string(&path).unwrap_or_default()),
+                };
+                let previews = previews(&text, &offset_edits(&text, &edits));
+                FileEdit {
+                    path,
+                    edits,
+                    previews,
+                }
+            })
+            .collect();
+
+        Self {
+            title,
+            files,
+            skipped_operations,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+}
+
+fn text_edits(edits: Vec<OneOf<TextEdit, lsp_types::AnnotatedTextEdit>>) -> Vec<TextEdit> {
+    edits
+        .into_iter()
+        .map(|edit| match edit {
+            OneOf::Left(edit) => edit,
+            OneOf::Right(annotated) => annotated.text_edit,
+        })
+        .collect()
+}
+
+/// Converts LSP text edits of `text` to offset ranges, sorted by offset.
+fn offset_edits(text: &Rope, edits: &[TextEdit]) -> Vec<(Range<usize>, String)> {
+    let text = RopeTextRef::new(text);
+    let mut edits = edits
+        .iter()
+        .map(|edit| {
+            let start = text.offset_of_position(&edit.range.start);
+            let end = text.offset_of_position(&edit.range.end);
+            (start..end.max(start), edit.new_text.clone())
+        })
+        .collect::<Vec<_>>();
+    edits.sort_by_key(|(range, _)| range.start);
+    edits
+}
+
+/// The lines changed by `edits` (sorted by offset), edits on the same lines being grouped.
+fn previews(text: &Rope, edits: &[(Range<usize>, String)]) -> Vec<EditPreview> {
+    let text = RopeTextRef::new(text);
+    let mut previews = Vec::new();
+    let mut edits = edits.iter().peekable();
+    while let Some(first) = edits.next() {
+        let line = text.line_of_offset(first.0.start);
+        let mut last_line = text.line_of_offset(first.0.end);
+        let mut group = vec![first];
+        while let Some(next) =
+            edits.next_if(|(range, _)| text.line_of_offset(range.start) <= last_line)
+        {
+            last_line = last_line.max(text.line_of_offset(next.0.end));
+            group.push(next);
+        }
+
+        let start = text.offset_of_line(line);
+        let end = text.offset_of_line(last_line + 1);
+        let before = text.slice_to_cow(start..end).into_owned();
+        let mut after = before.clone();
+        for (range, new_text) in group.iter().rev() {
+            after.replace_range(range.start - start..range.end.min(end) - start, new_text);
+        }
+        previews.push(EditPreview {
+            line,
+            before: preview_line(&before),
+            after: preview_line(&after),
+        });
+    }
+    previews
+}
+
+/// Shows changed lines on a single line.
+fn preview_line(text: &str) -> String {
+    text.trim().replace('\n', " ⏎ ")
+}
+
+impl super::EditorState {
+    /// Shows `edit` for confirmation, see [`Self::apply_pending_edit`].
+    pub(super) fn set_pending_edit(&mut self, edit: PendingEdit) {
+        if edit.is_empty() {
+            tracing::info!("{}: nothing to change", edit.title);
+            return;
+        }
+        self.pending_edit = Some(edit);
+    }
+
+    pub fn cancel_pending_edit(&mut self) {
+        self.pending_edit = None;
+    }
+
+    /// Applies the pending workspace edit, as a single undoable step per file.
+    ///
+    /// Files that are not open are opened through the proxy, and edited once loaded.
+    pub fn apply_pending_edit(&mut self) {
+        let Some(pending_edit) = self.pending_edit.take() else {
+            return;
+        };
+        for file in pending_edit.files {
+            let edits = file.edits.clone();
+            let on_load = move |document: &mut Document| {
+                let offset_edits = offset_edits(document.buffer.text(), &edits);
+                document.edit(offset_edits, EditKind::Other, Vec::new());
+                document.break_undo_group();
+            };
+            // Checked first, as a document is only inserted once its loading ends.
+            if self.queue_on_load(&file.path, on_load.clone()) {
+                continue;
+            }
+            if self.documents.lock().unwrap().contains_key(&file.path) {
+                self.apply_file_edit(file.path, &file.edits);
+            } else {
+                self.open_file_with(file.path, on_load);
+            }
+        }
+    }
+
+    /// Applies LSP `edits` to the open document at `path`.
+    fn apply_file_edit(&mut self, path: PathBuf, edits: &[TextEdit]) {
+        let Some(edits) = self
+            .documents
+            .lock()
+            .unwrap()
+            .get(&path)
+            .map(|document| offset_edits(document.buffer.text(), edits))
+        else {
+            return;
+        };
+        self.edit_document(path, edits);
+    }
+
+    /// Replaces each range (in offsets, sorted) of the open document at
+    /// `path` by its text as a single undoable step, moving its cursors along.
+    pub(super) fn edit_document(&mut self, path: PathBuf, edits: Vec<(Range<usize>, String)>) {
+        let cursor_offsets = self
+            .document_cursors
+            .get(&path)
+            .map(|cursors| {
+                cursors
+                    .iter()
+                    .map(|c| c.get_cursor_offset())
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+
+        let Some(cursors) = self.edit_with_parked_cursors(&path, |document| {
+            let cursors_before = cursor_offsets
+                .iter()
+                .map(|offset| document.offset_to_position(*offset))
+                .collect();
+            document.edit(edits.clone(), EditKind::Other, cursors_before);
+            document.break_undo_group();
+            cursor_offsets
+                .iter()
+                .map(|offset| document.offset_to_position(shift_offset(*offset, &edits, false)))
+                .collect::<Vec<_>>()
+        }) else {
+            return;
+        };
+        self.update_styles_with_syntax(path.clone());
+        if !cursors.is_empty() {
+            self.set_cursors(path, &cursors);
+        }
+    }
+}
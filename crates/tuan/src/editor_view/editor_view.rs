@@ -1,7 +1,24 @@
-use super::paint::line::Line;
-use crate::theme;
-use crate::theme::theme::Theme as _;
-use crate::{document::Document, editor_view::EditorState};
+use super::{
+    paint::{
+        gutter::{Gutter, diagnostic_markers},
+        line::Line,
+        popup::{Placement, paint_popup, paint_rich_popup},
+        selection::{paint_find_matches, paint_selections},
+    },
+    rich_label::rich_label,
+};
+use crate::{
+    document::{DiskChange, Document},
+    editor_view::{
+        CodeActionList, CommandPalette, EditorConfig, EditorState, FindBar, LocationList,
+        LspResponse, PaneId, PaneLayout, PaneView, PendingEdit, Problem, ProjectSearch, QuickOpen,
+        QuickOpenItem, RenameInput, Tab,
+    },
+    explorer::explorer_view,
+    markdown::RichText,
+};
+use crossbeam_channel::Receiver;
+use lsp_types::{CodeActionOrCommand, DiagnosticSeverity};
 use masonry::core::Modifiers;
 use masonry::core::keyboard::Key;
 use masonry::{
@@ -9,58 +26,753 @@ use masonry::{
     core::{ScrollDelta, Widget},
     kurbo::Rect,
 };
+use std::collections::HashSet;
+use std::fmt::Debug;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 use std::time::Duration;
+use tuan_rpc::core::CoreNotification;
 use winit::dpi::LogicalPosition;
 use xilem::{Affine, Color};
 use xilem::{
-    Pod, ViewCtx, WidgetView,
-    core::{MessageResult, View, ViewMarker, fork},
+    AnyWidgetView, Pod, ViewCtx, WidgetView,
+    core::{MessageResult, NoElement, View, ViewMarker, fork},
     tokio,
-    view::{button, flex, task},
+    view::{Axis, FlexExt as _, button, checkbox, flex, label, portal, task, task_raw, text_input},
 };
 
 pub fn editor_view(state: &mut EditorState) -> impl WidgetView<EditorState> + use<> {
-    state.open_file("/Users/arthurfontaine/Developer/code/local/la-galerie-de-max/la-galerie-de-max copie/package.json".into());
+    let config_watcher_task = channel_task(
+        state.config_watcher.changed_files(),
+        |data: &mut EditorState, changed_files: HashSet<PathBuf>| {
+            data.reload_config(&changed_files);
+        },
+    );
+
+    let notification_task = channel_task(
+        state.notification_rx(),
+        |data: &mut EditorState, notification: CoreNotification| {
+            data.handle_notification(notification);
+        },
+    );
+    let lsp_response_task = channel_task(
+        state.lsp_response_rx(),
+        |data: &mut EditorState, response: LspResponse| {
+            data.handle_lsp_response(response);
+        },
+    );
 
     fork(
-        // TODO: remove the flex box and the Open File button, those are just for testing
-        flex((
-            button("Open File", |state: &mut EditorState| {
-                state.focus_document("/Users/arthurfontaine/Developer/code/local/la-galerie-de-max/la-galerie-de-max copie/package.json".into());
-            }),
-            EditorView,
-        )),
-        task(
-            async move |proxy| {
-                let mut interval = tokio::time::interval(Duration::from_millis(500));
-                loop {
-                    interval.tick().await;
-                    let Ok(()) = proxy.message(()) else {
-                        break;
-                    };
+        fork(
+            fork(
+                fork(
+                    flex((
+                        flex((
+                            state.explorer_visible.then(|| explorer_view(state)),
+                            state.search.clone().map(|search| {
+                                search_panel(search, &state.workspace_path, state.config.clone())
+                            }),
+                            flex((
+                                state.config_error.clone().map(label),
+                                state.command_palette.clone().map(command_palette),
+                                state.quick_open.clone().map(|quick_open| {
+                                    quick_open_panel(
+                                        quick_open,
+                                        state.quick_open_items(),
+                                        state.quick_open_indexing(),
+                                        state.config.clone(),
+                                    )
+                                }),
+                                tab_bar(state.tabs()),
+                                state.close_confirmation.clone().map(close_confirmation_bar),
+                                state
+                                    .delete_confirmation
+                                    .clone()
+                                    .map(delete_confirmation_bar),
+                                state
+                                    .focused_disk_change()
+                                    .map(|(path, change)| disk_change_bar(path, change)),
+                                state.rename.clone().map(rename_bar),
+                                state.save_as.clone().map(save_as_bar),
+                                state
+                                    .find
+                                    .clone()
+                                    .map(|find| find_bar(find, state.find_summary())),
+                                pane_view(&state.layout),
+                                state
+                                    .problems_visible
+                                    .then(|| problems_panel(state.problems())),
+                                state.locations.clone().map(locations_panel),
+                                state.code_actions.clone().map(code_actions_panel),
+                                state.pending_edit.clone().map(pending_edit_panel),
+                            ))
+                            .flex(1.0),
+                        ))
+                        .direction(Axis::Horizontal)
+                        .flex(1.0),
+                        status_bar(state),
+                    )),
+                    task(
+                        async move |proxy| {
+                            let mut interval = tokio::time::interval(Duration::from_millis(500));
+                            loop {
+                                interval.tick().await;
+                                let Ok(()) = proxy.message(()) else {
+                                    break;
+                                };
+                            }
+                        },
+                        |data: &mut EditorState, ()| {
+                            data.tick_cursors();
+                        },
+                    ),
+                ),
+                config_watcher_task,
+            ),
+            notification_task,
+        ),
+        lsp_response_task,
+    )
+}
+
+/// Forwards what `rx` receives to `on_message`, on the UI thread.
+///
+/// The channels of the proxy are blocking, so they are read from a blocking thread.
+fn channel_task<T, H>(
+    rx: Receiver<T>,
+    on_message: H,
+) -> impl View<EditorState, (), ViewCtx, Element = NoElement> + use<T, H>
+where
+    T: Debug + Send + 'static,
+    H: Fn(&mut EditorState, T) + Send + Sync + 'static,
+{
+    task_raw(
+        move |proxy| {
+            let rx = rx.clone();
+            async move {
+                let forward = tokio::task::spawn_blocking(move || {
+                    while let Ok(message) = rx.recv() {
+                        let Ok(()) = proxy.message(message) else {
+                            break;
+                        };
+                    }
+                });
+                if let Err(err) = forward.await {
+                    tracing::error!("Channel forwarding stopped: {err}");
                 }
-            },
-            |data: &mut EditorState, ()| {
-                data.tick_cursors();
-            },
+            }
+        },
+        on_message,
+    )
+}
+
+fn status_bar(state: &EditorState) -> impl WidgetView<EditorState> + use<> {
+    let (errors, warnings) = state.problem_counts();
+    let message = state.status_message.clone().map(|message| {
+        flex((
+            label(message),
+            button("×", |state: &mut EditorState| state.dismiss_message()),
+        ))
+        .direction(Axis::Horizontal)
+    });
+    flex((
+        label(state.proxy_status_text()),
+        button(
+            format!("✖ {errors}  ⚠ {warnings}"),
+            |state: &mut EditorState| state.toggle_problems(),
         ),
+        message,
+    ))
+    .direction(Axis::Horizontal)
+}
+
+/// The diagnostics of the whole workspace, each one jumping to its location when clicked.
+fn problems_panel(problems: Vec<Problem>) -> impl WidgetView<EditorState> + use<> {
+    let rows = problems
+        .into_iter()
+        .map(|problem| {
+            let icon = match problem.severity {
+                DiagnosticSeverity::ERROR => "✖",
+                DiagnosticSeverity::WARNING => "⚠",
+                _ => "ℹ",
+            };
+            let text = format!(
+                "{icon} {}:{}:{}  {}",
+                problem.path.display(),
+                problem.position.0 + 1,
+                problem.position.1 + 1,
+                problem.message
+            );
+            button(text, move |state: &mut EditorState| {
+                state.jump_to_problem(problem.clone())
+            })
+        })
+        .collect::<Vec<_>>();
+
+    flex((label("Problems"), portal(flex(rows))))
+}
+
+/// The locations found by a navigation request, each one jumping there when clicked.
+fn locations_panel(locations: LocationList) -> impl WidgetView<EditorState> + use<> {
+    let rows = locations
+        .items
+        .into_iter()
+        .map(|item| {
+            let text = format!(
+                "{}:{}:{}  {}",
+                item.path.display(),
+                item.position.line + 1,
+                item.position.character + 1,
+                item.preview
+            );
+            button(text, move |state: &mut EditorState| {
+                state.jump_to_location(item.clone())
+            })
+        })
+        .collect::<Vec<_>>();
+
+    flex((
+        flex((
+            label(locations.kind.title()),
+            button("Close", |state: &mut EditorState| state.close_locations()),
+        ))
+        .direction(Axis::Horizontal),
+        portal(flex(rows)),
+    ))
+}
+
+/// The code actions offered at the cursor, each one previewing its edit when clicked.
+fn code_actions_panel(code_actions: CodeActionList) -> impl WidgetView<EditorState> + use<> {
+    let rows = code_actions
+        .actions
+        .iter()
+        .enumerate()
+        .map(|(index, action)| {
+            let title = match action {
+                CodeActionOrCommand::CodeAction(action) => action.title.clone(),
+                CodeActionOrCommand::Command(command) => command.title.clone(),
+            };
+            button(title, move |state: &mut EditorState| {
+                state.pick_code_action(index)
+            })
+        })
+        .collect::<Vec<_>>();
+
+    flex((
+        flex((
+            label("Code actions"),
+            button("Close", |state: &mut EditorState| {
+                state.close_code_actions()
+            }),
+        ))
+        .direction(Axis::Horizontal),
+        portal(flex(rows)),
+    ))
+}
+
+/// The changes of a workspace edit, line by line, to apply or cancel.
+fn pending_edit_panel(edit: PendingEdit) -> impl WidgetView<EditorState> + use<> {
+    let rows = edit
+        .files
+        .iter()
+        .flat_map(|file| {
+            file.previews.iter().map(|preview| {
+                label(format!(
+                    "{}:{}  {}  →  {}",
+                    file.path.display(),
+                    preview.line + 1,
+                    preview.before,
+                    preview.after
+                ))
+            })
+        })
+        .collect::<Vec<_>>();
+
+    flex((
+        flex((
+            label(edit.title),
+            button("Apply", |state: &mut EditorState| {
+                state.apply_pending_edit()
+            }),
+            button("Cancel", |state: &mut EditorState| {
+                state.cancel_pending_edit()
+            }),
+        ))
+        .direction(Axis::Horizontal),
+        (edit.skipped_operations > 0).then(|| {
+            label(format!(
+                "{} file operation(s) are not supported and will be skipped.",
+                edit.skipped_operations
+            ))
+        }),
+        portal(flex(rows)),
+    ))
+}
+
+/// The panes of `layout`, each one an editor with its own scrolling and cursors.
+fn pane_view(layout: &PaneLayout) -> Box<AnyWidgetView<EditorState>> {
+    match layout {
+        PaneLayout::Pane(pane) => EditorView { pane: *pane }.boxed(),
+        PaneLayout::Split { axis, children } => flex(
+            children
+                .iter()
+                .map(|child| pane_view(child).flex(1.0))
+                .collect::<Vec<_>>(),
+        )
+        .direction(*axis)
+        .boxed(),
+    }
+}
+
+fn tab_bar(tabs: Vec<Tab>) -> impl WidgetView<EditorState> + use<> {
+    flex(
+        tabs.into_iter()
+            .map(|tab| {
+                let title = match (tab.is_focused, tab.is_dirty) {
+                    (true, true) => format!("[● {}]", tab.title),
+                    (true, false) => format!("[{}]", tab.title),
+                    (false, true) => format!("● {}", tab.title),
+                    (false, false) => tab.title,
+                };
+                flex((
+                    button(title, {
+                        let path = tab.path.clone();
+                        move |state: &mut EditorState| state.focus_tab(path.clone())
+                    }),
+                    button("×", move |state: &mut EditorState| {
+                        state.close_tab(tab.path.clone(), false)
+                    }),
+                ))
+                .direction(Axis::Horizontal)
+            })
+            .collect::<Vec<_>>(),
     )
+    .direction(Axis::Horizontal)
+}
+
+/// Asks before moving an explorer entry to the trash.
+fn delete_confirmation_bar(path: PathBuf) -> impl WidgetView<EditorState> + use<> {
+    flex((
+        label(format!(
+            "Move {} to the trash? Its open tabs will be closed.",
+            path.display()
+        )),
+        button("Move to Trash", |state: &mut EditorState| {
+            state.confirm_delete()
+        }),
+        button("Cancel", |state: &mut EditorState| state.cancel_delete()),
+    ))
+    .direction(Axis::Horizontal)
+}
+
+/// Asks before closing a document with unsaved changes.
+fn close_confirmation_bar(path: PathBuf) -> impl WidgetView<EditorState> + use<> {
+    flex((
+        label(format!("{} has unsaved changes.", path.display())),
+        button("Close without saving", {
+            let path = path.clone();
+            move |state: &mut EditorState| state.close_tab(path.clone(), true)
+        }),
+        button("Cancel", |state: &mut EditorState| {
+            state.close_confirmation = None;
+        }),
+    ))
+    .direction(Axis::Horizontal)
+}
+
+/// Searches the files of the workspace, and replaces the matches in the checked files.
+fn search_panel(
+    search: ProjectSearch,
+    workspace_path: &Path,
+    config: Arc<EditorConfig>,
+) -> impl WidgetView<EditorState> + use<> {
+    let status = if let Some(error) = &search.error {
+        error.clone()
+    } else if search.searching {
+        "Searching…".to_string()
+    } else if search.query.is_empty() {
+        String::new()
+    } else {
+        format!(
+            "{} results in {} files",
+            search.match_count(),
+            search.files.len()
+        )
+    };
+
+    let files = search
+        .files
+        .into_iter()
+        .map(|file| {
+            let title = format!(
+                "{} ({})",
+                file.path
+                    .strip_prefix(workspace_path)
+                    .unwrap_or(&file.path)
+                    .display(),
+                file.matches.len()
+            );
+            let rows = file
+                .matches
+                .into_iter()
+                .map(|found| {
+                    let path = file.path.clone();
+                    flex((
+                        button(format!("{:>5}", found.line + 1), {
+                            let found = found.clone();
+                            move |state: &mut EditorState| {
+                                state.open_search_match(path.clone(), found.clone())
+                            }
+                        }),
+                        rich_label(found.preview, config.clone()),
+                    ))
+                    .direction(Axis::Horizontal)
+                })
+                .collect::<Vec<_>>();
+            flex((
+                checkbox(title, file.included, {
+                    let path = file.path.clone();
+                    move |state: &mut EditorState, included| {
+                        state.set_search_file_included(path.clone(), included)
+                    }
+                }),
+                flex(rows),
+            ))
+        })
+        .collect::<Vec<_>>();
+
+    flex((
+        flex((
+            label("Search"),
+            button("×", |state: &mut EditorState| state.toggle_search()),
+        ))
+        .direction(Axis::Horizontal),
+        text_input(search.query, |state: &mut EditorState, query| {
+            state.set_search_query(query)
+        })
+        .on_enter(|state: &mut EditorState, _| state.run_search()),
+        flex((
+            button(
+                toggle_label("Aa", search.case_sensitive),
+                |state: &mut EditorState| state.toggle_search_case_sensitive(),
+            ),
+            button(
+                toggle_label(".*", search.regex),
+                |state: &mut EditorState| state.toggle_search_regex(),
+            ),
+            button("Search", |state: &mut EditorState| state.run_search()),
+        ))
+        .direction(Axis::Horizontal),
+        flex((
+            label("Include:"),
+            text_input(search.include, |state: &mut EditorState, include| {
+                state.set_search_include(include)
+            })
+            .on_enter(|state: &mut EditorState, _| state.run_search()),
+        ))
+        .direction(Axis::Horizontal),
+        flex((
+            label("Exclude:"),
+            text_input(search.exclude, |state: &mut EditorState, exclude| {
+                state.set_search_exclude(exclude)
+            })
+            .on_enter(|state: &mut EditorState, _| state.run_search()),
+        ))
+        .direction(Axis::Horizontal),
+        flex((
+            label("Replace:"),
+            text_input(
+                search.replacement,
+                |state: &mut EditorState, replacement| state.set_search_replacement(replacement),
+            ),
+            button("Replace all", |state: &mut EditorState| {
+                state.replace_in_files()
+            }),
+        ))
+        .direction(Axis::Horizontal),
+        label(status),
+        portal(flex(files)).flex(1.0),
+    ))
+}
+
+/// Lists the editor actions matching the input, running one when clicked
+/// or the best match when Enter is pressed.
+fn command_palette(palette: CommandPalette) -> impl WidgetView<EditorState> + use<> {
+    let rows = palette
+        .items
+        .into_iter()
+        .map(|item| {
+            let text = match &item.key {
+                Some(key) => format!("{}    {}", item.label, key),
+                None => item.label.clone(),
+            };
+            button(text, move |state: &mut EditorState| {
+                state.run_palette_action(item.action.clone())
+            })
+        })
+        .collect::<Vec<_>>();
+
+    flex((
+        flex((
+            text_input(palette.input, |state: &mut EditorState, input| {
+                state.set_command_palette_input(input)
+            })
+            .on_enter(|state: &mut EditorState, _| state.run_first_palette_item())
+            .flex(1.0),
+            button("×", |state: &mut EditorState| {
+                state.close_command_palette()
+            }),
+        ))
+        .direction(Axis::Horizontal),
+        portal(flex(rows)),
+    ))
+}
+
+/// Finds a workspace file by its path, opening one when clicked or the best
+/// match when Enter is pressed.
+fn quick_open_panel(
+    quick_open: QuickOpen,
+    items: Arc<Vec<QuickOpenItem>>,
+    indexing: bool,
+    config: Arc<EditorConfig>,
+) -> impl WidgetView<EditorState> + use<> {
+    let rows = items
+        .iter()
+        .map(|item| {
+            let name = item
+                .path
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            flex((
+                button(name, {
+                    let path = item.path.clone();
+                    move |state: &mut EditorState| state.open_quick_open_item(path.clone())
+                }),
+                rich_label(item.label.clone(), config.clone()),
+            ))
+            .direction(Axis::Horizontal)
+        })
+        .collect::<Vec<_>>();
+
+    flex((
+        flex((
+            text_input(quick_open.input, |state: &mut EditorState, input| {
+                state.set_quick_open_input(input)
+            })
+            .on_enter(|state: &mut EditorState, _| state.open_first_quick_open_item())
+            .flex(1.0),
+            button("×", |state: &mut EditorState| state.close_quick_open()),
+        ))
+        .direction(Axis::Horizontal),
+        indexing.then(|| label("Indexing files…")),
+        portal(flex(rows)),
+    ))
+}
+
+/// Asks for the new name of the symbol being renamed.
+fn rename_bar(rename: RenameInput) -> impl WidgetView<EditorState> + use<> {
+    flex((
+        label("Rename to:"),
+        text_input(rename.name, |state: &mut EditorState, name| {
+            state.set_rename_input(name)
+        }),
+        button("OK", |state: &mut EditorState| state.submit_rename()),
+        button("Cancel", |state: &mut EditorState| state.cancel_rename()),
+    ))
+    .direction(Axis::Horizontal)
+}
+
+/// Asks for the path to save the focused document at.
+fn save_as_bar(path: String) -> impl WidgetView<EditorState> + use<> {
+    flex((
+        label("Save as:"),
+        text_input(path, |state: &mut EditorState, path| {
+            state.set_save_as_input(path)
+        })
+        .on_enter(|state: &mut EditorState, _| state.submit_save_as()),
+        button("Save", |state: &mut EditorState| state.submit_save_as()),
+        button("Cancel", |state: &mut EditorState| state.cancel_save_as()),
+    ))
+    .direction(Axis::Horizontal)
+}
+
+/// The label of a button toggling a search mode, in brackets when the mode is on.
+fn toggle_label(name: &str, on: bool) -> String {
+    if on {
+        format!("[{name}]")
+    } else {
+        name.to_string()
+    }
+}
+
+/// Searches the focused document, and replaces the matches when the replace row is shown.
+fn find_bar(find: FindBar, summary: String) -> impl WidgetView<EditorState> + use<> {
+    flex((
+        flex((
+            label("Find:"),
+            text_input(find.query, |state: &mut EditorState, query| {
+                state.set_find_query(query)
+            })
+            .on_enter(|state: &mut EditorState, _| state.find_next()),
+            button(
+                toggle_label("Aa", find.case_sensitive),
+                |state: &mut EditorState| state.toggle_find_case_sensitive(),
+            ),
+            button(
+                toggle_label("ab", find.whole_word),
+                |state: &mut EditorState| state.toggle_find_whole_word(),
+            ),
+            button(toggle_label(".*", find.regex), |state: &mut EditorState| {
+                state.toggle_find_regex()
+            }),
+            label(summary),
+            button("↑", |state: &mut EditorState| state.find_previous()),
+            button("↓", |state: &mut EditorState| state.find_next()),
+            button("×", |state: &mut EditorState| state.close_find()),
+        ))
+        .direction(Axis::Horizontal),
+        find.replace_visible.then(|| {
+            flex((
+                label("Replace:"),
+                text_input(find.replacement, |state: &mut EditorState, replacement| {
+                    state.set_find_replacement(replacement)
+                })
+                .on_enter(|state: &mut EditorState, _| state.replace_one()),
+                button("Replace", |state: &mut EditorState| state.replace_one()),
+                button("Replace all", |state: &mut EditorState| state.replace_all()),
+            ))
+            .direction(Axis::Horizontal)
+        }),
+        find.error.map(label),
+    ))
+}
+
+/// Offers to reload or overwrite a document whose file changed on disk.
+fn disk_change_bar(path: PathBuf, change: DiskChange) -> impl WidgetView<EditorState> + use<> {
+    let (message, can_reload) = match change {
+        DiskChange::Modified(_) => (format!("{} changed on disk.", path.display()), true),
+        DiskChange::Deleted => (format!("{} was deleted on disk.", path.display()), false),
+    };
+
+    flex((
+        label(message),
+        can_reload.then(|| {
+            button("Reload", {
+                let path = path.clone();
+                move |state: &mut EditorState| state.reload_disk_change(path.clone())
+            })
+        }),
+        button("Overwrite", move |state: &mut EditorState| {
+            state.overwrite_disk_change(path.clone())
+        }),
+    ))
+    .direction(Axis::Horizontal)
 }
 
 struct EditorPortal {
-    state: EditorState,
+    view: PaneView,
     y_to_line_mapping: Vec<(f64, f64, Line)>,
 }
 
 impl EditorPortal {
-    fn new(state: EditorState) -> Self {
+    fn new(view: PaneView) -> Self {
         Self {
-            state,
+            view,
             y_to_line_mapping: Vec::new(),
         }
     }
 }
 
+impl EditorPortal {
+    /// The `(line, column)` of the character painted at `position`, if it is on a line.
+    fn text_position_at(
+        &self,
+        ctx: &masonry::core::EventCtx<'_>,
+        position: LogicalPosition<f64>,
+        scroll_delta: (f64, f64),
+    ) -> Option<(usize, usize)> {
+        let (x, line) = self.line_at(ctx, position, scroll_delta)?;
+        Some((
+            line.line.line_number,
+            line.get_clicked_character_index(x).unwrap_or(0),
+        ))
+    }
+
+    /// The `(line, column)` of the character under `position`, `None` past the end of its line.
+    fn character_at(
+        &self,
+        ctx: &masonry::core::EventCtx<'_>,
+        position: LogicalPosition<f64>,
+        scroll_delta: (f64, f64),
+    ) -> Option<(usize, usize)> {
+        let (x, line) = self.line_at(ctx, position, scroll_delta)?;
+        Some((line.line.line_number, line.get_clicked_character_index(x)?))
+    }
+
+    /// The line painted at `position`, and the x of `position` from the start of the text.
+    fn line_at(
+        &self,
+        ctx: &masonry::core::EventCtx<'_>,
+        position: LogicalPosition<f64>,
+        scroll_delta: (f64, f64),
+    ) -> Option<(f32, &Line)> {
+        let x = position.x - ctx.paint_rect().x0 - scroll_delta.0 - self.gutter_width();
+        let y = position.y - ctx.paint_rect().y0 - scroll_delta.1;
+
+        self.y_to_line_mapping
+            .iter()
+            .find(|(y_min, y_max, _)| *y_min <= y && y <= *y_max)
+            .map(|(_, _, line)| (x as f32, line))
+    }
+
+    /// The width of the gutter of the focused document.
+    fn gutter_width(&self) -> f64 {
+        let line_count = self
+            .view
+            .document
+            .as_ref()
+            .map_or(0, |document| document.count_lines());
+        Gutter::new(&self.view.config, line_count).width()
+    }
+
+    /// Whether `position` is over the gutter, which does not scroll horizontally.
+    fn is_in_gutter(
+        &self,
+        ctx: &masonry::core::EventCtx<'_>,
+        position: LogicalPosition<f64>,
+    ) -> bool {
+        position.x - ctx.paint_rect().x0 < self.gutter_width()
+    }
+
+    /// The line painted at the height of `position`, the first or last painted
+    /// one when `position` is above or below them, e.g. while dragging.
+    fn line_number_at(
+        &self,
+        ctx: &masonry::core::EventCtx<'_>,
+        position: LogicalPosition<f64>,
+        scroll_delta: (f64, f64),
+    ) -> Option<usize> {
+        let y = position.y - ctx.paint_rect().y0 - scroll_delta.1;
+        let (first, last) = (
+            self.y_to_line_mapping.first()?,
+            self.y_to_line_mapping.last()?,
+        );
+        let line = if y < first.0 {
+            &first.2
+        } else if y > last.1 {
+            &last.2
+        } else {
+            &self
+                .y_to_line_mapping
+                .iter()
+                .find(|(y_min, y_max, _)| *y_min <= y && y <= *y_max)?
+                .2
+        };
+        Some(line.line.line_number)
+    }
+}
+
 impl Widget for EditorPortal {
     fn layout(
         &mut self,
@@ -77,23 +789,20 @@ impl Widget for EditorPortal {
         props: &masonry::core::PropertiesRef<'_>,
         scene: &mut masonry::vello::Scene,
     ) {
-        let document = self.state.get_focused_document();
-
-        if document.is_none() {
+        let view = &self.view;
+        let Some(document) = &view.document else {
             tracing::debug!("No focused document to paint");
             return;
-        }
-        let mut document = document.unwrap();
+        };
 
         let size = ctx.size();
+        view.set_viewport_height(size.height);
 
         let background_rect = Rect::new(0.0, 0.0, size.width, size.height);
-        let background_color = match &self.state.config.theme {
-            theme::Theme::Vscode(vscode_theme) => vscode_theme
-                .get_style(vec!["editor.background"])
-                .and_then(|s| s.color),
-        }
-        .unwrap_or(Color::BLACK);
+        let background_color = view
+            .config
+            .theme_color(&["editor.background"])
+            .unwrap_or(Color::BLACK);
         scene.fill(
             masonry::peniko::Fill::EvenOdd,
             Affine::IDENTITY,
@@ -102,10 +811,7 @@ impl Widget for EditorPortal {
             &background_rect,
         );
 
-        let scroll_delta = self
-            .state
-            .get_document_scroll(&document.path)
-            .unwrap_or((0.0, 0.0));
+        let scroll_delta = view.scroll;
 
         let viewport = Rect::new(
             -scroll_delta.0,
@@ -114,29 +820,119 @@ impl Widget for EditorPortal {
             size.height - scroll_delta.1,
         );
 
-        document.update_styles_with_syntax();
         self.y_to_line_mapping.clear();
 
         let lines = document.get_visible_lines(viewport);
-        let cursors = self
-            .state
-            .get_document_cursors(&document.path)
-            .unwrap_or_else(Vec::new);
+        let cursors = &view.cursors;
+        let diagnostics = &view.diagnostics;
 
-        let config = self.state.config.clone();
+        let config = view.config.clone();
         let lines = lines
             .into_iter()
-            .map(|line| Line::new(&config, &line, &document, ctx, cursors.clone()))
+            .map(|line| Line::new(&config, &line, document, ctx, cursors.clone(), diagnostics))
             .collect::<Vec<_>>();
 
+        // The text is painted right of the gutter.
+        let gutter = Gutter::new(&config, document.count_lines());
+        let text_delta = (scroll_delta.0 + gutter.width(), scroll_delta.1);
+
+        paint_find_matches(scene, &config, text_delta, &lines, &view.find_matches);
+        paint_selections(scene, &config, text_delta, &lines, cursors);
+
         for cursor in cursors {
-            cursor.paint(scene, scroll_delta, &lines);
+            cursor.paint(scene, text_delta, &lines);
         }
 
         for line in &lines {
-            let (y_min, y_max) = line.paint(scene, scroll_delta);
+            let (y_min, y_max) = line.paint(scene, text_delta);
             self.y_to_line_mapping.push((y_min, y_max, line.clone()));
         }
+
+        let cursor_lines = cursors.iter().map(|cursor| cursor.line).collect::<Vec<_>>();
+        gutter.paint(
+            ctx,
+            scene,
+            scroll_delta.1,
+            &self.y_to_line_mapping,
+            &cursor_lines,
+            &diagnostic_markers(&config, diagnostics),
+        );
+
+        let line_height = config.real_line_height() as f64;
+        if let Some((items, selected)) = &view.completion
+            && let Some(cursor) = cursors.first()
+            && let Some(line) = lines.iter().find(|l| l.line.line_number == cursor.line)
+        {
+            let (x0, x1) = line
+                .get_x_range_for_index(cursor.column)
+                .unwrap_or_else(|| {
+                    let x = line.get_x_for_index(cursor.column);
+                    (x, x + line.get_character_width())
+                });
+            let y = cursor.line as f64 * line_height + text_delta.1;
+            paint_popup(
+                ctx,
+                scene,
+                &config,
+                Rect::new(
+                    x0 as f64 + text_delta.0,
+                    y,
+                    x1 as f64 + text_delta.0,
+                    y + line_height,
+                ),
+                size,
+                items,
+                *selected,
+            );
+        } else {
+            // The hover information opened by a key shows alone at the cursor,
+            // the one of the pointer below the diagnostics it is on.
+            let hover = view.hover.as_ref();
+            let content = hover.and_then(|hover| hover.content.as_deref().cloned());
+            let (position, text) = match hover {
+                Some(hover) if !hover.from_pointer => {
+                    (Some(hover.position), content.unwrap_or_default())
+                }
+                _ => {
+                    let mut text = RichText::plain(view.hovered_diagnostics.join("\n"));
+                    text.append(content.unwrap_or_default());
+                    (view.hover_position, text)
+                }
+            };
+            if let Some((line_number, column)) = position
+                && let Some(line) = lines.iter().find(|l| l.line.line_number == line_number)
+            {
+                let x = line.get_x_for_index(column) as f64 + text_delta.0;
+                let y = line_number as f64 * line_height + text_delta.1;
+                paint_rich_popup(
+                    ctx,
+                    scene,
+                    &config,
+                    Rect::new(x, y, x, y + line_height),
+                    Placement::Below,
+                    size,
+                    &text,
+                );
+            }
+        }
+
+        // The signature help goes above the cursor, out of the way of the completions.
+        if let Some(content) = &view.signature_help
+            && let Some(cursor) = cursors.first()
+            && let Some(line) = lines.iter().find(|l| l.line.line_number == cursor.line)
+        {
+            let x = line.get_x_for_index(cursor.column) as f64 + text_delta.0;
+            let y = cursor.line as f64 * line_height + text_delta.1;
+            paint_rich_popup(
+                ctx,
+                scene,
+                &config,
+                Rect::new(x, y, x, y + line_height),
+                Placement::Above,
+                size,
+                content,
+            );
+        }
     }
 
     fn accessibility_role(&self) -> masonry::accesskit::Role {
@@ -174,7 +970,7 @@ impl Widget for EditorPortal {
                 state,
             } => {
                 if let ScrollDelta::PixelDelta(delta) = delta {
-                    if let Some(focused_document) = self.state.get_focused_document() {
+                    if let Some(focused_document) = self.view.document.clone() {
                         ctx.submit_action(EditorAction::Scroll {
                             delta: (delta.x, delta.y),
                             document: focused_document,
@@ -182,6 +978,37 @@ impl Widget for EditorPortal {
                     }
                 }
             }
+            masonry::core::PointerEvent::Move(update) => {
+                let Some(focused_document) = self.view.document.clone() else {
+                    return;
+                };
+                let scroll_delta = self.view.scroll;
+                let position: LogicalPosition<f64> =
+                    update.current.position.to_logical(ctx.get_scale_factor());
+
+                if let Some(anchor_line) = self.view.line_selection_anchor {
+                    if let Some(line) = self.line_number_at(ctx, position, scroll_delta) {
+                        ctx.submit_action(EditorAction::SelectLines {
+                            document: focused_document,
+                            anchor_line,
+                            line,
+                        });
+                    }
+                    return;
+                }
+
+                let hover_position = self.character_at(ctx, position, scroll_delta);
+                if hover_position != self.view.hover_position {
+                    ctx.submit_action(EditorAction::Hover {
+                        position: hover_position,
+                    });
+                }
+            }
+            masonry::core::PointerEvent::Leave(_) => {
+                if self.view.hover_position.is_some() {
+                    ctx.submit_action(EditorAction::Hover { position: None });
+                }
+            }
             masonry::core::PointerEvent::Down {
                 pointer,
                 button,
@@ -189,29 +1016,29 @@ impl Widget for EditorPortal {
             } => {
                 ctx.request_focus();
 
-                let focused_document = self.state.get_focused_document().unwrap();
-
-                let scroll_delta = self
-                    .state
-                    .get_document_scroll(&focused_document.path)
-                    .unwrap_or((0.0, 0.0));
+                let Some(focused_document) = self.view.document.clone() else {
+                    return;
+                };
+                let scroll_delta = self.view.scroll;
 
                 let position: LogicalPosition<f64> =
                     state.position.to_logical(ctx.get_scale_factor());
 
-                let x = position.x - ctx.paint_rect().x0 - scroll_delta.0;
-                let y = position.y - ctx.paint_rect().y0 - scroll_delta.1;
+                // Clicking the gutter selects the whole line, dragging extends to more lines.
+                if self.is_in_gutter(ctx, position) {
+                    if let Some(line) = self.line_number_at(ctx, position, scroll_delta) {
+                        ctx.capture_pointer();
+                        ctx.submit_action(EditorAction::SelectLines {
+                            document: focused_document,
+                            anchor_line: line,
+                            line,
+                        });
+                    }
+                    return;
+                }
 
                 let (line_number, char_index) = self
-                    .y_to_line_mapping
-                    .iter()
-                    .find(|(y_min, y_max, _)| *y_min <= y && y <= *y_max)
-                    .map(|(_, _, line)| {
-                        (
-                            line.line.line_number,
-                            line.get_clicked_character_index(x as f32).unwrap_or(0),
-                        )
-                    })
+                    .text_position_at(ctx, position, scroll_delta)
                     .unwrap_or((0, 0));
 
                 ctx.submit_action(EditorAction::ClearCursors {
@@ -222,6 +1049,11 @@ impl Widget for EditorPortal {
                     position: (line_number, char_index),
                 });
             }
+            masonry::core::PointerEvent::Up { .. } | masonry::core::PointerEvent::Cancel(_) => {
+                if self.view.line_selection_anchor.is_some() {
+                    ctx.submit_action(EditorAction::EndLineSelection);
+                }
+            }
             _ => {}
         }
     }
@@ -255,7 +1087,9 @@ impl Widget for EditorPortal {
     }
 }
 
-struct EditorView;
+struct EditorView {
+    pane: PaneId,
+}
 impl ViewMarker for EditorView {}
 impl View<EditorState, (), ViewCtx> for EditorView {
     type Element = Pod<EditorPortal>;
@@ -267,7 +1101,7 @@ impl View<EditorState, (), ViewCtx> for EditorView {
         app_state: &mut EditorState,
     ) -> (Self::Element, Self::ViewState) {
         (
-            ctx.with_action_widget(|_| (Pod::new(EditorPortal::new(app_state.clone())))),
+            ctx.with_action_widget(|_| Pod::new(EditorPortal::new(app_state.pane_view(self.pane)))),
             (),
         )
     }
@@ -280,7 +1114,7 @@ impl View<EditorState, (), ViewCtx> for EditorView {
         mut element: xilem::core::Mut<Self::Element>,
         app_state: &mut EditorState,
     ) {
-        *element.widget = EditorPortal::new(app_state.clone());
+        *element.widget = EditorPortal::new(app_state.pane_view(self.pane));
         element.ctx.request_render();
     }
 
@@ -304,21 +1138,43 @@ impl View<EditorState, (), ViewCtx> for EditorView {
         if let Ok(editor_action) = message.downcast::<EditorAction>() {
             match editor_action.as_ref() {
                 EditorAction::KeyPress(key, modifiers) => {
+                    app_state.focus_pane(self.pane);
                     app_state.press_key(key.clone(), modifiers.clone());
                     MessageResult::RequestRebuild
                 }
                 EditorAction::Scroll { delta, document } => {
-                    app_state.scroll_document(&document.path, (delta.0, delta.1));
+                    app_state.with_pane(self.pane, |state| {
+                        state.scroll_document(&document.path, (delta.0, delta.1))
+                    });
                     MessageResult::RequestRebuild
                 }
                 EditorAction::AddCursor { document, position } => {
+                    app_state.focus_pane(self.pane);
                     app_state.add_cursor(document.path.clone(), position);
                     MessageResult::RequestRebuild
                 }
                 EditorAction::ClearCursors { document } => {
+                    app_state.focus_pane(self.pane);
                     app_state.clear_cursors(document.path.clone());
                     MessageResult::RequestRebuild
                 }
+                EditorAction::Hover { position } => {
+                    app_state.set_hover_position(self.pane, *position);
+                    MessageResult::RequestRebuild
+                }
+                EditorAction::SelectLines {
+                    document,
+                    anchor_line,
+                    line,
+                } => {
+                    app_state.focus_pane(self.pane);
+                    app_state.select_lines(document.path.clone(), *anchor_line, *line);
+                    MessageResult::RequestRebuild
+                }
+                EditorAction::EndLineSelection => {
+                    app_state.end_line_selection();
+                    MessageResult::RequestRebuild
+                }
             }
         } else {
             MessageResult::Nop
@@ -340,4 +1196,14 @@ enum EditorAction {
     ClearCursors {
         document: Document,
     },
+    Hover {
+        position: Option<(usize, usize)>,
+    },
+    /// Selects the whole lines from `anchor_line` to `line`, clicking or dragging in the gutter.
+    SelectLines {
+        document: Document,
+        anchor_line: usize,
+        line: usize,
+    },
+    EndLineSelection,
 }
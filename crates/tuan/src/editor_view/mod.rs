@@ -1,9 +1,14 @@
+pub mod editor_config;
 mod editor_state;
 mod editor_view;
-pub mod editor_config;
 pub(super) mod paint;
+mod rich_label;
 
+pub use editor_config::EditorConfig;
 pub(crate) use editor_state::action::EditorAction;
-pub use editor_state::EditorState;
+pub use editor_state::{
+    CodeActionList, CommandPalette, DiagnosticSpan, EditorState, FindBar, LocationList,
+    LspResponse, PaneId, PaneLayout, PaneView, PendingEdit, Problem, ProjectSearch, QuickOpen,
+    QuickOpenItem, RenameInput, Tab,
+};
 pub use editor_view::editor_view;
-pub use editor_config::EditorConfig;
@@ -1,17 +1,17 @@
 use std::{
     collections::HashMap,
+    ops::Range,
     path::PathBuf,
     sync::{Arc, Mutex},
 };
 
 use masonry::kurbo::Rect;
-use tuan_core::buffer::rope_text::RopeText;
+use tuan_core::{buffer::rope_text::RopeText, selection::Selection};
 use xilem::{Affine, Color};
 
 use crate::{
     document,
     editor_view::{EditorConfig, paint::line},
-    theme::{self, theme::Theme},
 };
 
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
@@ -25,6 +25,8 @@ pub enum BlinkState {
 pub struct Cursor {
     pub line: usize,
     pub column: usize,
+    /// The `(line, column)` where the selection started, the cursor being its moving end.
+    pub anchor: Option<(usize, usize)>,
     pub blink_state: BlinkState,
     editor_config: Arc<EditorConfig>,
     documents: Arc<Mutex<HashMap<PathBuf, document::Document>>>,
@@ -65,12 +67,10 @@ impl Cursor {
             BlinkState::Off => Rect::new(0.0, 0.0, 0.0, 0.0),
         };
 
-        let cursor_color = match &self.editor_config.theme {
-            theme::Theme::Vscode(vscode_theme) => vscode_theme
-                .get_style(vec!["editorCursor.foreground"])
-                .and_then(|s| s.color),
-        }
-        .unwrap_or(Color::BLACK);
+        let cursor_color = self
+            .editor_config
+            .theme_color(&["editorCursor.foreground"])
+            .unwrap_or(Color::BLACK);
 
         scene.fill(
             masonry::peniko::Fill::EvenOdd,
@@ -95,6 +95,7 @@ impl Cursor {
         Self {
             line,
             column,
+            anchor: None,
             documents,
             focused_document_path,
             blink_state: BlinkState::On,
@@ -102,6 +103,15 @@ impl Cursor {
         }
     }
 
+    pub(crate) fn set_editor_config(&mut self, editor_config: Arc<EditorConfig>) {
+        self.editor_config = editor_config;
+    }
+
+    /// Follows the document to `path`, e.g. once it is saved there.
+    pub(crate) fn set_document_path(&mut self, path: PathBuf) {
+        self.focused_document_path = Some(path);
+    }
+
     fn set_blink_state(&mut self, state: BlinkState) {
         self.blink_state = state;
     }
@@ -168,8 +178,92 @@ impl Cursor {
 
 impl Cursor {
     pub fn get_cursor_offset(&self) -> usize {
+        self.get_offset_of((self.line, self.column))
+    }
+
+    fn get_offset_of(&self, (line, column): (usize, usize)) -> usize {
         self.get_document()
-            .map(|doc| doc.buffer.offset_of_line(self.line) + self.column)
+            .map(|doc| {
+                let (line, column) = doc.clamp_position((line, column));
+                doc.buffer.offset_of_line(line) + column
+            })
             .unwrap_or(0)
     }
 }
+
+impl Cursor {
+    /// Starts a selection at the current position if there is none, then lets `f` move the cursor.
+    pub fn extend_selection(&mut self, f: impl FnOnce(&mut Self)) {
+        if self.anchor.is_none() {
+            self.anchor = Some((self.line, self.column));
+        }
+        f(self);
+    }
+
+    pub fn clear_selection(&mut self) {
+        self.anchor = None;
+    }
+
+    pub fn select(&mut self, anchor: (usize, usize), head: (usize, usize)) {
+        self.anchor = Some(anchor);
+        (self.line, self.column) = head;
+        self.set_blink_state(BlinkState::Move);
+    }
+
+    /// The selection of this cursor, a caret when nothing is selected.
+    pub fn get_selection(&self) -> Selection {
+        let head = self.get_cursor_offset();
+        match self.anchor {
+            Some(anchor) => Selection::region(self.get_offset_of(anchor), head),
+            None => Selection::caret(head),
+        }
+    }
+
+    /// The selected range of offsets, unless the selection is empty.
+    pub fn get_selection_range(&self) -> Option<Range<usize>> {
+        let selection = self.get_selection();
+        let region = selection.regions().first()?;
+        (!region.is_caret()).then(|| region.min()..region.max())
+    }
+
+    /// Selects the word around the cursor.
+    pub fn select_word(&mut self) {
+        let Some(document) = self.get_document() else {
+            return;
+        };
+        let content = document.buffer.line_content(self.line);
+        let column = self.column.min(content.len());
+        if !content.is_char_boundary(column) {
+            return;
+        }
+
+        let is_word = |c: char| c.is_alphanumeric() || c == '_';
+        let start = content[..column]
+            .char_indices()
+            .rev()
+            .take_while(|(_, c)| is_word(*c))
+            .last()
+            .map(|(i, _)| i)
+            .unwrap_or(column);
+        let end = content[column..]
+            .char_indices()
+            .find(|(_, c)| !is_word(*c))
+            .map(|(i, _)| column + i)
+            .unwrap_or(content.len());
+
+        self.select((self.line, start), (self.line, end));
+    }
+
+    /// Selects the whole line of the cursor, including its line break.
+    pub fn select_line(&mut self) {
+        let Some(document) = self.get_document() else {
+            return;
+        };
+        let head = if self.line + 1 < document.count_lines() {
+            (self.line + 1, 0)
+        } else {
+            (self.line, document.get_line_length(self.line))
+        };
+        self.select((self.line, 0), head);
+    }
+}
@@ -0,0 +1,45 @@
+use lsp_types::DiagnosticSeverity;
+use masonry::kurbo::{BezPath, Stroke};
+use xilem::{Affine, Color};
+
+use crate::editor_view::EditorConfig;
+
+/// The color of the squiggles and gutter markers of a diagnostic.
+pub(crate) fn severity_color(editor_config: &EditorConfig, severity: DiagnosticSeverity) -> Color {
+    let (scope, default) = match severity {
+        DiagnosticSeverity::WARNING => ("editorWarning.foreground", Color::from_rgb8(230, 180, 60)),
+        DiagnosticSeverity::INFORMATION => {
+            ("editorInfo.foreground", Color::from_rgb8(80, 160, 230))
+        }
+        DiagnosticSeverity::HINT => ("editorHint.foreground", Color::from_rgb8(140, 140, 140)),
+        _ => ("editorError.foreground", Color::from_rgb8(235, 80, 80)),
+    };
+
+    editor_config.theme_color(&[scope]).unwrap_or(default)
+}
+
+/// Paints a wavy underline from `x0` to `x1` at height `y`, in the coordinates of `transform`.
+pub(crate) fn paint_squiggle(
+    scene: &mut masonry::vello::Scene,
+    transform: Affine,
+    (x0, x1): (f64, f64),
+    y: f64,
+    color: Color,
+) {
+    const WAVE_WIDTH: f64 = 4.0;
+    const WAVE_HEIGHT: f64 = 1.5;
+
+    let mut path = BezPath::new();
+    path.move_to((x0, y));
+    let mut x = x0;
+    let mut up = true;
+    while x < x1 {
+        let next_x = (x + WAVE_WIDTH / 2.0).min(x1);
+        let next_y = if up { y - WAVE_HEIGHT } else { y };
+        path.line_to((next_x, next_y));
+        x = next_x;
+        up = !up;
+    }
+
+    scene.stroke(&Stroke::new(1.0), transform, color, None, &path);
+}
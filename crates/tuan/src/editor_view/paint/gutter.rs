@@ -0,0 +1,196 @@
+use masonry::kurbo::{Circle, Rect};
+use xilem::{Affine, Color};
+
+use crate::{
+    editor_view::{
+        DiagnosticSpan, EditorConfig,
+        paint::{diagnostics::severity_color, line, popup::rich_text_layout},
+    },
+    markdown::RichText,
+    settings::LineNumbers,
+};
+
+/// The space between the line numbers and the text.
+const NUMBERS_PADDING: f64 = 8.0;
+
+/// A column of the gutter, left of the line numbers, showing markers on some lines.
+///
+/// The slots are laid out in the order of [`MarkerSlot::ALL`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum MarkerSlot {
+    /// The most severe diagnostic starting on the line.
+    Diagnostics,
+}
+
+impl MarkerSlot {
+    const ALL: &[MarkerSlot] = &[MarkerSlot::Diagnostics];
+
+    fn index(self) -> usize {
+        Self::ALL.iter().position(|slot| *slot == self).unwrap_or(0)
+    }
+}
+
+/// A dot in a marker slot of the gutter.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct GutterMarker {
+    pub(crate) line: usize,
+    pub(crate) slot: MarkerSlot,
+    pub(crate) color: Color,
+}
+
+/// A marker on each line where a diagnostic starts, colored by its most severe one.
+pub(crate) fn diagnostic_markers(
+    editor_config: &EditorConfig,
+    diagnostics: &[DiagnosticSpan],
+) -> Vec<GutterMarker> {
+    let mut markers: Vec<GutterMarker> = Vec::new();
+    let mut spans = diagnostics.iter().collect::<Vec<_>>();
+    spans.sort_by_key(|span| (span.line, span.severity));
+    for span in spans {
+        if markers
+            .last()
+            .is_some_and(|marker| marker.line == span.line)
+        {
+            continue;
+        }
+        markers.push(GutterMarker {
+            line: span.line,
+            slot: MarkerSlot::Diagnostics,
+            color: severity_color(editor_config, span.severity),
+        });
+    }
+    markers
+}
+
+/// The gutter on the left of the text: the marker slots, then the line numbers.
+///
+/// It scrolls vertically with the text, but stays in place when scrolling horizontally.
+pub(crate) struct Gutter<'a> {
+    editor_config: &'a EditorConfig,
+    /// The number of lines of the document, deciding how wide the numbers are.
+    line_count: usize,
+}
+
+impl<'a> Gutter<'a> {
+    pub(crate) fn new(editor_config: &'a EditorConfig, line_count: usize) -> Self {
+        Self {
+            editor_config,
+            line_count,
+        }
+    }
+
+    fn slot_width(&self) -> f64 {
+        self.editor_config.real_line_height() as f64
+    }
+
+    fn numbers_width(&self) -> f64 {
+        if self.editor_config.line_numbers == LineNumbers::Off {
+            return 0.0;
+        }
+        // At least two digits, so that the text does not shift as the first lines are typed.
+        let digits = self.line_count.max(1).to_string().len().max(2);
+        // A monospace digit is about 0.6em wide.
+        digits as f64 * self.editor_config.font_size as f64 * 0.6 + NUMBERS_PADDING
+    }
+
+    /// The width of the gutter, where the text starts.
+    pub(crate) fn width(&self) -> f64 {
+        MarkerSlot::ALL.len() as f64 * self.slot_width() + self.numbers_width()
+    }
+
+    /// The number shown for `line`: its distance to the line of the first
+    /// cursor with relative numbers, except on that line.
+    fn number(&self, line: usize, cursor_line: Option<usize>) -> usize {
+        match (self.editor_config.line_numbers, cursor_line) {
+            (LineNumbers::Relative, Some(cursor_line)) if cursor_line != line => {
+                line.abs_diff(cursor_line)
+            }
+            _ => line + 1,
+        }
+    }
+
+    /// Paints the gutter over the left of the editor, for the `lines` painted
+    /// at the y of their `(y_min, y_max, line)`, before scrolling.
+    ///
+    /// The numbers of the lines holding one of `cursor_lines` stand out.
+    pub(crate) fn paint(
+        &self,
+        ctx: &mut masonry::core::PaintCtx<'_>,
+        scene: &mut masonry::vello::Scene,
+        scroll_y: f64,
+        lines: &[(f64, f64, line::Line)],
+        cursor_lines: &[usize],
+        markers: &[GutterMarker],
+    ) {
+        let config = self.editor_config;
+        let width = self.width();
+        let background_color = config
+            .theme_color(&["editorGutter.background", "editor.background"])
+            .unwrap_or(Color::BLACK);
+        scene.fill(
+            masonry::peniko::Fill::EvenOdd,
+            Affine::IDENTITY,
+            background_color,
+            None,
+            &Rect::new(0.0, 0.0, width, ctx.size().height),
+        );
+
+        let line_height = config.real_line_height() as f64;
+        let slot_width = self.slot_width();
+        for marker in markers {
+            if !lines
+                .iter()
+                .any(|(_, _, line)| line.line.line_number == marker.line)
+            {
+                continue;
+            }
+            let center = (
+                (marker.slot.index() as f64 + 0.5) * slot_width,
+                marker.line as f64 * line_height + line_height / 2.0 + scroll_y,
+            );
+            scene.fill(
+                masonry::peniko::Fill::NonZero,
+                Affine::IDENTITY,
+                marker.color,
+                None,
+                &Circle::new(center, line_height / 5.0),
+            );
+        }
+
+        if config.line_numbers == LineNumbers::Off {
+            return;
+        }
+        let foreground = config
+            .theme_color(&["editorLineNumber.foreground"])
+            .unwrap_or(Color::from_rgba8(0x85, 0x85, 0x85, 0xff));
+        let active_foreground = config
+            .theme_color(&["editorLineNumber.activeForeground", "editor.foreground"])
+            .unwrap_or(Color::WHITE);
+        let numbers_end = width - NUMBERS_PADDING;
+        for (y_min, _, line) in lines {
+            let line_number = line.line.line_number;
+            let active = cursor_lines.contains(&line_number);
+            let number = self.number(line_number, cursor_lines.first().copied());
+            let (layout, brushes) = rich_text_layout(
+                ctx,
+                config,
+                &RichText::plain(number.to_string()),
+                if active {
+                    active_foreground
+                } else {
+                    foreground
+                },
+                None,
+            );
+            // Right-aligned, on the baseline of the text of the line.
+            let x = numbers_end - layout.width() as f64;
+            masonry::core::render_text(
+                scene,
+                Affine::translate((x, y_min + scroll_y)),
+                &layout,
+                &brushes,
+                true, // hinting
+            );
+        }
+    }
+}
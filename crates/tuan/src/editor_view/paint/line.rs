@@ -1,7 +1,6 @@
 use crate::{
     document,
-    editor_view::EditorConfig,
-    theme::{self, theme::Theme as _},
+    editor_view::{DiagnosticSpan, EditorConfig},
 };
 use masonry::{
     TextAlignOptions,
@@ -13,7 +12,10 @@ use masonry::{
 };
 use xilem::{Affine, Color, FontWeight, TextAlign};
 
-use super::cursor;
+use super::{
+    cursor,
+    diagnostics::{paint_squiggle, severity_color},
+};
 
 #[derive(Clone)]
 pub(crate) struct Line {
@@ -23,6 +25,8 @@ pub(crate) struct Line {
     text_layout: masonry::parley::Layout<BrushIndex>,
     brushes: Vec<Brush>,
     baseline: f32,
+    /// The character ranges of the line underlined by diagnostics, with their color.
+    squiggles: Vec<(std::ops::Range<usize>, Color)>,
     pub(crate) line: document::line::Line,
 }
 
@@ -53,6 +57,17 @@ impl Line {
             true, // hinting
         );
 
+        for (range, color) in &self.squiggles {
+            let x0 = self.get_x_for_index(range.start) as f64;
+            let x1 = if range.is_empty() {
+                x0 + self.get_character_width() as f64
+            } else {
+                self.get_x_for_index(range.end) as f64
+            };
+            let y = self.baseline as f64 + 2.0;
+            paint_squiggle(scene, transform, (x0, x1), y, *color);
+        }
+
         return (y_min, y_max);
     }
 }
@@ -64,10 +79,29 @@ impl Line {
         document: &document::Document,
         paint_ctx: &mut masonry::core::PaintCtx<'_>,
         cursors: Vec<cursor::Cursor>,
+        diagnostics: &[DiagnosticSpan],
     ) -> Self {
         let (text_layout, brushes, x_to_character_index_mapping, max_baseline) =
             Self::get_text_layout(&config, line, document, paint_ctx, cursors);
 
+        let squiggles = diagnostics
+            .iter()
+            .filter(|span| {
+                span.range.start <= line.end
+                    && (span.range.end > line.start || span.range.start >= line.start)
+            })
+            .map(|span| {
+                let start = span.range.start.max(line.start) - line.start;
+                let end = span
+                    .range
+                    .end
+                    .min(line.end)
+                    .max(span.range.start.max(line.start))
+                    - line.start;
+                (start..end, severity_color(config, span.severity))
+            })
+            .collect();
+
         Self {
             editor_config: config.clone(),
             x_to_character_index_mapping,
@@ -75,6 +109,7 @@ impl Line {
             brushes,
             line: line.clone(),
             baseline: max_baseline,
+            squiggles,
         }
     }
 
@@ -132,11 +167,7 @@ impl Line {
             }
         }
 
-        let on_cursor_color = match &editor_config.theme {
-            theme::Theme::Vscode(vscode_theme) => vscode_theme
-                .get_style(vec!["editorCursor.background"])
-                .and_then(|s| s.color),
-        };
+        let on_cursor_color = editor_config.theme_color(&["editorCursor.background"]);
         brushes.push(on_cursor_color.unwrap_or_else(|| Color::BLACK).into());
         let on_cursor_brush_index = BrushIndex(brushes.len() - 1);
         for cursor in cursors {
@@ -199,4 +230,24 @@ impl Line {
             .get(index)
             .map(|(start, end, _)| (*start, *end))
     }
+
+    /// The x of the left edge of the character at `index`, or of the end of the line past it.
+    pub fn get_x_for_index(&self, index: usize) -> f32 {
+        match self.x_to_character_index_mapping.get(index) {
+            Some((start, _, _)) => *start,
+            None => self
+                .x_to_character_index_mapping
+                .last()
+                .map(|(_, end, _)| *end)
+                .unwrap_or(0.0),
+        }
+    }
+
+    /// The width of a character, used for painting past the end of the line.
+    pub fn get_character_width(&self) -> f32 {
+        self.x_to_character_index_mapping
+            .first()
+            .map(|(start, end, _)| end - start)
+            .unwrap_or(self.editor_config.font_size / 2.0)
+    }
 }
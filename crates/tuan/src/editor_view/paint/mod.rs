@@ -1,2 +1,6 @@
 pub(crate) mod cursor;
-pub(crate) mod line;
\ No newline at end of file
+pub(crate) mod diagnostics;
+pub(crate) mod gutter;
+pub(crate) mod line;
+pub(crate) mod popup;
+pub(crate) mod selection;
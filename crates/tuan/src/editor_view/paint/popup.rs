@@ -0,0 +1,207 @@
+use masonry::{
+    TextAlignOptions,
+    core::BrushIndex,
+    kurbo::{Rect, RoundedRect, Size, Stroke},
+    parley::{FontFamily, FontStack, FontStyle, GenericFamily, Layout, StyleProperty},
+    peniko::{Brush, Mix},
+};
+use xilem::{Affine, Color, FontWeight, TextAlign};
+
+use crate::{editor_view::EditorConfig, markdown::RichText};
+
+const PADDING: f64 = 4.0;
+
+/// Which side of its anchor a popup goes to, when there is room for it there.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Placement {
+    Below,
+    Above,
+}
+
+/// Paints `lines` in a bordered box below `anchor`, or above it when there
+/// is no room below, kept within `bounds`. Line `highlighted` is drawn with
+/// the selection background, for lists.
+pub(crate) fn paint_popup(
+    ctx: &mut masonry::core::PaintCtx<'_>,
+    scene: &mut masonry::vello::Scene,
+    editor_config: &EditorConfig,
+    anchor: Rect,
+    bounds: Size,
+    lines: &[String],
+    highlighted: Option<usize>,
+) {
+    let text = RichText::plain(lines.join("\n"));
+    paint_text_popup(
+        ctx,
+        scene,
+        editor_config,
+        anchor,
+        Placement::Below,
+        bounds,
+        &text,
+        highlighted,
+    );
+}
+
+/// Paints styled `text`, e.g. rendered markdown, in a bordered box on the
+/// `placement` side of `anchor`, or on the other side when there is no room,
+/// kept within `bounds`.
+pub(crate) fn paint_rich_popup(
+    ctx: &mut masonry::core::PaintCtx<'_>,
+    scene: &mut masonry::vello::Scene,
+    editor_config: &EditorConfig,
+    anchor: Rect,
+    placement: Placement,
+    bounds: Size,
+    text: &RichText,
+) {
+    paint_text_popup(
+        ctx,
+        scene,
+        editor_config,
+        anchor,
+        placement,
+        bounds,
+        text,
+        None,
+    );
+}
+
+/// Lays out styled `text` in the editor font, wrapped at `max_width`, and
+/// the brushes of its colors, `foreground` being that of unstyled text.
+pub(crate) fn rich_text_layout(
+    ctx: &mut masonry::core::PaintCtx<'_>,
+    editor_config: &EditorConfig,
+    text: &RichText,
+    foreground: Color,
+    max_width: Option<f32>,
+) -> (Layout<BrushIndex>, Vec<Brush>) {
+    let mut brushes: Vec<Brush> = vec![foreground.into()];
+    let (fcx, lcx) = ctx.text_contexts();
+    let mut builder = lcx.ranged_builder(fcx, &text.text, 1.0, true);
+    builder.push_default(StyleProperty::FontStack(FontStack::Single(
+        FontFamily::Generic(GenericFamily::Monospace),
+    )));
+    builder.push_default(StyleProperty::FontSize(editor_config.font_size));
+    builder.push_default(StyleProperty::Brush(BrushIndex(0)));
+    for range_style in &text.styles {
+        let range = range_style.start..range_style.end;
+        let style = &range_style.style;
+        if style.italic {
+            builder.push(StyleProperty::FontStyle(FontStyle::Italic), range.clone());
+        }
+        if style.bold {
+            builder.push(StyleProperty::FontWeight(FontWeight::BOLD), range.clone());
+        }
+        if style.underline {
+            builder.push(StyleProperty::Underline(true), range.clone());
+        }
+        if style.strikethrough {
+            builder.push(StyleProperty::Strikethrough(true), range.clone());
+        }
+        if let Some(color) = style.foreground.or(style.color) {
+            brushes.push(color.into());
+            builder.push(
+                StyleProperty::Brush(BrushIndex(brushes.len() - 1)),
+                range.clone(),
+            );
+        }
+    }
+    let mut layout = builder.build(&text.text);
+    layout.break_all_lines(max_width);
+    layout.align(None, TextAlign::Start, TextAlignOptions::default());
+    (layout, brushes)
+}
+
+#[allow(clippy::too_many_arguments)]
+fn paint_text_popup(
+    ctx: &mut masonry::core::PaintCtx<'_>,
+    scene: &mut masonry::vello::Scene,
+    editor_config: &EditorConfig,
+    anchor: Rect,
+    placement: Placement,
+    bounds: Size,
+    text: &RichText,
+    highlighted: Option<usize>,
+) {
+    if text.is_empty() {
+        return;
+    }
+
+    let foreground = editor_config
+        .theme_color(&["editorHoverWidget.foreground", "editor.foreground"])
+        .unwrap_or(Color::WHITE);
+    let background = editor_config
+        .theme_color(&["editorHoverWidget.background", "editor.background"])
+        .unwrap_or(Color::BLACK);
+    let border = editor_config
+        .theme_color(&["editorHoverWidget.border"])
+        .unwrap_or(foreground.with_alpha(0.3));
+    let highlight = editor_config
+        .theme_color(&["editor.selectionBackground"])
+        .unwrap_or(Color::from_rgba8(128, 128, 128, 96));
+
+    let (layout, brushes) = rich_text_layout(
+        ctx,
+        editor_config,
+        text,
+        foreground,
+        Some((bounds.width / 2.0).max(200.0) as f32),
+    );
+
+    let size = Size::new(
+        layout.width() as f64 + 2.0 * PADDING,
+        (layout.height() as f64 + 2.0 * PADDING).min(bounds.height),
+    );
+    let x = anchor.x0.min(bounds.width - size.width).max(0.0);
+    let fits_below = anchor.y1 + size.height <= bounds.height;
+    let fits_above = anchor.y0 >= size.height;
+    let y = match placement {
+        Placement::Below if fits_below || !fits_above => anchor.y1,
+        Placement::Above if !fits_above && fits_below => anchor.y1,
+        _ => anchor.y0 - size.height,
+    };
+    let rect = Rect::from_origin_size((x, y), size);
+
+    scene.fill(
+        masonry::peniko::Fill::NonZero,
+        Affine::IDENTITY,
+        background,
+        None,
+        &RoundedRect::from_rect(rect, 3.0),
+    );
+    scene.stroke(
+        &Stroke::new(1.0),
+        Affine::IDENTITY,
+        border,
+        None,
+        &RoundedRect::from_rect(rect, 3.0),
+    );
+
+    if let Some(line) = highlighted.and_then(|index| layout.lines().nth(index)) {
+        let metrics = line.metrics();
+        scene.fill(
+            masonry::peniko::Fill::NonZero,
+            Affine::IDENTITY,
+            highlight,
+            None,
+            &Rect::new(
+                rect.x0,
+                rect.y0 + PADDING + metrics.min_coord as f64,
+                rect.x1,
+                rect.y0 + PADDING + metrics.max_coord as f64,
+            ),
+        );
+    }
+
+    // Text taller than the bounds is cut at the bottom of the box.
+    scene.push_layer(Mix::Clip, 1.0, Affine::IDENTITY, &rect);
+    masonry::core::render_text(
+        scene,
+        Affine::translate((rect.x0 + PADDING, rect.y0 + PADDING)),
+        &layout,
+        &brushes,
+        true, // hinting
+    );
+    scene.pop_layer();
+}
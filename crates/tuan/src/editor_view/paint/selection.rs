@@ -0,0 +1,110 @@
+use std::ops::Range;
+
+use masonry::kurbo::Rect;
+use xilem::{Affine, Color};
+
+use crate::editor_view::{EditorConfig, paint::cursor::Cursor, paint::line};
+
+/// Paints the selected ranges of `cursors` behind the visible `lines`.
+pub(crate) fn paint_selections(
+    scene: &mut masonry::vello::Scene,
+    editor_config: &EditorConfig,
+    scroll_delta: (f64, f64),
+    lines: &[line::Line],
+    cursors: &[Cursor],
+) {
+    let ranges = cursors
+        .iter()
+        .filter_map(Cursor::get_selection_range)
+        .collect::<Vec<_>>();
+    if ranges.is_empty() {
+        return;
+    }
+
+    let selection_color = editor_config
+        .theme_color(&["editor.selectionBackground"])
+        .unwrap_or(Color::from_rgba8(128, 128, 128, 96));
+
+    paint_ranges(
+        scene,
+        editor_config,
+        scroll_delta,
+        lines,
+        &ranges,
+        selection_color,
+    );
+}
+
+/// Paints the matches of the find bar, sorted by offset, behind the visible `lines`.
+pub(crate) fn paint_find_matches(
+    scene: &mut masonry::vello::Scene,
+    editor_config: &EditorConfig,
+    scroll_delta: (f64, f64),
+    lines: &[line::Line],
+    matches: &[Range<usize>],
+) {
+    let (Some(first_line), Some(last_line)) = (lines.first(), lines.last()) else {
+        return;
+    };
+    let start = matches.partition_point(|range| range.end < first_line.line.start);
+    let end = matches.partition_point(|range| range.start <= last_line.line.end);
+    if start >= end {
+        return;
+    }
+
+    let match_color = editor_config
+        .theme_color(&["editor.findMatchHighlightBackground"])
+        .unwrap_or(Color::from_rgba8(234, 92, 0, 85));
+
+    paint_ranges(
+        scene,
+        editor_config,
+        scroll_delta,
+        lines,
+        &matches[start..end],
+        match_color,
+    );
+}
+
+/// Paints the background of `ranges` (in offsets) on the visible `lines`.
+fn paint_ranges(
+    scene: &mut masonry::vello::Scene,
+    editor_config: &EditorConfig,
+    scroll_delta: (f64, f64),
+    lines: &[line::Line],
+    ranges: &[Range<usize>],
+    color: Color,
+) {
+    let line_height = editor_config.real_line_height() as f64;
+
+    for line in lines {
+        let (line_start, line_end) = (line.line.start, line.line.end);
+
+        for range in ranges {
+            if range.start > line_end || range.end < line_start || range.end == line_start {
+                continue;
+            }
+
+            let start_index = range.start.max(line_start) - line_start;
+            let end_index = range.end.min(line_end) - line_start;
+
+            let x0 = line.get_x_for_index(start_index) as f64;
+            let mut x1 = line.get_x_for_index(end_index) as f64;
+            // The selection continues on the next line: show the selected line break.
+            if range.end > line_end {
+                x1 += line.get_character_width() as f64;
+            }
+
+            let y = line.line.line_number as f64 * line_height + scroll_delta.1;
+            let rect = Rect::new(x0 + scroll_delta.0, y, x1 + scroll_delta.0, y + line_height);
+
+            scene.fill(
+                masonry::peniko::Fill::EvenOdd,
+                Affine::IDENTITY,
+                color,
+                None,
+                &rect,
+            );
+        }
+    }
+}
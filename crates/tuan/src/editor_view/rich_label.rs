@@ -0,0 +1,156 @@
+use std::sync::Arc;
+
+use masonry::{
+    accesskit::Role,
+    core::Widget,
+    kurbo::{Rect, Size},
+    peniko::Mix,
+};
+use xilem::{
+    Affine, Color, Pod, ViewCtx,
+    core::{MessageResult, View, ViewMarker},
+};
+
+use super::{EditorConfig, EditorState, paint::popup::rich_text_layout};
+use crate::markdown::RichText;
+
+/// A single line of styled text in the editor font, e.g. a highlighted line of code.
+pub(crate) fn rich_label(text: Arc<RichText>, config: Arc<EditorConfig>) -> RichLabel {
+    RichLabel { text, config }
+}
+
+pub(crate) struct RichLabel {
+    text: Arc<RichText>,
+    config: Arc<EditorConfig>,
+}
+
+pub(crate) struct RichLabelWidget {
+    text: Arc<RichText>,
+    config: Arc<EditorConfig>,
+}
+
+impl Widget for RichLabelWidget {
+    fn layout(
+        &mut self,
+        _ctx: &mut masonry::core::LayoutCtx<'_>,
+        _props: &mut masonry::core::PropertiesMut<'_>,
+        bc: &masonry::core::BoxConstraints,
+    ) -> Size {
+        // The text is laid out when painted: a monospace character is about 0.6em wide.
+        let characters = self.text.text.chars().count() as f64;
+        bc.constrain(Size::new(
+            characters * self.config.font_size as f64 * 0.6,
+            self.config.real_line_height() as f64,
+        ))
+    }
+
+    fn paint(
+        &mut self,
+        ctx: &mut masonry::core::PaintCtx<'_>,
+        _props: &masonry::core::PropertiesRef<'_>,
+        scene: &mut masonry::vello::Scene,
+    ) {
+        let foreground = self
+            .config
+            .theme_color(&["editor.foreground"])
+            .unwrap_or(Color::WHITE);
+        let (layout, brushes) = rich_text_layout(ctx, &self.config, &self.text, foreground, None);
+
+        let size = ctx.size();
+        let y = (size.height - layout.height() as f64) / 2.0;
+        scene.push_layer(
+            Mix::Clip,
+            1.0,
+            Affine::IDENTITY,
+            &Rect::from_origin_size((0.0, 0.0), size),
+        );
+        masonry::core::render_text(
+            scene,
+            Affine::translate((0.0, y)),
+            &layout,
+            &brushes,
+            true, // hinting
+        );
+        scene.pop_layer();
+    }
+
+    fn accessibility_role(&self) -> Role {
+        Role::Label
+    }
+
+    fn accessibility(
+        &mut self,
+        _ctx: &mut masonry::core::AccessCtx<'_>,
+        _props: &masonry::core::PropertiesRef<'_>,
+        node: &mut masonry::accesskit::Node,
+    ) {
+        node.set_value(self.text.text.clone());
+    }
+
+    fn register_children(&mut self, _ctx: &mut masonry::core::RegisterCtx<'_>) {}
+
+    fn children_ids(&self) -> masonry::core::ChildrenIds {
+        masonry::core::ChildrenIds::new()
+    }
+
+    fn get_debug_text(&self) -> Option<String> {
+        Some(self.text.text.clone())
+    }
+}
+
+impl ViewMarker for RichLabel {}
+impl View<EditorState, (), ViewCtx> for RichLabel {
+    type Element = Pod<RichLabelWidget>;
+    type ViewState = ();
+
+    fn build(
+        &self,
+        _ctx: &mut ViewCtx,
+        _app_state: &mut EditorState,
+    ) -> (Self::Element, Self::ViewState) {
+        (
+            Pod::new(RichLabelWidget {
+                text: self.text.clone(),
+                config: self.config.clone(),
+            }),
+            (),
+        )
+    }
+
+    fn rebuild(
+        &self,
+        prev: &Self,
+        _view_state: &mut Self::ViewState,
+        _ctx: &mut ViewCtx,
+        mut element: xilem::core::Mut<Self::Element>,
+        _app_state: &mut EditorState,
+    ) {
+        if Arc::ptr_eq(&self.text, &prev.text) && Arc::ptr_eq(&self.config, &prev.config) {
+            return;
+        }
+        element.widget.text = self.text.clone();
+        element.widget.config = self.config.clone();
+        element.ctx.request_layout();
+        element.ctx.request_render();
+    }
+
+    fn teardown(
+        &self,
+        _view_state: &mut Self::ViewState,
+        ctx: &mut ViewCtx,
+        element: xilem::core::Mut<'_, Self::Element>,
+        _app_state: &mut EditorState,
+    ) {
+        ctx.teardown_leaf(element);
+    }
+
+    fn message(
+        &self,
+        _view_state: &mut Self::ViewState,
+        _id_path: &[xilem::core::ViewId],
+        _message: xilem::core::DynMessage,
+        _app_state: &mut EditorState,
+    ) -> MessageResult<()> {
+        MessageResult::Nop
+    }
+}
@@ -0,0 +1,233 @@
+use std::{
+    collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+use ignore::{
+    Match,
+    gitignore::{Gitignore, GitignoreBuilder},
+};
+use tuan_rpc::proxy::{ProxyResponse, ProxyRpcHandler};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileEntry {
+    pub path: PathBuf,
+    pub is_dir: bool,
+}
+
+/// A visible line of the file tree.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Row {
+    pub path: PathBuf,
+    pub name: String,
+    pub is_dir: bool,
+    pub is_expanded: bool,
+    pub depth: usize,
+}
+
+/// What the name typed in the explorer is for.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InputKind {
+    NewFile { dir: PathBuf },
+    NewDirectory { dir: PathBuf },
+    Rename { path: PathBuf },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Input {
+    pub kind: InputKind,
+    pub name: String,
+}
+
+/// The file tree of the workspace.
+///
+/// Directories are listed lazily through the proxy, when first expanded,
+/// and entries ignored by a `.gitignore` are hidden.
+pub struct Explorer {
+    root: PathBuf,
+    /// The entries of each directory listed so far.
+    children: HashMap<PathBuf, Vec<FileEntry>>,
+    expanded: HashSet<PathBuf>,
+    /// The `.gitignore` of each listed directory, if it has one.
+    gitignores: HashMap<PathBuf, Gitignore>,
+    pub selected: Option<PathBuf>,
+    pub input: Option<Input>,
+}
+
+impl Explorer {
+    pub fn new(root: PathBuf) -> Self {
+        Self {
+            expanded: HashSet::from([root.clone()]),
+            root,
+            children: HashMap::new(),
+            gitignores: HashMap::new(),
+            selected: None,
+            input: None,
+        }
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    fn set_children(&mut self, dir: PathBuf, mut entries: Vec<FileEntry>) {
+        let gitignore_path = dir.join(".gitignore");
+        if gitignore_path.is_file() {
+            let mut builder = GitignoreBuilder::new(&dir);
+            if let Some(err) = builder.add(&gitignore_path) {
+                tracing::debug!("Failed to read {:?}: {err}", gitignore_path);
+            }
+            match builder.build() {
+                Ok(gitignore) => {
+                    self.gitignores.insert(dir.clone(), gitignore);
+                }
+                Err(err) => tracing::debug!("Invalid {:?}: {err}", gitignore_path),
+            }
+        } else {
+            self.gitignores.remove(&dir);
+        }
+
+        // Directories first, then by name.
+        entries.sort_by(|a, b| b.is_dir.cmp(&a.is_dir).then_with(|| a.path.cmp(&b.path)));
+        self.children.insert(dir, entries);
+    }
+
+    /// Whether `entry` is hidden, deciding with the nearest `.gitignore` having an opinion.
+    fn is_ignored(&self, entry: &FileEntry) -> bool {
+        if entry.path.file_name().is_some_and(|name| name == ".git") {
+            return true;
+        }
+        for dir in entry.path.ancestors().skip(1) {
+            if let Some(gitignore) = self.gitignores.get(dir) {
+                match gitignore.matched(&entry.path, entry.is_dir) {
+                    Match::Ignore(_) => return true,
+                    Match::Whitelist(_) => return false,
+                    Match::None => {}
+                }
+            }
+            if dir == self.root {
+                break;
+            }
+        }
+        false
+    }
+
+    /// The visible lines of the tree, in display order.
+    pub fn rows(&self) -> Vec<Row> {
+        let mut rows = Vec::new();
+        self.push_rows(&self.root, 0, &mut rows);
+        rows
+    }
+
+    fn push_rows(&self, dir: &Path, depth: usize, rows: &mut Vec<Row>) {
+        let Some(entries) = self.children.get(dir) else {
+            return;
+        };
+        for entry in entries.iter().filter(|entry| !self.is_ignored(entry)) {
+            let is_expanded = entry.is_dir && self.expanded.contains(&entry.path);
+            rows.push(Row {
+                path: entry.path.clone(),
+                name: entry
+                    .path
+                    .file_name()
+                    .map(|name| name.to_string_lossy().into_owned())
+                    .unwrap_or_default(),
+                is_dir: entry.is_dir,
+                is_expanded,
+                depth,
+            });
+            if is_expanded {
+                self.push_rows(&entry.path, depth + 1, rows);
+            }
+        }
+    }
+
+    pub fn is_dir(&self, path: &Path) -> bool {
+        path == self.root
+            || path.parent().is_some_and(|parent| {
+                self.children
+                    .get(parent)
+                    .is_some_and(|entries| entries.iter().any(|e| e.path == path && e.is_dir))
+            })
+    }
+
+    /// Expands or collapses `dir`, returning whether it must be listed.
+    pub fn toggle(&mut self, dir: &Path) -> bool {
+        if !self.expanded.remove(dir) {
+            self.expanded.insert(dir.to_path_buf());
+            return !self.children.contains_key(dir);
+        }
+        false
+    }
+
+    /// Expands the directories down to `path` and selects it, returning the
+    /// directories that must be listed.
+    pub fn reveal(&mut self, path: &Path) -> Vec<PathBuf> {
+        if !path.starts_with(&self.root) {
+            return Vec::new();
+        }
+        self.selected = Some(path.to_path_buf());
+
+        let mut to_list = Vec::new();
+        for dir in path.ancestors().skip(1) {
+            self.expanded.insert(dir.to_path_buf());
+            if !self.children.contains_key(dir) {
+                to_list.push(dir.to_path_buf());
+            }
+            if dir == self.root {
+                break;
+            }
+        }
+        to_list
+    }
+
+    /// The listed directories still expanded, to list again after a change on disk.
+    pub fn expanded_dirs(&self) -> Vec<PathBuf> {
+        self.expanded
+            .iter()
+            .filter(|dir| self.children.contains_key(*dir))
+            .cloned()
+            .collect()
+    }
+
+    /// Where a new entry goes: the selected directory, or the directory of the selected file.
+    pub fn target_dir(&self) -> PathBuf {
+        match &self.selected {
+            Some(path) if self.is_dir(path) => path.clone(),
+            Some(path) => path
+                .parent()
+                .map_or_else(|| self.root.clone(), Path::to_path_buf),
+            None => self.root.clone(),
+        }
+    }
+}
+
+/// Lists `dir` through the proxy, updating the explorer when the response arrives.
+pub fn list_dir(explorer: &Arc<Mutex<Explorer>>, proxy_rpc: &ProxyRpcHandler, dir: PathBuf) {
+    proxy_rpc.read_dir(dir.clone(), {
+        let explorer = explorer.clone();
+        move |result| match result {
+            Ok(ProxyResponse::ReadDirResponse { items }) => {
+                let entries = items
+                    .into_iter()
+                    .map(|item| FileEntry {
+                        path: item.path_buf,
+                        is_dir: item.is_dir,
+                    })
+                    .collect();
+                explorer.lock().unwrap().set_children(dir, entries);
+            }
+            Ok(_) => {}
+            Err(err) => tracing::error!("Failed to read {:?}: {}", dir, err.message),
+        }
+    });
+}
+
+/// Lists again every expanded directory, e.g. after the workspace changed on disk.
+pub fn refresh(explorer: &Arc<Mutex<Explorer>>, proxy_rpc: &ProxyRpcHandler) {
+    let dirs = explorer.lock().unwrap().expanded_dirs();
+    for dir in dirs {
+        list_dir(explorer, proxy_rpc, dir);
+    }
+}
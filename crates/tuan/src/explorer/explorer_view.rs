@@ -0,0 +1,92 @@
+use xilem::{
+    WidgetView,
+    view::{Axis, button, flex, label, portal, text_input},
+};
+
+use super::{Input, InputKind, Row};
+use crate::editor_view::EditorState;
+
+pub fn explorer_view(state: &EditorState) -> impl WidgetView<EditorState> + use<> {
+    let (title, rows, selected, input) = {
+        let explorer = state.explorer.lock().unwrap();
+        let title = explorer
+            .root()
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_else(|| explorer.root().display().to_string());
+        (
+            title,
+            explorer.rows(),
+            explorer.selected.clone(),
+            explorer.input.clone(),
+        )
+    };
+
+    flex((
+        label(title),
+        flex((
+            button("New File", |state: &mut EditorState| state.start_new_file()),
+            button("New Folder", |state: &mut EditorState| {
+                state.start_new_directory()
+            }),
+            button("Rename", |state: &mut EditorState| {
+                state.start_explorer_rename()
+            }),
+            button("Delete", |state: &mut EditorState| {
+                state.delete_selected_entry()
+            }),
+            button("Reveal", |state: &mut EditorState| {
+                state.reveal_active_file()
+            }),
+        ))
+        .direction(Axis::Horizontal),
+        input.map(input_row),
+        portal(flex(
+            rows.into_iter()
+                .map(|row| {
+                    let is_selected = selected.as_ref() == Some(&row.path);
+                    row_view(row, is_selected)
+                })
+                .collect::<Vec<_>>(),
+        )),
+    ))
+}
+
+fn row_view(row: Row, is_selected: bool) -> impl WidgetView<EditorState> + use<> {
+    let marker = match (row.is_dir, row.is_expanded) {
+        (true, true) => "▾ ",
+        (true, false) => "▸ ",
+        (false, _) => "  ",
+    };
+    let text = format!(
+        "{}{}{}{}",
+        if is_selected { "> " } else { "  " },
+        "  ".repeat(row.depth),
+        marker,
+        row.name
+    );
+    button(text, move |state: &mut EditorState| {
+        state.click_explorer_entry(row.path.clone())
+    })
+}
+
+fn input_row(input: Input) -> impl WidgetView<EditorState> + use<> {
+    let prompt = match &input.kind {
+        InputKind::NewFile { .. } => "New file:",
+        InputKind::NewDirectory { .. } => "New folder:",
+        InputKind::Rename { .. } => "Rename to:",
+    };
+    flex((
+        label(prompt),
+        text_input(input.name, |state: &mut EditorState, name| {
+            state.set_explorer_input(name)
+        }),
+        button("OK", |state: &mut EditorState| {
+            state.submit_explorer_input()
+        }),
+        button("Cancel", |state: &mut EditorState| {
+            state.cancel_explorer_input()
+        }),
+    ))
+    .direction(Axis::Horizontal)
+}
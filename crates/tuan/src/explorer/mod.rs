@@ -0,0 +1,5 @@
+mod explorer;
+mod explorer_view;
+
+pub use explorer::*;
+pub use explorer_view::explorer_view;
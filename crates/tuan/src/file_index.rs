@@ -0,0 +1,290 @@
+use std::{
+    collections::BTreeSet,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, Weak},
+};
+
+use ignore::WalkBuilder;
+use notify::{RecommendedWatcher, RecursiveMode, Watcher as _};
+
+/// The names of the files listing paths to leave out of the index.
+const IGNORE_FILES: [&str; 2] = [".gitignore", ".ignore"];
+
+/// Every file of the workspace, for the quick open.
+///
+/// The workspace is walked once in the background, skipping what the ignore
+/// files exclude as git does, then kept up to date from file-watcher events.
+/// Only the directories left in are watched, so that e.g. build outputs do
+/// not flood the index with events.
+#[derive(Clone)]
+pub struct FileIndex {
+    inner: Arc<Mutex<Inner>>,
+    /// Kept alive for as long as the index is used.
+    _watcher: Arc<Mutex<Option<RecommendedWatcher>>>,
+}
+
+struct Inner {
+    root: PathBuf,
+    files: BTreeSet<PathBuf>,
+    /// Set until the first walk is done.
+    indexing: bool,
+    /// Bumped whenever `files` changes.
+    rev: u64,
+    /// `files` as a list, until they change.
+    snapshot: Option<Arc<Vec<PathBuf>>>,
+}
+
+impl FileIndex {
+    pub fn new(root: PathBuf) -> Self {
+        let inner = Arc::new(Mutex::new(Inner {
+            root: root.clone(),
+            files: BTreeSet::new(),
+            indexing: true,
+            rev: 0,
+            snapshot: None,
+        }));
+
+        let (events_tx, events_rx) = crossbeam_channel::unbounded();
+        let watcher = notify::recommended_watcher(move |result| {
+            let _ = events_tx.send(result);
+        })
+        .inspect_err(|err| tracing::error!("Failed to watch the workspace files: {err}"))
+        .ok();
+        let watcher = Arc::new(Mutex::new(watcher));
+
+        // The directories are watched from this thread: the watcher cannot
+        // add watches from its own thread, which sends the events.
+        std::thread::spawn({
+            let inner = inner.clone();
+            let watcher = Arc::downgrade(&watcher);
+            move || {
+                let mut files = BTreeSet::new();
+                let mut dirs = Vec::new();
+                walk(&root, None, &mut files, &mut dirs);
+                tracing::debug!("Indexed {} files in {:?}", files.len(), root);
+                watch(&watcher, &dirs);
+
+                let mut locked = inner.lock().unwrap();
+                locked.files.extend(files);
+                locked.indexing = false;
+                locked.changed();
+                drop(locked);
+
+                // Ends once the index, and so the watcher sending the events, is dropped.
+                for result in events_rx {
+                    match result {
+                        Ok(event) if !event.kind.is_access() => {
+                            let mut locked = inner.lock().unwrap();
+                            let dirs = event
+                                .paths
+                                .iter()
+                                .flat_map(|path| locked.refresh(path))
+                                .collect::<Vec<_>>();
+                            drop(locked);
+                            watch(&watcher, &dirs);
+                        }
+                        Ok(_) => {}
+                        Err(err) => tracing::error!("Workspace watcher error: {err}"),
+                    }
+                }
+            }
+        });
+
+        Self {
+            inner,
+            _watcher: watcher,
+        }
+    }
+
+    /// The indexed files, sorted.
+    pub fn files(&self) -> Arc<Vec<PathBuf>> {
+        let mut inner = self.inner.lock().unwrap();
+        let Inner {
+            files, snapshot, ..
+        } = &mut *inner;
+        snapshot
+            .get_or_insert_with(|| Arc::new(files.iter().cloned().collect()))
+            .clone()
+    }
+
+    /// Changes whenever the indexed files do.
+    pub fn rev(&self) -> u64 {
+        self.inner.lock().unwrap().rev
+    }
+
+    /// Whether the workspace is still being walked for the first time.
+    pub fn is_indexing(&self) -> bool {
+        self.inner.lock().unwrap().indexing
+    }
+}
+
+impl Inner {
+    fn changed(&mut self) {
+        self.rev += 1;
+        self.snapshot = None;
+    }
+
+    /// Updates the index after `path` changed: it was created, removed or
+    /// renamed. Returns the new directories to watch.
+    fn refresh(&mut self, path: &Path) -> Vec<PathBuf> {
+        if !path.starts_with(&self.root) || path == self.root {
+            return Vec::new();
+        }
+
+        // A changed ignore file can hide or show anything below its directory.
+        let path = match path.file_name() {
+            Some(name) if IGNORE_FILES.iter().any(|file| name == *file) => {
+                let Some(dir) = path.parent() else {
+                    return Vec::new();
+                };
+                dir
+            }
+            _ => path,
+        };
+
+        let before = self.files.len();
+        let removed = self
+            .files
+            .range(path.to_path_buf()..)
+            .take_while(|file| file.starts_with(path))
+            .cloned()
+            .collect::<Vec<_>>();
+        for file in &removed {
+            self.files.remove(file);
+        }
+
+        let mut dirs = Vec::new();
+        match path.parent() {
+            Some(parent) if path != self.root => {
+                walk(parent, Some(path), &mut self.files, &mut dirs)
+            }
+            _ => walk(path, None, &mut self.files, &mut dirs),
+        }
+
+        // A modified file is removed then added back, leaving the index as it was.
+        let unchanged =
+            self.files.len() == before && removed.iter().all(|file| self.files.contains(file));
+        if !unchanged {
+            self.changed();
+        }
+        dirs
+    }
+}
+
+/// Adds the files below `dir` to `files`, leaving out the ignored ones, and
+/// the directories walked through to `dirs`. With `only`, just `only` and what
+/// is below it is walked, `dir` being its parent.
+fn walk(dir: &Path, only: Option<&Path>, files: &mut BTreeSet<PathBuf>, dirs: &mut Vec<PathBuf>) {
+    let only = only.map(Path::to_path_buf);
+    let walker = WalkBuilder::new(dir)
+        // Dotfiles are listed, only the ignore files leave files out.
+        .hidden(false)
+        .require_git(false)
+        .filter_entry({
+            let only = only.clone();
+            move |entry| {
+                entry.file_name() != ".git"
+                    && only
+                        .as_ref()
+                        .is_none_or(|only| entry.depth() == 0 || entry.path().starts_with(only))
+            }
+        })
+        .build();
+    for entry in walker {
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(err) => {
+                tracing::debug!("Failed to list {:?}: {err}", dir);
+                continue;
+            }
+        };
+        if only.is_some() && entry.depth() == 0 {
+            continue;
+        }
+        let Some(file_type) = entry.file_type() else {
+            continue;
+        };
+        // Symlinked directories are not followed, as they may loop.
+        if file_type.is_dir() {
+            dirs.push(entry.into_path());
+        } else if file_type.is_file() || entry.path().is_file() {
+            files.insert(entry.into_path());
+        }
+    }
+}
+
+/// Watches each of `dirs` without what is below it, which is watched only
+/// when not ignored.
+fn watch(watcher: &Weak<Mutex<Option<RecommendedWatcher>>>, dirs: &[PathBuf]) {
+    let Some(watcher) = watcher.upgrade() else {
+        return;
+    };
+    let mut watcher = watcher.lock().unwrap();
+    let Some(watcher) = watcher.as_mut() else {
+        return;
+    };
+    for dir in dirs {
+        if let Err(err) = watcher.watch(dir, RecursiveMode::NonRecursive) {
+            tracing::debug!("Failed to watch {:?}: {err}", dir);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn walk_leaves_out_what_git_ignores() {
+        let root = std::env::temp_dir().join(format!("tuan-file-index-{}", std::process::id()));
+        for (relative, content) in [
+            (".gitignore", "target/\n"),
+            (".git/info/exclude", "secret.txt\n"),
+            (".env", ""),
+            ("secret.txt", ""),
+            ("src/main.rs", ""),
+            ("src/.gitignore", "*.log\n"),
+            ("src/debug.log", ""),
+            ("target/debug/tuan", ""),
+        ] {
+            let path = root.join(relative);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, content).unwrap();
+        }
+
+        let mut files = BTreeSet::new();
+        let mut dirs = Vec::new();
+        walk(&root, None, &mut files, &mut dirs);
+        let relative = |paths: Vec<&PathBuf>| {
+            paths
+                .into_iter()
+                .map(|path| path.strip_prefix(&root).unwrap().to_path_buf())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            relative(files.iter().collect()),
+            [".env", ".gitignore", "src/.gitignore", "src/main.rs"]
+                .map(PathBuf::from)
+                .to_vec()
+        );
+        dirs.sort();
+        assert_eq!(
+            relative(dirs.iter().collect()),
+            ["", "src"].map(PathBuf::from).to_vec()
+        );
+
+        // Walking a single path still follows the ignore files above it.
+        let mut files = BTreeSet::new();
+        walk(
+            &root.join("src"),
+            Some(&root.join("src/debug.log")),
+            &mut files,
+            &mut Vec::new(),
+        );
+        assert!(files.is_empty());
+        walk(&root, Some(&root.join("src")), &mut files, &mut Vec::new());
+        assert_eq!(files.len(), 2);
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+}
@@ -0,0 +1,103 @@
+//! Fuzzy matching of a typed pattern against candidates, e.g. completion labels.
+
+/// How well a candidate matches a pattern.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FuzzyMatch {
+    /// Higher is better.
+    pub score: i64,
+    /// The char indices of the candidate matched by the pattern.
+    pub indices: Vec<usize>,
+}
+
+const MATCH_SCORE: i64 = 16;
+const CONSECUTIVE_BONUS: i64 = 24;
+const WORD_START_BONUS: i64 = 32;
+const CASE_BONUS: i64 = 4;
+const GAP_PENALTY: i64 = 2;
+
+/// Matches the chars of `pattern` in order, case-insensitively, anywhere in
+/// `candidate`. Consecutive matches, matches at word starts and prefixes
+/// score higher; gaps and longer candidates score lower.
+///
+/// Returns `None` when `candidate` does not contain all the chars of `pattern`.
+pub fn fuzzy_match(pattern: &str, candidate: &str) -> Option<FuzzyMatch> {
+    let candidate = candidate.chars().collect::<Vec<_>>();
+    let mut indices = Vec::with_capacity(pattern.len());
+    let mut score = 0;
+    let mut next = 0;
+
+    for p in pattern.chars() {
+        // Prefer a word start over the first occurrence, unless it is the next char.
+        let first = (next..candidate.len()).find(|&i| eq_ignore_case(candidate[i], p))?;
+        let index = if first == next {
+            first
+        } else {
+            (first..candidate.len())
+                .find(|&i| eq_ignore_case(candidate[i], p) && is_word_start(&candidate, i))
+                .unwrap_or(first)
+        };
+
+        score += MATCH_SCORE;
+        if candidate[index] == p {
+            score += CASE_BONUS;
+        }
+        if is_word_start(&candidate, index) {
+            score += WORD_START_BONUS;
+        }
+        match indices.last() {
+            Some(&last) if last + 1 == index => score += CONSECUTIVE_BONUS,
+            Some(&last) => score -= GAP_PENALTY * (index - last - 1) as i64,
+            None => score -= GAP_PENALTY * index as i64,
+        }
+
+        indices.push(index);
+        next = index + 1;
+    }
+
+    score -= (candidate.len() - indices.len()) as i64;
+    Some(FuzzyMatch { score, indices })
+}
+
+fn eq_ignore_case(a: char, b: char) -> bool {
+    a == b || a.to_lowercase().eq(b.to_lowercase())
+}
+
+/// Whether the char at `index` starts a word: after a separator, or an
+/// uppercase letter after a lowercase one, as in `camelCase`.
+fn is_word_start(chars: &[char], index: usize) -> bool {
+    let Some(previous) = index.checked_sub(1).map(|i| chars[i]) else {
+        return true;
+    };
+    let current = chars[index];
+    !previous.is_alphanumeric() || (previous.is_lowercase() && current.is_uppercase())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn score(pattern: &str, candidate: &str) -> i64 {
+        fuzzy_match(pattern, candidate).unwrap().score
+    }
+
+    #[test]
+    fn every_char_must_match_in_order() {
+        assert_eq!(fuzzy_match("xyz", "abc"), None);
+        assert_eq!(fuzzy_match("ba", "abc"), None);
+        assert_eq!(fuzzy_match("FB", "foo_bar").unwrap().indices, vec![0, 4]);
+    }
+
+    #[test]
+    fn word_starts_are_preferred_over_first_occurrences() {
+        assert_eq!(fuzzy_match("b", "abc_bar").unwrap().indices, vec![4]);
+        assert_eq!(fuzzy_match("fb", "fooBar").unwrap().indices, vec![0, 3]);
+    }
+
+    #[test]
+    fn scores_rank_prefixes_then_word_starts_then_shorter_candidates() {
+        assert!(score("fo", "foo") > score("fo", "afoo"));
+        assert!(score("b", "foo_bar") > score("b", "fooxbar"));
+        assert!(score("ab", "ab") > score("ab", "abc"));
+        assert!(score("ab", "ab") > score("AB", "ab"));
+    }
+}
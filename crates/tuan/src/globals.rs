@@ -1,7 +1,21 @@
-use std::sync::LazyLock;
+use std::sync::{LazyLock, OnceLock};
+
+static CONFIG_DIR_OVERRIDE: OnceLock<String> = OnceLock::new();
+
+/// Overrides [`CONFIG_DIR`], e.g. from the `--config-dir` CLI flag.
+///
+/// Must be called before `CONFIG_DIR` is first accessed to have any effect.
+pub fn set_config_dir(dir: String) {
+    if CONFIG_DIR_OVERRIDE.set(dir).is_err() {
+        tracing::warn!("CONFIG_DIR override has already been set");
+    }
+}
 
 #[cfg(target_os = "macos")]
 pub static CONFIG_DIR: LazyLock<String> = LazyLock::new(|| {
+    if let Some(dir) = CONFIG_DIR_OVERRIDE.get() {
+        return dir.clone();
+    }
     let home = dirs::home_dir()
         .map(|p| p.to_string_lossy().into_owned())
         .unwrap_or_else(|| "/".to_string());
@@ -12,6 +26,9 @@ pub static CONFIG_DIR: LazyLock<String> = LazyLock::new(|| {
 
 #[cfg(target_os = "linux")]
 pub static CONFIG_DIR: LazyLock<String> = LazyLock::new(|| {
+    if let Some(dir) = CONFIG_DIR_OVERRIDE.get() {
+        return dir.clone();
+    }
     let home = dirs::home_dir()
         .map(|p| p.to_string_lossy().into_owned())
         .unwrap_or_else(|| "/".to_string());
@@ -21,5 +38,10 @@ pub static CONFIG_DIR: LazyLock<String> = LazyLock::new(|| {
 });
 
 #[cfg(target_os = "windows")]
-pub static CONFIG_DIR: LazyLock<String> =
-    LazyLock::new(|| option_env!("TUAN_CONFIG_DIR").unwrap_or(r"%APPDATA%\tuan-editor").into());
+pub static CONFIG_DIR: LazyLock<String> = LazyLock::new(|| {
+    CONFIG_DIR_OVERRIDE.get().cloned().unwrap_or_else(|| {
+        option_env!("TUAN_CONFIG_DIR")
+            .unwrap_or(r"%APPDATA%\tuan-editor")
+            .into()
+    })
+});
@@ -1,25 +1,79 @@
-use crate::{editor_view::EditorAction, keybindings::keybindings_config::KeybindingsConfig};
-use std::fs;
+use crate::{
+    editor_view::EditorAction, globals, keybindings::keybindings_config::KeybindingsConfig,
+};
+use std::{fs, path::PathBuf};
+
+/// The keymap shared by every platform.
+const DEFAULT_KEYBINDINGS: &str = include_str!("../../assets/keybind_config.json");
+
+/// The platform keymap, using `Cmd` on macOS and `Ctrl` elsewhere.
+#[cfg(target_os = "macos")]
+const PLATFORM_KEYBINDINGS: &str = include_str!("../../assets/keybind_config.macos.json");
+#[cfg(not(target_os = "macos"))]
+const PLATFORM_KEYBINDINGS: &str = include_str!("../../assets/keybind_config.linux.json");
 
 #[derive(Clone)]
 pub struct Keybindings {
     pub(crate) keybinds: keybinds::Keybinds<EditorAction>,
+    /// The keys as written in the keymaps, with their action.
+    keys: Vec<(String, EditorAction)>,
 }
 
 impl Keybindings {
-    pub fn new() -> Result<Self, keybinds::Error> {
-        let mut keybinds = keybinds::Keybinds::default();
+    /// The user keymap, merged on top of the bundled ones.
+    pub fn config_path() -> PathBuf {
+        PathBuf::from(globals::CONFIG_DIR.as_str()).join("keybindings.json")
+    }
 
-        let config_path = "/Users/arthurfontaine/Developer/code/github.com/arthur-fontaine/tuan/crates/tuan/assets/keybind_config.json";
-        let config_data =
-            fs::read_to_string(config_path).expect("Failed to read keybind_config.json");
-        let config = serde_json::from_str::<KeybindingsConfig>(&config_data)
-            .expect("Failed to parse keybind_config.json");
+    /// Loads the bundled keymaps with the user keymap merged on top.
+    ///
+    /// A missing user keymap is not an error. A broken one is, in which case
+    /// callers should fall back to [`Keybindings::default`].
+    pub fn new() -> Result<Self, Box<dyn std::error::Error>> {
+        let config = match fs::read_to_string(Self::config_path()) {
+            Ok(config_data) => Self::default_config()
+                .merge(serde_json::from_str::<KeybindingsConfig>(&config_data)?)?,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Self::default_config(),
+            Err(err) => return Err(err.into()),
+        };
+
+        Self::from_config(config)
+    }
+
+    fn default_config() -> KeybindingsConfig {
+        let default = serde_json::from_str::<KeybindingsConfig>(DEFAULT_KEYBINDINGS)
+            .expect("The bundled keybind_config.json should be valid");
+        let platform = serde_json::from_str::<KeybindingsConfig>(PLATFORM_KEYBINDINGS)
+            .expect("The bundled platform keybindings should be valid");
+        default
+            .merge(platform)
+            .expect("The bundled platform keys should parse")
+    }
+
+    fn from_config(config: KeybindingsConfig) -> Result<Self, Box<dyn std::error::Error>> {
+        let mut keybinds = keybinds::Keybinds::default();
+        let mut keys = Vec::with_capacity(config.keybindings.len());
 
         for keybind in config.keybindings {
-            keybinds.bind(&keybind.key, keybind.action)?;
+            keybinds.bind(&keybind.key, keybind.action.clone())?;
+            keys.push((keybind.key.trim().to_string(), keybind.action));
         }
 
-        Ok(Self { keybinds })
+        Ok(Self { keybinds, keys })
+    }
+
+    /// The first key bound to `action`, e.g. `Ctrl+s`.
+    pub fn key_for(&self, action: &EditorAction) -> Option<&str> {
+        self.keys
+            .iter()
+            .find(|(_, bound)| bound == action)
+            .map(|(key, _)| key.as_str())
+    }
+}
+
+impl Default for Keybindings {
+    /// The bundled keymaps only.
+    fn default() -> Self {
+        Self::from_config(Self::default_config()).expect("The bundled keybindings should be valid")
     }
 }
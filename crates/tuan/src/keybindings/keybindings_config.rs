@@ -1,4 +1,5 @@
 use crate::editor_view::EditorAction;
+use keybinds::KeySeq;
 use serde::Deserialize;
 
 #[derive(Debug, Deserialize)]
@@ -11,3 +12,79 @@ pub(super) struct KeybindingConfig {
     pub(super) key: String,
     pub(super) action: EditorAction,
 }
+
+impl KeybindingConfig {
+    /// The key as the keybinds see it, whatever the order and case of its modifiers.
+    fn key_seq(&self) -> Result<KeySeq, keybinds::Error> {
+        self.key.trim().parse()
+    }
+}
+
+impl KeybindingsConfig {
+    /// Adds the keybindings of `other`, replacing the ones bound to the same key.
+    ///
+    /// Fails when a key of `other` cannot be parsed.
+    pub(super) fn merge(mut self, other: Self) -> Result<Self, keybinds::Error> {
+        let keys = other
+            .keybindings
+            .iter()
+            .map(KeybindingConfig::key_seq)
+            .collect::<Result<Vec<_>, _>>()?;
+        self.keybindings.retain(|keybinding| {
+            !keybinding
+                .key_seq()
+                .is_ok_and(|key_seq| keys.contains(&key_seq))
+        });
+        self.keybindings.extend(other.keybindings);
+        Ok(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(keybindings: &[(&str, EditorAction)]) -> KeybindingsConfig {
+        KeybindingsConfig {
+            keybindings: keybindings
+                .iter()
+                .map(|(key, action)| KeybindingConfig {
+                    key: key.to_string(),
+                    action: action.clone(),
+                })
+                .collect(),
+        }
+    }
+
+    fn keys(config: &KeybindingsConfig) -> Vec<(&str, EditorAction)> {
+        config
+            .keybindings
+            .iter()
+            .map(|keybinding| (keybinding.key.as_str(), keybinding.action.clone()))
+            .collect()
+    }
+
+    #[test]
+    fn merge_replaces_the_same_key_whatever_the_order_of_its_modifiers() {
+        let merged = config(&[
+            ("Ctrl+Shift+z", EditorAction::Redo),
+            ("Ctrl+y", EditorAction::Redo),
+        ])
+        .merge(config(&[(" shift+ctrl+z", EditorAction::Undo)]))
+        .unwrap();
+        assert_eq!(
+            keys(&merged),
+            [
+                ("Ctrl+y", EditorAction::Redo),
+                (" shift+ctrl+z", EditorAction::Undo)
+            ]
+        );
+    }
+
+    #[test]
+    fn merge_rejects_keys_that_do_not_parse() {
+        let merged = config(&[("Ctrl+y", EditorAction::Redo)])
+            .merge(config(&[("Ctrl+", EditorAction::Undo)]));
+        assert!(merged.is_err());
+    }
+}
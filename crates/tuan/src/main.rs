@@ -1,32 +1,61 @@
 use std::sync::Arc;
 
+use clap::Parser as _;
+use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 use winit::error::EventLoopError;
 use xilem::{EventLoop, WidgetView, WindowOptions, Xilem, core::lens};
-use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
-use crate::editor_view::{EditorConfig, EditorState, editor_view};
+use crate::{
+    cli::Cli,
+    editor_view::{EditorConfig, EditorState, editor_view},
+    settings::Settings,
+};
 
+mod cli;
+mod clipboard;
+mod config_watcher;
 mod document;
 mod editor_view;
+mod explorer;
+mod file_index;
+mod fuzzy;
 mod globals;
+mod keybindings;
+mod markdown;
 mod proxy;
+mod settings;
 mod terminal;
-mod workspace;
 mod theme;
-mod keybindings;
+mod workspace;
 
 pub struct AppState {
     editor_state: EditorState,
 }
 
 impl AppState {
-    fn new() -> Self {
-        Self {
-            editor_state: EditorState::new(
-                "/Users/arthurfontaine/Developer/code/local/la-galerie-de-max/la-galerie-de-max copie".into(),
-                Arc::new(EditorConfig::default()),
-            ),
+    fn new(cli: &Cli) -> Self {
+        let workspace_path = cli.workspace_path();
+
+        let (settings, errors) = Settings::load(&workspace_path);
+        for error in errors {
+            tracing::error!("Ignoring settings file: {error}");
         }
+        let editor_config = EditorConfig::from_settings(&settings).unwrap_or_else(|error| {
+            tracing::error!("Falling back to the default theme: {error}");
+            EditorConfig::from_settings(&Settings {
+                theme: None,
+                ..settings.clone()
+            })
+            .expect("Settings without a theme should always be valid")
+        });
+
+        let mut editor_state = EditorState::new(workspace_path, settings, Arc::new(editor_config));
+
+        for file in cli.files_to_open() {
+            editor_state.open_file_at(file.path.clone(), file.cursor_position());
+        }
+
+        Self { editor_state }
     }
 }
 
@@ -35,18 +64,29 @@ fn app_logic(data: &mut AppState) -> impl WidgetView<AppState> + use<> {
 }
 
 fn main() -> Result<(), EventLoopError> {
+    let cli = Cli::parse();
+
     // Initialize tracing with a filter to reduce debug noise
+    let env_filter = match &cli.log_level {
+        Some(log_level) => {
+            tracing_subscriber::EnvFilter::try_new(log_level).unwrap_or_else(|err| {
+                eprintln!("Invalid --log-level {log_level:?}: {err}");
+                tracing_subscriber::EnvFilter::new("warn,tuan=debug")
+            })
+        }
+        None => tracing_subscriber::EnvFilter::try_from_default_env()
+            .unwrap_or_else(|_| tracing_subscriber::EnvFilter::new("warn,tuan=debug")),
+    };
     tracing_subscriber::registry()
-        .with(
-            tracing_subscriber::EnvFilter::try_from_default_env()
-                .unwrap_or_else(|_| {
-                    tracing_subscriber::EnvFilter::new("warn,tuan=debug")
-                })
-        )
+        .with(env_filter)
         .with(tracing_subscriber::fmt::layer())
         .init();
 
-    let app = Xilem::new_simple(AppState::new(), app_logic, WindowOptions::new("Tuan"));
+    if let Some(config_dir) = &cli.config_dir {
+        globals::set_config_dir(config_dir.to_string_lossy().into_owned());
+    }
+
+    let app = Xilem::new_simple(AppState::new(&cli), app_logic, WindowOptions::new("Tuan"));
     app.run_in(EventLoop::with_user_event())?;
     Ok(())
 }
@@ -0,0 +1,259 @@
+//! Rendering of markdown, e.g. LSP hover contents, to styled text.
+
+use std::path::Path;
+
+use pulldown_cmark::{CodeBlockKind, Event, Parser, Tag, TagEnd};
+use xilem::Color;
+
+use crate::{
+    document::{RangeStyle, highlight},
+    editor_view::EditorConfig,
+    theme::Style,
+};
+
+/// Text with styled byte ranges.
+#[derive(Debug, Clone, Default)]
+pub struct RichText {
+    pub text: String,
+    pub styles: Vec<RangeStyle>,
+}
+
+impl RichText {
+    pub fn plain(text: impl Into<String>) -> Self {
+        Self {
+            text: text.into(),
+            styles: Vec::new(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.text.is_empty()
+    }
+
+    pub fn push(&mut self, text: &str, style: Option<Style>) {
+        let start = self.text.len();
+        self.text.push_str(text);
+        if let Some(style) = style
+            && !text.is_empty()
+        {
+            self.styles.push(RangeStyle {
+                start,
+                end: self.text.len(),
+                style,
+            });
+        }
+    }
+
+    /// Appends `text` with `styles`, given in offsets of `text`.
+    pub fn push_styled(&mut self, text: &str, styles: Vec<RangeStyle>) {
+        let shift = self.text.len();
+        self.text.push_str(text);
+        self.styles
+            .extend(styles.into_iter().map(|style| RangeStyle {
+                start: style.start + shift,
+                end: style.end + shift,
+                style: style.style,
+            }));
+    }
+
+    /// Appends `other` as a new paragraph.
+    pub fn append(&mut self, other: RichText) {
+        if other.is_empty() {
+            return;
+        }
+        if !self.is_empty() {
+            self.text.push_str("\n\n");
+        }
+        self.push_styled(&other.text, other.styles);
+    }
+
+    fn trim_end(&mut self) {
+        self.text.truncate(self.text.trim_end().len());
+        let len = self.text.len();
+        for style in &mut self.styles {
+            style.end = style.end.min(len);
+        }
+        self.styles.retain(|style| style.start < style.end);
+    }
+}
+
+/// Renders `markdown` as styled text: emphasis, headings and links are styled,
+/// list items get a bullet, and code blocks are highlighted in their language,
+/// or in the language of `path` when they do not name one.
+pub fn render(markdown: &str, path: &Path, config: &EditorConfig) -> RichText {
+    let mut renderer = Renderer {
+        path,
+        config,
+        rich_text: RichText::default(),
+        bold: 0,
+        italic: 0,
+        link: 0,
+        lists: Vec::new(),
+        at_item_start: false,
+        code_block: None,
+    };
+    for event in Parser::new(markdown) {
+        renderer.event(event);
+    }
+    let mut rich_text = renderer.rich_text;
+    rich_text.trim_end();
+    rich_text
+}
+
+struct Renderer<'a> {
+    path: &'a Path,
+    config: &'a EditorConfig,
+    rich_text: RichText,
+    /// How many nested tags make the text bold, italic, or a link.
+    bold: usize,
+    italic: usize,
+    link: usize,
+    /// The next number of each nested list, `None` for bulleted ones.
+    lists: Vec<Option<u64>>,
+    /// Set right after a bullet, where the paragraph of the item starts without a break.
+    at_item_start: bool,
+    /// The language and text of the code block being read.
+    code_block: Option<(Option<String>, String)>,
+}
+
+impl Renderer<'_> {
+    fn event(&mut self, event: Event<'_>) {
+        match event {
+            Event::Start(tag) => self.start(tag),
+            Event::End(tag) => self.end(tag),
+            Event::Text(text) => match &mut self.code_block {
+                Some((_, code)) => code.push_str(&text),
+                None => self.push_text(&text),
+            },
+            Event::Code(code) => {
+                let style = self.style(self.config.theme_color(&["textPreformat.foreground"]));
+                self.push(&code, style);
+            }
+            Event::SoftBreak => self.push_text(" "),
+            Event::HardBreak => self.push_text("\n"),
+            Event::Rule => {
+                self.block_break();
+                self.push_text(&"─".repeat(20));
+            }
+            _ => {}
+        }
+    }
+
+    fn start(&mut self, tag: Tag<'_>) {
+        match tag {
+            Tag::Paragraph => self.block_break(),
+            Tag::Heading { .. } => {
+                self.block_break();
+                self.bold += 1;
+            }
+            Tag::BlockQuote(..) => {
+                self.block_break();
+                self.italic += 1;
+            }
+            Tag::CodeBlock(kind) => {
+                self.block_break();
+                let language = match kind {
+                    CodeBlockKind::Fenced(info) => info
+                        .split([' ', ',', '{'])
+                        .next()
+                        .filter(|language| !language.is_empty())
+                        .map(str::to_string),
+                    CodeBlockKind::Indented => None,
+                };
+                self.code_block = Some((language, String::new()));
+            }
+            Tag::List(start) => {
+                if self.lists.is_empty() {
+                    self.block_break();
+                }
+                self.lists.push(start);
+            }
+            Tag::Item => {
+                if !self.rich_text.is_empty() && !self.rich_text.text.ends_with('\n') {
+                    self.rich_text.text.push('\n');
+                }
+                let indent = "  ".repeat(self.lists.len().saturating_sub(1));
+                let bullet = match self.lists.last_mut() {
+                    Some(Some(number)) => {
+                        *number += 1;
+                        format!("{indent}{}. ", *number - 1)
+                    }
+                    _ => format!("{indent}• "),
+                };
+                self.rich_text.push(&bullet, None);
+                self.at_item_start = true;
+            }
+            Tag::Emphasis => self.italic += 1,
+            Tag::Strong => self.bold += 1,
+            Tag::Link { .. } => self.link += 1,
+            _ => {}
+        }
+    }
+
+    fn end(&mut self, tag: TagEnd) {
+        match tag {
+            TagEnd::Heading(_) => self.bold = self.bold.saturating_sub(1),
+            TagEnd::BlockQuote(..) => self.italic = self.italic.saturating_sub(1),
+            TagEnd::CodeBlock => {
+                if let Some((language, code)) = self.code_block.take() {
+                    let code = code.strip_suffix('\n').unwrap_or(&code);
+                    let styles = highlight::highlight_code(
+                        code,
+                        language.as_deref(),
+                        self.path,
+                        self.config,
+                    );
+                    self.rich_text.push_styled(code, styles);
+                }
+            }
+            TagEnd::List(_) => {
+                self.lists.pop();
+            }
+            TagEnd::Emphasis => self.italic = self.italic.saturating_sub(1),
+            TagEnd::Strong => self.bold = self.bold.saturating_sub(1),
+            TagEnd::Link => self.link = self.link.saturating_sub(1),
+            _ => {}
+        }
+    }
+
+    /// Separates a new block from the previous one: by an empty line, or
+    /// by a line break within a list.
+    fn block_break(&mut self) {
+        if self.at_item_start || self.rich_text.is_empty() {
+            return;
+        }
+        let text = &mut self.rich_text.text;
+        text.truncate(text.trim_end_matches(' ').len());
+        let breaks = if self.lists.is_empty() { "\n\n" } else { "\n" };
+        while !text.ends_with(breaks) {
+            text.push('\n');
+        }
+    }
+
+    fn push_text(&mut self, text: &str) {
+        let color = (self.link > 0)
+            .then(|| self.config.theme_color(&["textLink.foreground"]))
+            .flatten();
+        let style = self.style(color);
+        self.push(text, style);
+    }
+
+    fn push(&mut self, text: &str, style: Option<Style>) {
+        self.at_item_start = false;
+        self.rich_text.push(text, style);
+    }
+
+    /// The style of the text at this point, `None` when it is plain.
+    fn style(&self, color: Option<Color>) -> Option<Style> {
+        let style = Style {
+            color,
+            foreground: None,
+            background: None,
+            italic: self.italic > 0,
+            bold: self.bold > 0,
+            underline: self.link > 0,
+            strikethrough: false,
+        };
+        (style.color.is_some() || style.italic || style.bold || style.underline).then_some(style)
+    }
+}
@@ -105,28 +105,67 @@ pub fn new_proxy(
             .unwrap();
     }
 
-    let (tx, rx) = crossbeam_channel::unbounded();
+    let notification_rx = forward_notifications(&core_rpc, term_tx);
+    ProxyData {
+        proxy_rpc,
+        core_rpc,
+        notification_rx,
+    }
+}
+
+/// Runs a fake proxy answering the requests of the editor with the handler
+/// made by `handler`, given the RPC handler to respond with, to test the
+/// editor without a real proxy and language servers.
+///
+/// The core side is the real one, so notifications sent with
+/// `core_rpc.notification` reach the editor as they do from a real proxy.
+#[cfg(test)]
+pub fn new_fake_proxy<H>(handler: impl FnOnce(ProxyRpcHandler) -> H) -> ProxyData
+where
+    H: tuan_rpc::proxy::ProxyHandler + Send + 'static,
+{
+    let proxy_rpc = ProxyRpcHandler::new();
+    let core_rpc = CoreRpcHandler::new();
+    let mut handler = handler(proxy_rpc.clone());
+
     {
-        let core_rpc = core_rpc.clone();
+        let proxy_rpc = proxy_rpc.clone();
         std::thread::Builder::new()
-            .name("CoreRpcHandler".to_owned())
-            .spawn(move || {
-                let mut proxy = Proxy { tx, term_tx };
-                core_rpc.mainloop(&mut proxy);
-                core_rpc.notification(CoreNotification::ProxyStatus {
-                    status: ProxyStatus::Disconnected,
-                });
-            })
-            .unwrap()
-    };
+            .name("FakeProxyRpcHandler".to_owned())
+            .spawn(move || proxy_rpc.mainloop(&mut handler))
+            .unwrap();
+    }
 
+    let (term_tx, _) = crossbeam_channel::unbounded();
+    let notification_rx = forward_notifications(&core_rpc, term_tx);
     ProxyData {
         proxy_rpc,
         core_rpc,
-        notification_rx: rx,
+        notification_rx,
     }
 }
 
+/// Forwards the notifications of the proxy to the returned channel, and the
+/// terminal updates to `term_tx`.
+fn forward_notifications(
+    core_rpc: &CoreRpcHandler,
+    term_tx: Sender<(TermId, TermEvent)>,
+) -> Receiver<CoreNotification> {
+    let (tx, rx) = crossbeam_channel::unbounded();
+    let core_rpc = core_rpc.clone();
+    std::thread::Builder::new()
+        .name("CoreRpcHandler".to_owned())
+        .spawn(move || {
+            let mut proxy = Proxy { tx, term_tx };
+            core_rpc.mainloop(&mut proxy);
+            core_rpc.notification(CoreNotification::ProxyStatus {
+                status: ProxyStatus::Disconnected,
+            });
+        })
+        .unwrap();
+    rx
+}
+
 impl CoreHandler for Proxy {
     fn handle_notification(&mut self, rpc: tuan_rpc::core::CoreNotification) {
         if let CoreNotification::UpdateTerminal { term_id, content } = &rpc {
@@ -143,7 +182,13 @@ impl CoreHandler for Proxy {
         }
     }
 
-    fn handle_request(&mut self, _id: tuan_rpc::RequestId, _rpc: tuan_rpc::core::CoreRequest) {}
+    // lapce-rpc defines no request from the proxy to the editor: plugin prompts
+    // and workspace edits reach the editor as notifications and proxy
+    // responses, so the proxy never waits on an answer from here. The empty
+    // match stops compiling as soon as lapce-rpc adds a request to answer.
+    fn handle_request(&mut self, _id: tuan_rpc::RequestId, rpc: tuan_rpc::core::CoreRequest) {
+        match rpc {}
+    }
 }
 
 pub fn new_command(program: &str) -> Command {
@@ -0,0 +1,70 @@
+use std::{fmt::Display, path::PathBuf};
+
+/// An error found while loading a settings file.
+///
+/// Settings errors never abort the editor: the offending layer is skipped
+/// (or the previous configuration is kept) and the error is reported.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SettingsError {
+    pub path: PathBuf,
+    pub kind: SettingsErrorKind,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SettingsErrorKind {
+    /// The file exists but could not be read.
+    Io(String),
+    /// The file is not valid JSON, or its root is not an object.
+    Parse {
+        line: usize,
+        column: usize,
+        message: String,
+    },
+    /// The key is not a known setting.
+    UnknownKey(String),
+    /// The key is known but its value has the wrong type or is out of range.
+    InvalidValue { key: String, message: String },
+}
+
+impl SettingsError {
+    pub(super) fn new(path: impl Into<PathBuf>, kind: SettingsErrorKind) -> Self {
+        Self {
+            path: path.into(),
+            kind,
+        }
+    }
+
+    pub(crate) fn invalid_value(
+        path: impl Into<PathBuf>,
+        key: &str,
+        message: impl Into<String>,
+    ) -> Self {
+        Self::new(
+            path,
+            SettingsErrorKind::InvalidValue {
+                key: key.to_string(),
+                message: message.into(),
+            },
+        )
+    }
+}
+
+impl Display for SettingsError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let path = self.path.display();
+        match &self.kind {
+            SettingsErrorKind::Io(message) => write!(f, "{path}: {message}"),
+            SettingsErrorKind::Parse {
+                line,
+                column,
+                message,
+            } => write!(f, "{path}:{line}:{column}: {message}"),
+            SettingsErrorKind::UnknownKey(key) => write!(f, "{path}: unknown setting {key:?}"),
+            SettingsErrorKind::InvalidValue { key, message } => {
+                write!(f, "{path}: invalid value for {key:?}: {message}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for SettingsError {}
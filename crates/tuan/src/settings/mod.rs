@@ -0,0 +1,5 @@
+mod error;
+mod settings;
+
+pub use error::*;
+pub use settings::*;
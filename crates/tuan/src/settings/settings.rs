@@ -0,0 +1,173 @@
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, de::DeserializeOwned};
+use serde_json::Value;
+
+use super::{SettingsError, SettingsErrorKind};
+use crate::globals;
+
+/// The editor settings, as read from `settings.json` files.
+///
+/// Every field is optional so that layers can be merged: an unset value falls
+/// back to the layer below, and eventually to the `EditorConfig` defaults.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Settings {
+    /// `editor.fontSize`, in logical pixels.
+    pub font_size: Option<f32>,
+    /// `editor.lineHeight`, as a multiple of the font size.
+    pub line_height: Option<f32>,
+    /// `editor.theme`, the path to a VS Code color theme.
+    /// Relative paths are resolved against the directory of the settings file.
+    pub theme: Option<PathBuf>,
+    /// `editor.lineNumbers`, how the gutter numbers the lines.
+    pub line_numbers: Option<LineNumbers>,
+}
+
+/// How the gutter numbers the lines.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LineNumbers {
+    #[default]
+    On,
+    /// The distance to the line of the cursor, which shows its own number.
+    Relative,
+    Off,
+}
+
+impl Settings {
+    pub fn user_settings_path() -> PathBuf {
+        PathBuf::from(globals::CONFIG_DIR.as_str()).join("settings.json")
+    }
+
+    pub fn workspace_settings_path(workspace_path: &Path) -> PathBuf {
+        workspace_path.join(".tuan").join("settings.json")
+    }
+
+    /// Loads the user settings, then the workspace settings on top of them.
+    ///
+    /// A layer containing errors is skipped entirely, and its errors are returned.
+    pub fn load(workspace_path: &Path) -> (Self, Vec<SettingsError>) {
+        let mut settings = Self::default();
+        let mut errors = Vec::new();
+
+        for path in [
+            Self::user_settings_path(),
+            Self::workspace_settings_path(workspace_path),
+        ] {
+            match Self::from_path(&path) {
+                Ok(layer) => settings = settings.merge(layer),
+                Err(layer_errors) => errors.extend(layer_errors),
+            }
+        }
+
+        (settings, errors)
+    }
+
+    /// Reads a single settings layer. A missing file is an empty layer.
+    pub fn from_path(path: &Path) -> Result<Self, Vec<SettingsError>> {
+        match std::fs::read_to_string(path) {
+            Ok(content) => Self::from_json(path, &content),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(err) => Err(vec![SettingsError::new(
+                path,
+                SettingsErrorKind::Io(err.to_string()),
+            )]),
+        }
+    }
+
+    /// Parses a single settings layer, reporting every unknown key and invalid value.
+    pub fn from_json(path: &Path, json: &str) -> Result<Self, Vec<SettingsError>> {
+        if json.trim().is_empty() {
+            return Ok(Self::default());
+        }
+
+        let map = match serde_json::from_str::<Value>(json) {
+            Ok(Value::Object(map)) => map,
+            Ok(_) => {
+                return Err(vec![SettingsError::new(
+                    path,
+                    SettingsErrorKind::Parse {
+                        line: 1,
+                        column: 1,
+                        message: "expected a JSON object".to_string(),
+                    },
+                )]);
+            }
+            Err(err) => {
+                return Err(vec![SettingsError::new(
+                    path,
+                    SettingsErrorKind::Parse {
+                        line: err.line(),
+                        column: err.column(),
+                        message: err.to_string(),
+                    },
+                )]);
+            }
+        };
+
+        let mut settings = Self::default();
+        let mut errors = Vec::new();
+
+        for (key, value) in map {
+            let result = match key.as_str() {
+                "editor.fontSize" => {
+                    parse_positive(path, &key, value).map(|v| settings.font_size = Some(v))
+                }
+                "editor.lineHeight" => {
+                    parse_positive(path, &key, value).map(|v| settings.line_height = Some(v))
+                }
+                "editor.theme" => parse_value::<PathBuf>(path, &key, value).map(|theme| {
+                    let base = path.parent().unwrap_or(Path::new(""));
+                    settings.theme = Some(base.join(theme));
+                }),
+                "editor.lineNumbers" => parse_value::<LineNumbers>(path, &key, value)
+                    .map(|v| settings.line_numbers = Some(v)),
+                _ => Err(SettingsError::new(
+                    path,
+                    SettingsErrorKind::UnknownKey(key.clone()),
+                )),
+            };
+            if let Err(err) = result {
+                errors.push(err);
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(settings)
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// Returns `self` with every value set in `other` overriding it.
+    pub fn merge(self, other: Self) -> Self {
+        Self {
+            font_size: other.font_size.or(self.font_size),
+            line_height: other.line_height.or(self.line_height),
+            theme: other.theme.or(self.theme),
+            line_numbers: other.line_numbers.or(self.line_numbers),
+        }
+    }
+}
+
+fn parse_value<T: DeserializeOwned>(
+    path: &Path,
+    key: &str,
+    value: Value,
+) -> Result<T, SettingsError> {
+    serde_json::from_value(value)
+        .map_err(|err| SettingsError::invalid_value(path, key, err.to_string()))
+}
+
+fn parse_positive(path: &Path, key: &str, value: Value) -> Result<f32, SettingsError> {
+    let v = parse_value::<f32>(path, key, value)?;
+    if v.is_finite() && v > 0.0 {
+        Ok(v)
+    } else {
+        Err(SettingsError::invalid_value(
+            path,
+            key,
+            format!("expected a positive number, got {v}"),
+        ))
+    }
+}
@@ -14,3 +14,14 @@ impl Theme {
         Self::Vscode(theme)
     }
 }
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::from_vscode_theme(
+            vscode_theme::VscodeTheme::from_json(include_str!(
+                "../../assets/rose-pine-moon-color-theme.json"
+            ))
+            .expect("The bundled theme should be valid"),
+        )
+    }
+}
@@ -10,3 +10,19 @@ pub struct Style {
     pub underline: bool,
     pub strikethrough: bool,
 }
+
+impl Style {
+    /// Bold and underlined, over the style of the text: how the parts of a
+    /// text matching what the user looks for are shown.
+    pub fn match_highlight() -> Self {
+        Self {
+            color: None,
+            foreground: None,
+            background: None,
+            italic: false,
+            bold: true,
+            underline: true,
+            strikethrough: false,
+        }
+    }
+}
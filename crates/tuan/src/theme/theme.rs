@@ -2,4 +2,9 @@ use super::Style;
 
 pub trait Theme: Clone + std::fmt::Debug {
   fn get_style(&self, tokens: Vec<&str>) -> Option<Style>;
+
+  /// Resolves the style of each single scope, in order.
+  fn get_styles(&self, scopes: &[&str]) -> Vec<Option<Style>> {
+    scopes.iter().map(|scope| self.get_style(vec![*scope])).collect()
+  }
 }
@@ -1,6 +1,7 @@
 use hex_color::HexColor;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::sync::{Arc, OnceLock, RwLock};
 use std::{cmp::Ordering, path::PathBuf};
 use xilem::Color;
 
@@ -14,11 +15,25 @@ pub struct VscodeTheme {
     color_scheme: ColorScheme,
     colors: HashMap<String, String>,
     token_colors: Vec<TokenColor>,
+    /// Shared by the clones of the theme, which never changes once loaded.
+    #[serde(skip)]
+    resolver: Arc<Resolver>,
+}
+
+/// The compiled selectors of a theme, and the styles already resolved with them.
+#[derive(Debug, Default)]
+struct Resolver {
+    /// Each selector of `token_colors`, in order, with the index of its rule.
+    selectors: OnceLock<Vec<(Selector, usize)>>,
+    /// Resolved styles by scope stack, its scopes joined by spaces.
+    styles: RwLock<HashMap<String, Option<Style>>>,
 }
 
 impl VscodeTheme {
     pub fn from_json(json: &str) -> Result<Self, Box<dyn std::error::Error>> {
-        Ok(serde_json::from_str(json)?)
+        let theme: Self = serde_json::from_str(json)?;
+        theme.selectors();
+        Ok(theme)
     }
 
     pub fn from_path(path: PathBuf) -> Result<Self, Box<dyn std::error::Error>> {
@@ -83,16 +98,16 @@ where
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 struct Selector {
     include: Sequence,
     excludes: Vec<Sequence>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 struct Sequence(Vec<SimpleSelector>);
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 struct SimpleSelector {
     segments: Vec<String>,
     is_wildcard: bool,
@@ -259,25 +274,33 @@ fn to_style(foreground: Option<Color>, background: Option<Color>, font_mask: Opt
     }
 }
 
-impl Theme for VscodeTheme {
-    fn get_style(&self, tokens: Vec<&str>) -> Option<Style> {
-        if tokens.is_empty() {
-            return None;
-        }
+impl VscodeTheme {
+    fn selectors(&self) -> &[(Selector, usize)] {
+        self.resolver.selectors.get_or_init(|| {
+            self.token_colors
+                .iter()
+                .enumerate()
+                .flat_map(|(rule, token_color)| {
+                    token_color
+                        .scope
+                        .iter()
+                        .map(move |raw_selector| (Selector::parse(raw_selector), rule))
+                })
+                .collect()
+        })
+    }
+
+    /// Resolves a non-empty scope stack, without the cache.
+    fn resolve(&self, tokens: &[&str]) -> Option<Style> {
         let mut matches: Vec<(u32, usize, &Settings)> = Vec::new();
-        let mut order = 0usize;
-        for rule in &self.token_colors {
-            for raw_selector in &rule.scope {
-                let sel = Selector::parse(raw_selector);
-                if let Some(spec) = sel.matches(&tokens) {
-                    matches.push((spec, order, &rule.settings));
-                }
-                order += 1;
+        for (order, (selector, rule)) in self.selectors().iter().enumerate() {
+            if let Some(spec) = selector.matches(tokens) {
+                matches.push((spec, order, &self.token_colors[*rule].settings));
             }
         }
         if matches.is_empty() {
             for token in tokens {
-                if let Some(color) = self.colors.get(token) {
+                if let Some(color) = self.colors.get(*token) {
                     let color = HexColor::parse(color)
                         .ok()
                         .map(|c| Color::from_rgba8(c.r, c.g, c.b, c.a));
@@ -335,3 +358,46 @@ impl Theme for VscodeTheme {
         ))
     }
 }
+
+impl Theme for VscodeTheme {
+    fn get_style(&self, tokens: Vec<&str>) -> Option<Style> {
+        if tokens.is_empty() {
+            return None;
+        }
+        let key = tokens.join(" ");
+        if let Some(style) = self.resolver.styles.read().unwrap().get(&key) {
+            return style.clone();
+        }
+
+        let style = self.resolve(&tokens);
+        self.resolver
+            .styles
+            .write()
+            .unwrap()
+            .insert(key, style.clone());
+        style
+    }
+
+    fn get_styles(&self, scopes: &[&str]) -> Vec<Option<Style>> {
+        let mut styles = {
+            let cache = self.resolver.styles.read().unwrap();
+            scopes
+                .iter()
+                .map(|scope| cache.get(*scope).cloned())
+                .collect::<Vec<_>>()
+        };
+
+        let mut cache = None;
+        for (scope, style) in scopes.iter().zip(&mut styles) {
+            if style.is_none() {
+                let resolved = self.resolve(&[*scope]);
+                cache
+                    .get_or_insert_with(|| self.resolver.styles.write().unwrap())
+                    .insert(scope.to_string(), resolved.clone());
+                *style = Some(resolved);
+            }
+        }
+
+        styles.into_iter().map(Option::flatten).collect()
+    }
+}